@@ -0,0 +1,37 @@
+//! Benchmarks encoding and signing `track.scrobble` batches
+//!
+//! Usage:
+//!   cargo bench --features bench --bench scrobble_batch
+
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use last_fm_rs::Scrobble;
+
+fn batch(len: u64) -> Vec<Scrobble> {
+  (0..len)
+    .map(|i| {
+      Scrobble::new("Sigur Rós", format!("Track {}", i), 1_700_000_000 + i)
+        .with_album("Ágætis byrjun")
+        .with_track_number(i as u32 + 1)
+        .with_duration(Duration::from_secs(240))
+        .with_album_artist("Sigur Rós")
+    })
+    .collect()
+}
+
+fn encode_batch(c: &mut Criterion) {
+  let mut group = c.benchmark_group("encode_batch");
+  for len in [1, 10, 50] {
+    let scrobbles = batch(len);
+    group.bench_with_input(
+      BenchmarkId::from_parameter(len),
+      &scrobbles,
+      |b, scrobbles| b.iter(|| last_fm_rs::encode_batch(scrobbles, "key", "session", "secret")),
+    );
+  }
+  group.finish();
+}
+
+criterion_group!(benches, encode_batch);
+criterion_main!(benches);
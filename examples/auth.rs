@@ -1,7 +1,9 @@
-/// Example: Desktop authentication flow
-///
-/// Usage:
-///   cargo run --example auth -- YOUR_API_KEY YOUR_API_SECRET
+//! Example: Desktop authentication flow
+//!
+//! Usage:
+//!   cargo run --example auth -- YOUR_API_KEY YOUR_API_SECRET
+
+use std::time::Duration;
 
 use last_fm_rs::Client;
 
@@ -30,14 +32,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
   let auth_url = client.get_auth_url(&token)?;
   println!("Step 2: Please authorize this application:");
   println!("  {}\n", auth_url);
-  println!("Press Enter after you've authorized...");
-
-  let mut input = String::new();
-  std::io::stdin().read_line(&mut input)?;
-
-  // Step 3: Exchange token for session key
-  println!("Step 3: Exchanging token for session key...");
-  let session = client.get_session(&token).await?;
+  // Step 3: Exchange token for session key once the user approves
+  println!("Step 3: Waiting for authorization...");
+  let session = client
+    .wait_for_session(&token, Duration::from_secs(3), Duration::from_secs(300))
+    .await?;
   println!("✓ Authentication successful!\n");
 
   println!("Session details:");
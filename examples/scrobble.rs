@@ -1,10 +1,10 @@
-/// Example: Scrobbling tracks
-///
-/// Usage:
-///   cargo run --example scrobble -- YOUR_API_KEY YOUR_SECRET YOUR_SESSION_KEY
+//! Example: Scrobbling tracks
+//!
+//! Usage:
+//!   cargo run --example scrobble -- YOUR_API_KEY YOUR_SECRET YOUR_SESSION_KEY
 
 use last_fm_rs::{Client, NowPlaying, Scrobble};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -29,7 +29,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
   let now_playing = NowPlaying::new("Kendrick Lamar", "Wesley's Theory")
     .with_album("To Pimp a Butterfly")
     .with_track_number(1)
-    .with_duration(287);
+    .with_duration(Duration::from_secs(287));
 
   client.update_now_playing(&now_playing).await?;
   println!("✓ Now Playing updated\n");
@@ -40,12 +40,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
   // Submit scrobble
   println!("Submitting scrobble...");
-  let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
-
-  let scrobble = Scrobble::new("Kendrick Lamar", "Wesley's Theory", timestamp)
-    .with_album("To Pimp a Butterfly")
-    .with_track_number(1)
-    .with_duration(287);
+  let scrobble = Scrobble::builder("Kendrick Lamar", "Wesley's Theory")
+    .timestamp(SystemTime::now())
+    .album("To Pimp a Butterfly")
+    .track_number(1)
+    .duration(Duration::from_secs(287))
+    .build()?;
 
   let response = client.scrobble(&[scrobble]).await?;
 
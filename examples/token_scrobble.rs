@@ -1,13 +1,13 @@
-/// Example: Token-based scrobbling to custom server
-///
-/// This example shows how to use the token-based authentication mode
-/// to scrobble to your own self-hosted scrobbling server.
-///
-/// Usage:
-///   cargo run --example token_scrobble -- BASE_URL TOKEN
+//! Example: Token-based scrobbling to custom server
+//!
+//! This example shows how to use the token-based authentication mode
+//! to scrobble to your own self-hosted scrobbling server.
+//!
+//! Usage:
+//!   cargo run --example token_scrobble -- BASE_URL TOKEN
 
 use last_fm_rs::{Client, NowPlaying, Scrobble};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -16,7 +16,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
   if args.len() != 3 {
     eprintln!("Usage: {} BASE_URL TOKEN", args[0]);
     eprintln!("\nExample:");
-    eprintln!("  {} https://scrob.example.com/api/ my-secret-token", args[0]);
+    eprintln!(
+      "  {} https://scrob.example.com/api/ my-secret-token",
+      args[0]
+    );
     std::process::exit(1);
   }
 
@@ -34,7 +37,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
   println!("Updating Now Playing...");
   let now_playing = NowPlaying::new("Kendrick Lamar", "Wesley's Theory")
     .with_album("To Pimp a Butterfly")
-    .with_duration(287)
+    .with_duration(Duration::from_secs(287))
     .with_track_number(1);
 
   client.update_now_playing(&now_playing).await?;
@@ -42,14 +45,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
   // Submit a scrobble
   println!("Submitting scrobble...");
-  let timestamp = SystemTime::now()
-    .duration_since(UNIX_EPOCH)?
-    .as_secs();
+  let now = SystemTime::now();
 
-  let scrobble = Scrobble::new("Kendrick Lamar", "Wesley's Theory", timestamp)
-    .with_album("To Pimp a Butterfly")
-    .with_duration(287)
-    .with_track_number(1);
+  let scrobble = Scrobble::builder("Kendrick Lamar", "Wesley's Theory")
+    .timestamp(now)
+    .album("To Pimp a Butterfly")
+    .duration(Duration::from_secs(287))
+    .track_number(1)
+    .build()?;
 
   let response = client.scrobble(&[scrobble]).await?;
   println!("✓ Scrobble submitted successfully!");
@@ -58,16 +61,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
   // Submit multiple scrobbles (batch)
   println!("Submitting batch scrobbles...");
-  let timestamp2 = timestamp - 300;
-  let timestamp3 = timestamp - 600;
-
   let scrobbles = vec![
-    Scrobble::new("Pink Floyd", "Time", timestamp2)
-      .with_album("The Dark Side of the Moon")
-      .with_track_number(4),
-    Scrobble::new("Pink Floyd", "The Great Gig in the Sky", timestamp3)
-      .with_album("The Dark Side of the Moon")
-      .with_track_number(5),
+    Scrobble::builder("Pink Floyd", "Time")
+      .timestamp(now - Duration::from_secs(300))
+      .album("The Dark Side of the Moon")
+      .track_number(4)
+      .build()?,
+    Scrobble::builder("Pink Floyd", "The Great Gig in the Sky")
+      .timestamp(now - Duration::from_secs(600))
+      .album("The Dark Side of the Moon")
+      .track_number(5)
+      .build()?,
   ];
 
   let response = client.scrobble(&scrobbles).await?;
@@ -17,17 +17,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
   println!("Track: {}", track.name);
   println!("Artist: {}", track.artist.name);
-  println!("URL: {}", track.url);
+  if let Some(url) = &track.url {
+    println!("URL: {}", url);
+  }
   println!("Listeners: {}", track.listeners);
   println!("Playcount: {}", track.playcount);
 
   if let Some(duration) = track.duration {
-    println!("Duration: {}s", duration / 1000);
+    println!("Duration: {}s", duration.as_secs());
   }
 
   if let Some(album) = &track.album {
     println!("\nAlbum: {} by {}", album.title, album.artist);
-    if let Some(image) = album.image.last() {
+    if let Some(image) = album.largest_image() {
       println!("Cover art: {}", image.url);
     }
   }
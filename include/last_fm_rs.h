@@ -0,0 +1,56 @@
+/*
+ * C bindings for last-fm-rs, built with the `ffi` feature:
+ *
+ *   cargo rustc --release --features ffi --crate-type staticlib
+ *
+ * Calls block until the request finishes. Strings are NUL-terminated UTF-8.
+ */
+
+#ifndef LAST_FM_RS_H
+#define LAST_FM_RS_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum LastfmStatus {
+  LASTFM_OK = 0,
+  LASTFM_INVALID_ARGUMENT = -1,
+  LASTFM_AUTH = -2,
+  LASTFM_RATE_LIMITED = -3,
+  LASTFM_API = -4,
+  LASTFM_NETWORK = -5,
+  LASTFM_OTHER = -6,
+} LastfmStatus;
+
+typedef struct LastfmClient LastfmClient;
+
+/* Returns NULL on failure; session_key may be NULL */
+LastfmClient *lastfm_client_new(const char *api_key, const char *api_secret,
+                                const char *session_key);
+
+/* Client for a token-authenticated scrobble server; NULL on failure */
+LastfmClient *lastfm_client_new_token(const char *base_url, const char *token);
+
+void lastfm_client_free(LastfmClient *client);
+
+/* album may be NULL; duration_secs is 0 if unknown */
+LastfmStatus lastfm_now_playing(const LastfmClient *client, const char *artist,
+                                const char *track, const char *album,
+                                uint32_t duration_secs);
+
+/* timestamp is when the track started, in unix seconds */
+LastfmStatus lastfm_scrobble(const LastfmClient *client, const char *artist,
+                             const char *track, const char *album,
+                             uint64_t timestamp, uint32_t duration_secs);
+
+/* Last failure on this thread, or NULL; valid until the next lastfm_ call */
+const char *lastfm_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* LAST_FM_RS_H */
@@ -1,40 +1,47 @@
 use serde::{Deserialize, Serialize};
 
 /// Authentication token (valid for 60 minutes)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct AuthToken {
   pub token: String,
 }
 
 /// Session key (infinite lifetime until revoked)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct SessionKey {
   pub key: String,
   pub name: String,
 }
 
+#[cfg(feature = "auth-flow")]
 #[derive(Debug, Deserialize)]
-struct TokenResponse {
+pub(crate) struct TokenResponse {
   token: String,
 }
 
+#[cfg(feature = "auth-flow")]
 #[derive(Debug, Deserialize)]
-struct SessionResponse {
+pub(crate) struct SessionResponse {
   session: SessionData,
 }
 
+#[cfg(feature = "auth-flow")]
 #[derive(Debug, Deserialize)]
 struct SessionData {
   name: String,
   key: String,
 }
 
+#[cfg(feature = "auth-flow")]
 impl From<TokenResponse> for AuthToken {
   fn from(resp: TokenResponse) -> Self {
     AuthToken { token: resp.token }
   }
 }
 
+#[cfg(feature = "auth-flow")]
 impl From<SessionResponse> for SessionKey {
   fn from(resp: SessionResponse) -> Self {
     SessionKey {
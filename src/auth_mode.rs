@@ -1,63 +1,95 @@
+use std::fmt;
+use std::sync::{RwLock, RwLockReadGuard};
+
 use url::Url;
 
+/// Which kind of credentials a [`Client`](crate::Client) was created with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum AuthKind {
+  /// Last.fm API key and secret, plus a session key once logged in
+  LastFm,
+  /// Bearer token for a custom scrobble server
+  Token,
+}
+
 /// Authentication mode for the client
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub(crate) enum AuthMode {
-  /// Last.fm API authentication with API key, secret, and optional session key
+  /// Last.fm API authentication with API key and secret; the session key
+  /// lives in the client's [`Session`]
   LastFm {
     api_key: String,
-    api_secret: String,
-    session_key: Option<String>,
+    /// `None` for key-only clients, which can't sign requests
+    #[cfg_attr(
+      not(any(feature = "scrobble", feature = "auth-flow")),
+      allow(dead_code)
+    )]
+    api_secret: Option<String>,
   },
   /// Token-based authentication for custom scrobble servers
   Token {
     base_url: Url,
+    #[cfg_attr(not(feature = "scrobble"), allow(dead_code))]
     token: String,
   },
 }
 
+/// Shows the API key and server, never the secret or bearer token
+impl fmt::Debug for AuthMode {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::LastFm {
+        api_key,
+        api_secret,
+      } => f
+        .debug_struct("LastFm")
+        .field("api_key", api_key)
+        .field("api_secret", &api_secret.as_ref().map(|_| "<redacted>"))
+        .finish(),
+      Self::Token { base_url, .. } => f
+        .debug_struct("Token")
+        .field("base_url", &base_url.as_str())
+        .field("token", &"<redacted>")
+        .finish(),
+    }
+  }
+}
+
 impl AuthMode {
   /// Create a new Last.fm auth mode
-  pub fn lastfm(
-    api_key: impl Into<String>,
-    api_secret: impl Into<String>,
-  ) -> Self {
+  pub fn lastfm(api_key: impl Into<String>, api_secret: impl Into<String>) -> Self {
     Self::LastFm {
       api_key: api_key.into(),
-      api_secret: api_secret.into(),
-      session_key: None,
+      api_secret: Some(api_secret.into()),
+    }
+  }
+
+  /// Create a Last.fm auth mode without a secret, for unsigned calls only
+  pub fn public(api_key: impl Into<String>) -> Self {
+    Self::LastFm {
+      api_key: api_key.into(),
+      api_secret: None,
     }
   }
 
   /// Create a new token-based auth mode
-  pub fn token(
-    base_url: Url,
-    token: impl Into<String>,
-  ) -> Self {
+  pub fn token(base_url: Url, token: impl Into<String>) -> Self {
     Self::Token {
       base_url,
       token: token.into(),
     }
   }
 
-  /// Set session key for Last.fm mode (no-op for Token mode)
-  pub fn set_session_key(&mut self, key: impl Into<String>) {
-    if let Self::LastFm { session_key, .. } = self {
-      *session_key = Some(key.into());
-    }
-  }
-
-  /// Get session key for Last.fm mode (None for Token mode)
-  #[cfg(test)]
-  pub(crate) fn session_key(&self) -> Option<&str> {
+  /// Public view of which mode this is
+  pub fn kind(&self) -> AuthKind {
     match self {
-      Self::LastFm { session_key, .. } => session_key.as_deref(),
-      Self::Token { .. } => None,
+      Self::LastFm { .. } => AuthKind::LastFm,
+      Self::Token { .. } => AuthKind::Token,
     }
   }
 
   /// Get API key (Last.fm mode only)
-  #[cfg(test)]
   pub(crate) fn api_key(&self) -> Option<&str> {
     match self {
       Self::LastFm { api_key, .. } => Some(api_key),
@@ -69,7 +101,7 @@ impl AuthMode {
   #[cfg(test)]
   pub(crate) fn api_secret(&self) -> Option<&str> {
     match self {
-      Self::LastFm { api_secret, .. } => Some(api_secret),
+      Self::LastFm { api_secret, .. } => api_secret.as_deref(),
       Self::Token { .. } => None,
     }
   }
@@ -86,3 +118,50 @@ impl AuthMode {
     matches!(self, Self::Token { .. })
   }
 }
+
+/// Last.fm session key, plus the name of its user when known
+#[derive(Debug, Clone)]
+pub(crate) struct SessionState {
+  #[cfg_attr(not(feature = "scrobble"), allow(dead_code))]
+  pub key: String,
+  pub username: Option<String>,
+}
+
+/// Session shared by a client and its clones, replaceable after
+/// construction
+///
+/// Cloning copies the current session rather than sharing it, so a client
+/// detached by a `with_*` method keeps its own.
+#[derive(Debug, Default)]
+pub(crate) struct Session(RwLock<Option<SessionState>>);
+
+impl Session {
+  pub fn get(&self) -> Option<SessionState> {
+    self.read().clone()
+  }
+
+  pub fn is_set(&self) -> bool {
+    self.read().is_some()
+  }
+
+  pub fn set(&self, state: Option<SessionState>) {
+    // A panic can't leave the session half-written, so ignore poisoning
+    *self
+      .0
+      .write()
+      .unwrap_or_else(|poisoned| poisoned.into_inner()) = state;
+  }
+
+  fn read(&self) -> RwLockReadGuard<'_, Option<SessionState>> {
+    self
+      .0
+      .read()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+}
+
+impl Clone for Session {
+  fn clone(&self) -> Self {
+    Self(RwLock::new(self.get()))
+  }
+}
@@ -0,0 +1,474 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::auth_mode::{AuthMode, Session};
+use crate::client::{Client, ClientInner};
+#[cfg(feature = "disk-cache")]
+use crate::disk_cache::DiskCache;
+use crate::error::{Error, Result};
+use crate::hooks::Hooks;
+use crate::retry::{ExponentialBackoff, RetryPolicy};
+use crate::signature::Md5Signer;
+#[cfg(feature = "metadata")]
+use crate::{
+  cache::TrackInfoCache,
+  coalesce::Coalescer,
+  key_pool::{KeyPool, KeySelection},
+};
+
+/// Identifies this crate when the application hasn't named itself
+const DEFAULT_USER_AGENT: &str = concat!(
+  "last-fm-rs/",
+  env!("CARGO_PKG_VERSION"),
+  " (+https://github.com/ducks/last-fm-rs)"
+);
+
+/// Last.fm's API endpoint
+const API_BASE: &str = "https://ws.audioscrobbler.com/2.0/";
+
+/// Page where users authorize an application's token
+const AUTH_URL: &str = "https://www.last.fm/api/auth/";
+
+/// Header naming the application to token-mode scrobble servers
+const CLIENT_ID_HEADER: &str = "x-client-id";
+
+/// Application identity sent with every request
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AppInfo {
+  name: String,
+  version: String,
+  contact: String,
+}
+
+impl AppInfo {
+  /// `name/version`
+  fn id(&self) -> String {
+    format!("{}/{}", self.name, self.version)
+  }
+
+  /// `name/version (contact) last-fm-rs/x.y.z`
+  fn user_agent(&self) -> String {
+    format!(
+      "{} ({}) last-fm-rs/{}",
+      self.id(),
+      self.contact,
+      env!("CARGO_PKG_VERSION")
+    )
+  }
+}
+
+/// Named set of URLs for a Last.fm-compatible service, for switching a
+/// client between production, staging and mock servers in one place
+///
+/// # Example
+///
+/// ```no_run
+/// use last_fm_rs::{Client, Endpoint};
+///
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let staging = Endpoint::new(
+///   "staging",
+///   "https://staging.example.com/2.0/",
+///   "https://staging.example.com/auth/",
+/// )
+/// .danger_accept_invalid_certs(true);
+/// let client = Client::builder("api_key", "secret").endpoint(staging).build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoint {
+  name: String,
+  api_base: String,
+  auth_url: String,
+  accept_invalid_certs: bool,
+}
+
+impl Endpoint {
+  /// Profile called `name` with the API endpoint and the page users
+  /// authorize tokens on
+  pub fn new(
+    name: impl Into<String>,
+    api_base: impl Into<String>,
+    auth_url: impl Into<String>,
+  ) -> Self {
+    Self {
+      name: name.into(),
+      api_base: api_base.into(),
+      auth_url: auth_url.into(),
+      accept_invalid_certs: false,
+    }
+  }
+
+  /// Last.fm itself, the default
+  pub fn lastfm() -> Self {
+    Self::new("last.fm", API_BASE, AUTH_URL)
+  }
+
+  /// libre.fm's Last.fm-compatible API
+  pub fn librefm() -> Self {
+    Self::new(
+      "libre.fm",
+      "https://libre.fm/2.0/",
+      "https://libre.fm/api/auth/",
+    )
+  }
+
+  /// Accept self-signed, expired or otherwise invalid TLS certificates
+  ///
+  /// Only for test and staging servers: anyone on the network path can
+  /// read and alter the traffic, API secret and session keys included.
+  /// Has no effect in builds without a TLS backend.
+  pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+    self.accept_invalid_certs = accept;
+    self
+  }
+
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn api_base(&self) -> &str {
+    &self.api_base
+  }
+
+  pub fn auth_url(&self) -> &str {
+    &self.auth_url
+  }
+}
+
+impl Default for Endpoint {
+  fn default() -> Self {
+    Self::lastfm()
+  }
+}
+
+/// Builder for a [`Client`] with custom HTTP settings
+///
+/// # Example
+///
+/// ```no_run
+/// use last_fm_rs::Client;
+///
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = Client::builder("api_key", "secret")
+///   .app_info("my-scrobbler", "1.2.0", "https://example.com/my-scrobbler")
+///   .timeout(std::time::Duration::from_secs(10))
+///   .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ClientBuilder {
+  auth: AuthMode,
+  #[cfg(feature = "compression")]
+  gzip: bool,
+  #[cfg(feature = "compression")]
+  brotli: bool,
+  timeout: Option<Duration>,
+  deadline: Option<Duration>,
+  app_info: Option<AppInfo>,
+  api_base: Option<String>,
+  auth_url: Option<String>,
+  accept_invalid_certs: bool,
+  #[cfg(feature = "metadata")]
+  extra_api_keys: Vec<String>,
+  #[cfg(feature = "metadata")]
+  key_selection: KeySelection,
+  #[cfg(feature = "metadata")]
+  track_info_cache: usize,
+  #[cfg(feature = "disk-cache")]
+  disk_cache: Option<Arc<DiskCache>>,
+  retry_policy: Arc<dyn RetryPolicy>,
+}
+
+impl std::fmt::Debug for ClientBuilder {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let mut debug = f.debug_struct("ClientBuilder");
+    debug.field("auth", &self.auth);
+    #[cfg(feature = "compression")]
+    debug
+      .field("gzip", &self.gzip)
+      .field("brotli", &self.brotli);
+    debug
+      .field("timeout", &self.timeout)
+      .field("deadline", &self.deadline)
+      .field("app_info", &self.app_info)
+      .field("api_base", &self.api_base)
+      .field("auth_url", &self.auth_url)
+      .field("accept_invalid_certs", &self.accept_invalid_certs);
+    #[cfg(feature = "metadata")]
+    debug
+      .field("extra_api_keys", &self.extra_api_keys)
+      .field("key_selection", &self.key_selection)
+      .field("track_info_cache", &self.track_info_cache);
+    #[cfg(feature = "disk-cache")]
+    debug.field("disk_cache", &self.disk_cache);
+    debug.finish_non_exhaustive()
+  }
+}
+
+impl ClientBuilder {
+  pub(crate) fn new(auth: AuthMode) -> Self {
+    Self {
+      auth,
+      #[cfg(feature = "compression")]
+      gzip: true,
+      #[cfg(feature = "compression")]
+      brotli: true,
+      timeout: None,
+      deadline: None,
+      app_info: None,
+      api_base: None,
+      auth_url: None,
+      accept_invalid_certs: false,
+      #[cfg(feature = "metadata")]
+      extra_api_keys: Vec::new(),
+      #[cfg(feature = "metadata")]
+      key_selection: KeySelection::default(),
+      #[cfg(feature = "metadata")]
+      track_info_cache: 0,
+      #[cfg(feature = "disk-cache")]
+      disk_cache: None,
+      retry_policy: Arc::new(ExponentialBackoff::default()),
+    }
+  }
+
+  /// Decide when failed requests are retried
+  ///
+  /// Defaults to [`ExponentialBackoff`]; pass [`NoRetry`](crate::NoRetry)
+  /// for strict latency budgets, or a more patient policy for bulk imports.
+  pub fn retry_policy(mut self, policy: impl RetryPolicy + 'static) -> Self {
+    self.retry_policy = Arc::new(policy);
+    self
+  }
+
+  /// Remember up to `capacity` [`track_get_info`](Client::track_get_info)
+  /// results, dropping the least recently used first
+  ///
+  /// Views that resolve the same tracks over and over then only ask Last.fm
+  /// once per track. The cache is shared by every clone of the client,
+  /// including ones with a different session or language; lookups for
+  /// another username or language are cached separately. Only successful
+  /// lookups are kept. Off by default.
+  ///
+  /// # Example
+  ///
+  /// ```no_run
+  /// use last_fm_rs::Client;
+  ///
+  /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+  /// let client = Client::builder("api_key", "secret")
+  ///   .track_info_cache(1000)
+  ///   .build()?;
+  /// # Ok(())
+  /// # }
+  /// ```
+  #[cfg(feature = "metadata")]
+  pub fn track_info_cache(mut self, capacity: usize) -> Self {
+    self.track_info_cache = capacity;
+    self
+  }
+
+  /// Keep [`track_get_info`](Client::track_get_info) results in `cache`
+  /// between runs
+  ///
+  /// Checked after the in-memory [`track_info_cache`](Self::track_info_cache)
+  /// and shared by every clone of the client. Ignored in token mode.
+  #[cfg(feature = "disk-cache")]
+  pub fn disk_cache(mut self, cache: DiskCache) -> Self {
+    self.disk_cache = Some(Arc::new(cache));
+    self
+  }
+
+  /// Share read-only traffic across additional API keys
+  ///
+  /// Unsigned lookups (track info, search, recent and loved tracks) pick a
+  /// key from the primary key plus `api_keys` according to `selection`, and
+  /// fall through to the next key when one is rate limited, invalid or
+  /// suspended. Signed calls (auth, scrobbling, loving) always use the
+  /// primary key and secret, since session keys belong to the API key that
+  /// issued them. Ignored in token mode.
+  ///
+  /// # Example
+  ///
+  /// ```no_run
+  /// use last_fm_rs::{Client, KeySelection};
+  ///
+  /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+  /// let client = Client::builder("primary_key", "secret")
+  ///   .api_key_pool(["second_key", "third_key"], KeySelection::RoundRobin)
+  ///   .build()?;
+  /// # Ok(())
+  /// # }
+  /// ```
+  #[cfg(feature = "metadata")]
+  pub fn api_key_pool<I>(mut self, api_keys: I, selection: KeySelection) -> Self
+  where
+    I: IntoIterator,
+    I::Item: Into<String>,
+  {
+    self.extra_api_keys = api_keys.into_iter().map(Into::into).collect();
+    self.key_selection = selection;
+    self
+  }
+
+  /// Identify the application, as Last.fm's API terms require
+  ///
+  /// Sends `User-Agent: name/version (contact) last-fm-rs/x.y.z`, where
+  /// `contact` is a URL or email address. In token mode the server also
+  /// receives `X-Client-Id: name/version`. Without this, requests identify
+  /// only this crate.
+  pub fn app_info(
+    mut self,
+    name: impl Into<String>,
+    version: impl Into<String>,
+    contact: impl Into<String>,
+  ) -> Self {
+    self.app_info = Some(AppInfo {
+      name: name.into(),
+      version: version.into(),
+      contact: contact.into(),
+    });
+    self
+  }
+
+  /// Send Last.fm-mode API calls to another endpoint
+  ///
+  /// Defaults to `https://ws.audioscrobbler.com/2.0/`. Useful for services
+  /// that implement the Last.fm API, such as libre.fm, and for test servers.
+  /// Pair it with [`auth_url`](Self::auth_url). Ignored in token mode.
+  ///
+  /// # Example
+  ///
+  /// ```no_run
+  /// use last_fm_rs::Client;
+  ///
+  /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+  /// let client = Client::builder("api_key", "secret")
+  ///   .api_base("https://libre.fm/2.0/")
+  ///   .auth_url("https://libre.fm/api/auth/")
+  ///   .build()?;
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub fn api_base(mut self, url: impl Into<String>) -> Self {
+    self.api_base = Some(url.into());
+    self
+  }
+
+  /// Page users are sent to by [`Client::get_auth_url`]
+  ///
+  /// Defaults to `https://www.last.fm/api/auth/`.
+  pub fn auth_url(mut self, url: impl Into<String>) -> Self {
+    self.auth_url = Some(url.into());
+    self
+  }
+
+  /// Use the URLs and TLS settings of `endpoint`
+  ///
+  /// Replaces earlier [`api_base`](Self::api_base) and
+  /// [`auth_url`](Self::auth_url) calls. The URLs are ignored in token mode;
+  /// the TLS setting applies in both modes.
+  pub fn endpoint(mut self, endpoint: Endpoint) -> Self {
+    self.api_base = Some(endpoint.api_base);
+    self.auth_url = Some(endpoint.auth_url);
+    self.accept_invalid_certs = endpoint.accept_invalid_certs;
+    self
+  }
+
+  /// Accept gzip-compressed responses (enabled by default)
+  #[cfg(feature = "compression")]
+  pub fn gzip(mut self, enable: bool) -> Self {
+    self.gzip = enable;
+    self
+  }
+
+  /// Accept brotli-compressed responses (enabled by default)
+  #[cfg(feature = "compression")]
+  pub fn brotli(mut self, enable: bool) -> Self {
+    self.brotli = enable;
+    self
+  }
+
+  /// Default timeout for every request (no timeout unless set)
+  ///
+  /// Individual calls can override it with [`Client::with_timeout`].
+  pub fn timeout(mut self, timeout: Duration) -> Self {
+    self.timeout = Some(timeout);
+    self
+  }
+
+  /// Default cap on each call's total time, retries and backoff included
+  ///
+  /// Individual calls can override it with [`Client::with_deadline`].
+  pub fn deadline(mut self, deadline: Duration) -> Self {
+    self.deadline = Some(deadline);
+    self
+  }
+
+  /// Build the client
+  ///
+  /// Fails if the HTTP client (e.g. its TLS backend) cannot be initialized,
+  /// if the [`app_info`](Self::app_info) can't be sent as a header, or if
+  /// a configured URL doesn't parse.
+  pub fn build(self) -> Result<Client> {
+    let api_base = url::Url::parse(self.api_base.as_deref().unwrap_or(API_BASE))?;
+    let auth_url = url::Url::parse(self.auth_url.as_deref().unwrap_or(AUTH_URL))?;
+
+    let mut headers = reqwest::header::HeaderMap::new();
+    let user_agent = match &self.app_info {
+      Some(app_info) => app_info.user_agent(),
+      None => DEFAULT_USER_AGENT.to_string(),
+    };
+    headers.insert(reqwest::header::USER_AGENT, header_value(user_agent)?);
+    if let (Some(app_info), AuthMode::Token { .. }) = (&self.app_info, &self.auth) {
+      headers.insert(CLIENT_ID_HEADER, header_value(app_info.id())?);
+    }
+
+    let http_client = reqwest::Client::builder().default_headers(headers);
+    #[cfg(any(feature = "native-tls", feature = "rustls"))]
+    let http_client = http_client.danger_accept_invalid_certs(self.accept_invalid_certs);
+    #[cfg(feature = "compression")]
+    let http_client = http_client.gzip(self.gzip).brotli(self.brotli);
+    let http_client = http_client.build()?;
+
+    #[cfg(feature = "metadata")]
+    let key_pool = match &self.auth {
+      AuthMode::LastFm { api_key, .. } if !self.extra_api_keys.is_empty() => Some(Arc::new(
+        KeyPool::new(api_key, self.extra_api_keys, self.key_selection),
+      )),
+      _ => None,
+    };
+
+    Ok(Client::from_inner(ClientInner {
+      auth: self.auth,
+      session: Session::default(),
+      api_base,
+      auth_url,
+      http_client,
+      #[cfg(feature = "metadata")]
+      lang: None,
+      signer: Arc::new(Md5Signer),
+      timeout: self.timeout,
+      deadline: self.deadline,
+      #[cfg(feature = "metadata")]
+      track_info_requests: Arc::new(Coalescer::new()),
+      #[cfg(feature = "metadata")]
+      track_info_cache: (self.track_info_cache > 0)
+        .then(|| TrackInfoCache::new(self.track_info_cache)),
+      #[cfg(feature = "disk-cache")]
+      disk_cache: self.disk_cache,
+      #[cfg(feature = "metadata")]
+      key_pool,
+      retry_policy: self.retry_policy,
+      hooks: Hooks::default(),
+    }))
+  }
+}
+
+fn header_value(value: String) -> Result<reqwest::header::HeaderValue> {
+  reqwest::header::HeaderValue::try_from(value).map_err(|_| {
+    Error::InvalidParameter("app_info contains characters not allowed in headers".to_string())
+  })
+}
@@ -0,0 +1,242 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::client::TrackInfoKey;
+use crate::track::TrackInfo;
+
+/// Snapshot of a cache's size and effectiveness
+///
+/// `hits` and `misses` count lookups since the cache was created; clearing
+/// the cache doesn't reset them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+  pub hits: u64,
+  pub misses: u64,
+  /// Entries currently stored
+  pub len: usize,
+  /// Most entries the cache keeps
+  pub capacity: usize,
+}
+
+impl CacheStats {
+  /// Share of lookups answered from the cache, 0.0 before the first one
+  pub fn hit_rate(&self) -> f64 {
+    let lookups = self.hits + self.misses;
+    if lookups == 0 {
+      0.0
+    } else {
+      self.hits as f64 / lookups as f64
+    }
+  }
+}
+
+/// In-memory cache of [`Client::track_get_info`](crate::Client::track_get_info)
+/// results, set up with
+/// [`ClientBuilder::track_info_cache`](crate::ClientBuilder::track_info_cache)
+///
+/// Every clone of the client shares it, so clearing or invalidating through
+/// one affects them all.
+#[derive(Clone)]
+pub struct TrackInfoCache(Arc<LruCache<TrackInfoKey, TrackInfo>>);
+
+impl TrackInfoCache {
+  pub(crate) fn new(capacity: usize) -> Self {
+    Self(Arc::new(LruCache::new(capacity)))
+  }
+
+  pub(crate) fn get(&self, key: &TrackInfoKey) -> Option<TrackInfo> {
+    self.0.get(key)
+  }
+
+  pub(crate) fn insert(&self, key: TrackInfoKey, info: TrackInfo) {
+    self.0.insert(key, info)
+  }
+
+  /// Drop every entry
+  pub fn clear(&self) {
+    self.0.retain(|_| false);
+  }
+
+  /// Drop the entries for a track, for every username and language it was
+  /// looked up with, so the next lookup asks Last.fm again
+  ///
+  /// Returns how many entries were dropped. Artist and track names must
+  /// match the ones looked up exactly.
+  pub fn invalidate(&self, artist: &str, track: &str) -> usize {
+    self.0.retain(|key| !key.is_track(artist, track))
+  }
+
+  pub fn stats(&self) -> CacheStats {
+    self.0.stats()
+  }
+}
+
+impl fmt::Debug for TrackInfoCache {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_tuple("TrackInfoCache")
+      .field(&self.stats())
+      .finish()
+  }
+}
+
+/// Map that holds at most `capacity` entries, evicting the least recently
+/// used one to make room
+pub(crate) struct LruCache<K, V> {
+  capacity: usize,
+  entries: Mutex<Entries<K, V>>,
+  hits: AtomicU64,
+  misses: AtomicU64,
+}
+
+struct Entries<K, V> {
+  /// Value and the tick of its last use
+  map: HashMap<K, (V, u64)>,
+  /// Keys by last use, oldest first
+  recency: BTreeMap<u64, K>,
+  tick: u64,
+}
+
+impl<K, V> Entries<K, V>
+where
+  K: Eq + Hash + Clone,
+{
+  fn next_tick(&mut self) -> u64 {
+    self.tick += 1;
+    self.tick
+  }
+}
+
+impl<K, V> LruCache<K, V>
+where
+  K: Eq + Hash + Clone,
+  V: Clone,
+{
+  pub fn new(capacity: usize) -> Self {
+    Self {
+      capacity,
+      entries: Mutex::new(Entries {
+        map: HashMap::new(),
+        recency: BTreeMap::new(),
+        tick: 0,
+      }),
+      hits: AtomicU64::new(0),
+      misses: AtomicU64::new(0),
+    }
+  }
+
+  /// Clone of the value for `key`, marking it as recently used
+  pub fn get(&self, key: &K) -> Option<V> {
+    let mut entries = self.lock();
+    let tick = entries.next_tick();
+    let Some((value, used)) = entries.map.get_mut(key) else {
+      self.misses.fetch_add(1, Ordering::Relaxed);
+      return None;
+    };
+    self.hits.fetch_add(1, Ordering::Relaxed);
+    let value = value.clone();
+    let previous = std::mem::replace(used, tick);
+    entries.recency.remove(&previous);
+    entries.recency.insert(tick, key.clone());
+    Some(value)
+  }
+
+  /// Store `value`, evicting the least recently used entry if full
+  pub fn insert(&self, key: K, value: V) {
+    if self.capacity == 0 {
+      return;
+    }
+    let mut entries = self.lock();
+    let tick = entries.next_tick();
+    if let Some((_, previous)) = entries.map.insert(key.clone(), (value, tick)) {
+      entries.recency.remove(&previous);
+    }
+    entries.recency.insert(tick, key);
+    while entries.map.len() > self.capacity {
+      let Some((_, oldest)) = entries.recency.pop_first() else {
+        break;
+      };
+      entries.map.remove(&oldest);
+    }
+  }
+
+  /// Keep only the entries whose key matches `keep`; returns how many were
+  /// dropped
+  pub fn retain(&self, mut keep: impl FnMut(&K) -> bool) -> usize {
+    let mut entries = self.lock();
+    let Entries { map, recency, .. } = &mut *entries;
+    let before = map.len();
+    map.retain(|key, (_, used)| {
+      let kept = keep(key);
+      if !kept {
+        recency.remove(used);
+      }
+      kept
+    });
+    before - map.len()
+  }
+
+  pub fn stats(&self) -> CacheStats {
+    CacheStats {
+      hits: self.hits.load(Ordering::Relaxed),
+      misses: self.misses.load(Ordering::Relaxed),
+      len: self.lock().map.len(),
+      capacity: self.capacity,
+    }
+  }
+
+  fn lock(&self) -> std::sync::MutexGuard<'_, Entries<K, V>> {
+    // Entries are only touched in short non-panicking sections
+    self
+      .entries
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_least_recently_used_is_evicted() {
+    let cache = LruCache::new(2);
+    cache.insert("a", 1);
+    cache.insert("b", 2);
+    assert_eq!(cache.get(&"a"), Some(1));
+
+    cache.insert("c", 3);
+    assert_eq!(cache.get(&"b"), None);
+    assert_eq!(cache.get(&"a"), Some(1));
+    assert_eq!(cache.get(&"c"), Some(3));
+
+    cache.insert("a", 4);
+    cache.insert("d", 5);
+    assert_eq!(cache.get(&"c"), None);
+    assert_eq!(cache.get(&"a"), Some(4));
+
+    let disabled = LruCache::new(0);
+    disabled.insert("a", 1);
+    assert_eq!(disabled.get(&"a"), None);
+  }
+
+  #[test]
+  fn test_retain_and_stats() {
+    let cache = LruCache::new(10);
+    for (key, value) in [("a", 1), ("b", 2), ("c", 3)] {
+      cache.insert(key, value);
+    }
+    assert_eq!(cache.retain(|key| *key != "b"), 1);
+    assert_eq!(cache.get(&"b"), None);
+    assert_eq!(cache.get(&"a"), Some(1));
+
+    let stats = cache.stats();
+    assert_eq!(
+      (stats.hits, stats.misses, stats.len, stats.capacity),
+      (1, 1, 2, 10)
+    );
+    assert_eq!(stats.hit_rate(), 0.5);
+  }
+}
@@ -1,35 +1,676 @@
-use std::collections::BTreeMap;
+#[cfg(feature = "scrobble")]
+use std::borrow::Borrow;
+#[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+use std::future::Future;
+#[cfg(feature = "metadata")]
+use std::io::Write;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
 
-use crate::auth::{AuthToken, SessionKey};
-use crate::auth_mode::AuthMode;
+#[cfg(feature = "metadata")]
+use futures::{Stream, StreamExt};
+
+use crate::auth::SessionKey;
+#[cfg(feature = "auth-flow")]
+use crate::auth::{AuthToken, SessionResponse, TokenResponse};
+use crate::auth_mode::{AuthKind, AuthMode, Session, SessionState};
+use crate::builder::ClientBuilder;
+#[cfg(feature = "auth-flow")]
+use crate::error::UNAUTHORIZED_TOKEN;
 use crate::error::{Error, Result};
-use crate::scrobble::{NowPlaying, Scrobble, ScrobbleResponse};
-use crate::signature;
+use crate::hooks::{Hooks, ResponseInfo, SignatureInfo};
+#[cfg(all(feature = "metadata", feature = "scrobble"))]
+use crate::loved::{self, SyncOptions, SyncReport};
+#[cfg(feature = "scrobble")]
+use crate::mbid::Mbid;
+#[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+use crate::params::Params;
+#[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+use crate::response;
+#[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+use crate::retry;
+use crate::retry::RetryPolicy;
+#[cfg(feature = "scrobble")]
+use crate::scrobble::{NowPlaying, Scrobble, ScrobbleOutcome, ScrobbleResponse};
+use crate::signature::Signer;
+#[cfg(feature = "scrobble")]
+use crate::state::Authorized;
+use crate::state::{Authenticated, ClientState, Dynamic, Unauthenticated};
+#[cfg(feature = "metadata")]
+use crate::{
+  cache::TrackInfoCache,
+  coalesce::Coalescer,
+  export::{ExportFormat, ExportRecord, Exporter},
+  hydrate::{self, Pacer},
+  key_pool::KeyPool,
+  loved::{LovedTrack, LovedTracksResponse},
+  recent::{self, RecentTrack, RecentTracksOptions, RecentTracksResponse},
+  response::ResponseParts,
+  search::{SearchPage, TrackMatch, TrackSearchResponse},
+  track::{Cover, Image, TrackInfo, TrackInfoResponse},
+  watch,
+};
+
+#[cfg(feature = "disk-cache")]
+use crate::disk_cache::{self, DiskCache};
+
+#[cfg(feature = "scrobble")]
+const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded";
+
+/// Cap on the pause between `wait_for_session` polls, as a multiple of the
+/// interval it started with
+#[cfg(feature = "auth-flow")]
+const MAX_SESSION_POLL: u32 = 4;
 
-const API_BASE: &str = "https://ws.audioscrobbler.com/2.0/";
-const AUTH_URL: &str = "http://www.last.fm/api/auth/";
+/// Secret for signing a request, or an auth error for key-only clients
+#[cfg(any(feature = "scrobble", feature = "auth-flow"))]
+fn require_secret(api_secret: &Option<String>) -> Result<&str> {
+  api_secret
+    .as_deref()
+    .ok_or_else(|| Error::auth("This call must be signed; create the client with an API secret"))
+}
 
 /// Last.fm API client
-pub struct Client {
-  auth: AuthMode,
-  http_client: reqwest::Client,
+///
+/// Cloning is cheap: clones share configuration and one HTTP connection
+/// pool, so a single client can be handed to any number of tasks.
+///
+/// # Cancellation
+///
+/// Every method returns a future that can be dropped at any point, e.g. via
+/// `tokio::select!` or `tokio::time::timeout`, to cancel it. The in-flight
+/// HTTP request is aborted and no retries are scheduled. A cancelled
+/// [`track_get_info`](Self::track_get_info) that other callers were sharing
+/// is taken over by one of them. Dropping an
+/// [`export_history`](Self::export_history) future stops it after the
+/// records already written; buffered writers flush those on drop.
+///
+/// # Authentication state
+///
+/// `Client` is short for `Client<Dynamic>`, which checks for a session key
+/// when a call needs one. For compile-time checking, start from
+/// [`into_unauthenticated`](Self::into_unauthenticated) and move to a
+/// `Client<Authenticated>` with [`login`](Self::login) or
+/// [`authenticate`](Self::authenticate):
+///
+/// ```no_run
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::time::Duration;
+/// use last_fm_rs::{Client, Scrobble};
+///
+/// let client = Client::new("api_key", "secret").into_unauthenticated();
+/// let token = client.get_token().await?;
+/// println!("Authorize at {}", client.get_auth_url(&token)?);
+/// let session = client
+///   .wait_for_session(&token, Duration::from_secs(3), Duration::from_secs(300))
+///   .await?;
+///
+/// let client = client.login(session);
+/// client.scrobble([Scrobble::now("Cher", "Believe")]).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Scrobbling before logging in doesn't compile:
+///
+/// ```compile_fail
+/// # async fn example() {
+/// use last_fm_rs::{Client, Scrobble};
+///
+/// let client = Client::new("api_key", "secret").into_unauthenticated();
+/// client.scrobble([Scrobble::now("Cher", "Believe")]).await;
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Client<S = Dynamic> {
+  inner: Arc<ClientInner>,
+  state: PhantomData<S>,
+}
+
+#[derive(Clone)]
+pub(crate) struct ClientInner {
+  pub(crate) auth: AuthMode,
+  /// Session key set at or after construction, shared with clones
+  pub(crate) session: Session,
+  /// Last.fm-mode API endpoint
+  pub(crate) api_base: url::Url,
+  /// Page where users authorize a token
+  #[cfg_attr(not(feature = "auth-flow"), allow(dead_code))]
+  pub(crate) auth_url: url::Url,
+  #[cfg_attr(
+    not(any(feature = "scrobble", feature = "auth-flow", feature = "metadata")),
+    allow(dead_code)
+  )]
+  pub(crate) http_client: reqwest::Client,
+  #[cfg(feature = "metadata")]
+  pub(crate) lang: Option<String>,
+  pub(crate) signer: Arc<dyn Signer>,
+  pub(crate) timeout: Option<Duration>,
+  pub(crate) deadline: Option<Duration>,
+  #[cfg(feature = "metadata")]
+  pub(crate) track_info_requests: Arc<Coalescer<TrackInfoKey, Result<TrackInfo>>>,
+  /// Shared with every clone, even detached ones
+  #[cfg(feature = "metadata")]
+  pub(crate) track_info_cache: Option<TrackInfoCache>,
+  #[cfg(feature = "disk-cache")]
+  pub(crate) disk_cache: Option<Arc<DiskCache>>,
+  #[cfg(feature = "metadata")]
+  pub(crate) key_pool: Option<Arc<KeyPool>>,
+  pub(crate) retry_policy: Arc<dyn RetryPolicy>,
+  pub(crate) hooks: Hooks,
+}
+
+/// Identity of a track.getInfo request, for coalescing and caching lookups
+#[cfg(feature = "metadata")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct TrackInfoKey {
+  artist: String,
+  track: String,
+  username: Option<String>,
+  lang: Option<String>,
+}
+
+#[cfg(feature = "metadata")]
+impl TrackInfoKey {
+  pub(crate) fn is_track(&self, artist: &str, track: &str) -> bool {
+    self.artist == artist && self.track == track
+  }
+
+  /// Key for the entry in a [`DiskCache`]
+  #[cfg(feature = "disk-cache")]
+  fn to_bytes(&self) -> Vec<u8> {
+    disk_cache::entry_key(
+      &self.artist,
+      &self.track,
+      self.username.as_deref(),
+      self.lang.as_deref(),
+    )
+  }
 }
 
 impl Client {
   /// Create a new Last.fm client
+  ///
+  /// # Panics
+  ///
+  /// Panics if the HTTP client cannot be initialized, like
+  /// `reqwest::Client::new`. Use [`Client::builder`] to handle that error.
   pub fn new(api_key: impl Into<String>, secret: impl Into<String>) -> Self {
+    Self::builder(api_key, secret)
+      .build()
+      .expect("failed to initialize HTTP client")
+  }
+
+  /// Start building a Last.fm client with custom HTTP settings
+  pub fn builder(api_key: impl Into<String>, secret: impl Into<String>) -> ClientBuilder {
+    ClientBuilder::new(AuthMode::lastfm(api_key, secret))
+  }
+
+  /// Create a Last.fm client with only an API key
+  ///
+  /// Enough for the unsigned read-only lookups (track info, search, recent
+  /// and loved tracks), so apps that never scrobble needn't ship a secret.
+  /// Authentication, now playing, scrobbling and loving tracks fail with
+  /// [`Error::Auth`].
+  ///
+  /// # Panics
+  ///
+  /// As for [`Client::new`]; use [`Client::public_builder`] to handle the
+  /// error.
+  pub fn new_public(api_key: impl Into<String>) -> Self {
+    Self::public_builder(api_key)
+      .build()
+      .expect("failed to initialize HTTP client")
+  }
+
+  /// Start building a key-only client; see [`Client::new_public`]
+  pub fn public_builder(api_key: impl Into<String>) -> ClientBuilder {
+    ClientBuilder::new(AuthMode::public(api_key))
+  }
+
+  /// Start building a token-mode client with custom HTTP settings
+  pub fn token_builder(
+    base_url: impl AsRef<str>,
+    token: impl Into<String>,
+  ) -> Result<ClientBuilder> {
+    let url = url::Url::parse(base_url.as_ref())?;
+    Ok(ClientBuilder::new(AuthMode::token(url, token)))
+  }
+
+  pub(crate) fn from_inner(inner: ClientInner) -> Self {
     Self {
-      auth: AuthMode::lastfm(api_key, secret),
-      http_client: reqwest::Client::new(),
+      inner: Arc::new(inner),
+      state: PhantomData,
     }
   }
+}
+
+impl<S: ClientState> Client<S> {
+  /// This client with its session checked at call time again; see
+  /// [`Dynamic`]
+  pub fn into_dynamic(self) -> Client {
+    self.with_state::<Dynamic>()
+  }
+
+  /// This client without the methods that need a session key
+  pub fn into_unauthenticated(self) -> Client<Unauthenticated> {
+    self.with_state::<Unauthenticated>()
+  }
+
+  /// Log in with a session from [`get_session`](Self::get_session) or
+  /// [`wait_for_session`](Self::wait_for_session)
+  pub fn login(self, session: SessionKey) -> Client<Authenticated> {
+    self.with_session(session).with_state::<Authenticated>()
+  }
+
+  /// Switch to [`Authenticated`] using the session key already set
+  ///
+  /// Fails with [`Error::Auth`] in Last.fm mode when there is none. Token
+  /// mode clients always succeed.
+  pub fn authenticate(self) -> Result<Client<Authenticated>> {
+    if !self.is_authenticated() {
+      return Err(Error::auth("Session key required"));
+    }
+    Ok(self.with_state::<Authenticated>())
+  }
+
+  /// Change state, taking a copy of the session so handles in other states
+  /// can't log this one in or out
+  fn with_state<T: ClientState>(mut self) -> Client<T> {
+    self.inner_mut();
+    Client {
+      inner: self.inner,
+      state: PhantomData,
+    }
+  }
+
+  /// Untyped handle on the same configuration, for the internal helpers
+  #[cfg(feature = "metadata")]
+  fn to_dynamic(&self) -> Client {
+    Client {
+      inner: self.inner.clone(),
+      state: PhantomData,
+    }
+  }
+
+  /// Mutable access to this client's configuration, detaching it from clones
+  fn inner_mut(&mut self) -> &mut ClientInner {
+    Arc::make_mut(&mut self.inner)
+  }
 
   /// Set session key for authenticated requests
+  ///
+  /// Forgets any username set by [`with_session`](Self::with_session).
   pub fn with_session_key(mut self, session_key: impl Into<String>) -> Self {
-    self.auth.set_session_key(session_key);
+    self.inner_mut().session = Session::default();
+    self.store_session(session_key.into(), None);
+    self
+  }
+
+  /// Log in with a session from [`get_session`](Self::get_session),
+  /// remembering the user's name for [`username`](Self::username)
+  pub fn with_session(mut self, session: SessionKey) -> Self {
+    self.inner_mut().session = Session::default();
+    self.store_session(session.key, Some(session.name));
+    self
+  }
+
+  fn store_session(&self, key: String, username: Option<String>) {
+    if let AuthMode::LastFm { .. } = self.inner.auth {
+      self.inner.session.set(Some(SessionState { key, username }));
+    }
+  }
+
+  /// Which kind of credentials this client uses
+  pub fn auth_mode(&self) -> AuthKind {
+    self.inner.auth.kind()
+  }
+
+  /// Whether the client can make calls on behalf of a user
+  ///
+  /// True in Last.fm mode once a session key is set, and always in token
+  /// mode. The credentials aren't checked with the server.
+  pub fn is_authenticated(&self) -> bool {
+    match self.inner.auth {
+      AuthMode::LastFm { .. } => self.has_session_key(),
+      AuthMode::Token { .. } => true,
+    }
+  }
+
+  /// Name of the logged-in user, if the client was given one through
+  /// [`with_session`](Self::with_session) or
+  /// [`set_session`](Self::set_session)
+  pub fn username(&self) -> Option<String> {
+    self
+      .inner
+      .session
+      .get()
+      .and_then(|session| session.username)
+  }
+
+  /// Whether a Last.fm session key is set; always false in token mode
+  pub fn has_session_key(&self) -> bool {
+    self.inner.session.is_set()
+  }
+
+  /// Last.fm API key, `None` in token mode
+  ///
+  /// The key is public by design (it's sent in the query of read-only
+  /// calls); the secret and session key have no getters.
+  pub fn api_key(&self) -> Option<&str> {
+    self.inner.auth.api_key()
+  }
+
+  /// URL requests are sent to: the API endpoint in Last.fm mode, the
+  /// server's base URL in token mode
+  pub fn base_url(&self) -> &url::Url {
+    match &self.inner.auth {
+      AuthMode::LastFm { .. } => &self.inner.api_base,
+      AuthMode::Token { base_url, .. } => base_url,
+    }
+  }
+
+  #[cfg(feature = "metadata")]
+  /// In-memory track info cache, if the client was built with one
+  ///
+  /// # Example
+  ///
+  /// ```no_run
+  /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+  /// use last_fm_rs::Client;
+  ///
+  /// let client = Client::builder("api_key", "secret").track_info_cache(1000).build()?;
+  /// let track = client.track_get_info("Cher", "Believe", None).await?;
+  ///
+  /// // After editing the track's tags on Last.fm
+  /// if let Some(cache) = client.track_info_cache() {
+  ///   cache.invalidate("Cher", "Believe");
+  ///   println!("{:.0}% of lookups were cached", cache.stats().hit_rate() * 100.0);
+  /// }
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub fn track_info_cache(&self) -> Option<&TrackInfoCache> {
+    self.inner.track_info_cache.as_ref()
+  }
+
+  #[cfg(feature = "disk-cache")]
+  /// Disk cache for track info, if the client was built with one
+  pub fn disk_cache(&self) -> Option<&DiskCache> {
+    self.inner.disk_cache.as_deref()
+  }
+
+  #[cfg(feature = "metadata")]
+  /// Request localized content from info methods
+  ///
+  /// Sent as the `lang` parameter (ISO 639 alpha-2 code, e.g. `"de"`) on
+  /// `*.getInfo` calls so wiki summaries come back in that language when
+  /// Last.fm has a translation.
+  pub fn with_lang(mut self, lang: impl Into<String>) -> Self {
+    self.inner_mut().lang = Some(lang.into());
+    self
+  }
+
+  /// Replace the retry policy (defaults to [`ExponentialBackoff`](crate::ExponentialBackoff))
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use last_fm_rs::{Client, NoRetry};
+  ///
+  /// // Interactive code that would rather show an error than wait
+  /// let client = Client::new("api_key", "secret").with_retry_policy(NoRetry);
+  /// ```
+  pub fn with_retry_policy(mut self, policy: impl RetryPolicy + 'static) -> Self {
+    self.inner_mut().retry_policy = Arc::new(policy);
+    self
+  }
+
+  /// Replace the request signing scheme (defaults to Last.fm's MD5 scheme)
+  ///
+  /// Useful for servers that implement the Last.fm protocol but sign requests
+  /// differently, e.g. with SHA-256 or an HMAC.
+  pub fn with_signer(mut self, signer: impl Signer + 'static) -> Self {
+    self.inner_mut().signer = Arc::new(signer);
+    self
+  }
+
+  /// Override the request timeout for calls made through this client
+  ///
+  /// Clones share the connection pool, so a short-lived override is cheap:
+  ///
+  /// ```no_run
+  /// # async fn example(client: &last_fm_rs::Client) -> last_fm_rs::Result<()> {
+  /// use std::time::Duration;
+  /// use last_fm_rs::NowPlaying;
+  ///
+  /// client
+  ///   .clone()
+  ///   .with_timeout(Duration::from_secs(2))
+  ///   .update_now_playing(&NowPlaying::new("Artist", "Track"))
+  ///   .await?;
+  /// # Ok(())
+  /// # }
+  /// ```
+  ///
+  /// Requests that exceed it fail with [`Error::Timeout`].
+  pub fn with_timeout(mut self, timeout: Duration) -> Self {
+    self.inner_mut().timeout = Some(timeout);
+    self
+  }
+
+  /// Cap how long each call may take in total, retries and backoff
+  /// included
+  ///
+  /// Calls still running when it passes fail with
+  /// [`Error::DeadlineExceeded`]. Unlike [`with_timeout`](Self::with_timeout),
+  /// which bounds each HTTP request, this bounds what the caller waits.
+  pub fn with_deadline(mut self, deadline: Duration) -> Self {
+    self.inner_mut().deadline = Some(deadline);
+    self
+  }
+
+  /// Run `hook` on every HTTP request before it is sent
+  ///
+  /// Hooks may add headers or rewrite the request; returning an error fails
+  /// the call with that error without sending anything, which is handy for
+  /// injecting faults in tests. Hooks run in the order they were added, and
+  /// again on every retry.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use last_fm_rs::Client;
+  ///
+  /// let client = Client::new("api_key", "secret").on_request(|request| {
+  ///   request
+  ///     .headers_mut()
+  ///     .insert("x-request-source", "nightly-sync".parse().unwrap());
+  ///   Ok(())
+  /// });
+  /// ```
+  pub fn on_request(
+    mut self,
+    hook: impl Fn(&mut reqwest::Request) -> Result<()> + Send + Sync + 'static,
+  ) -> Self {
+    self.inner_mut().hooks.add_request(Arc::new(hook));
+    self
+  }
+
+  /// Run `hook` after every HTTP exchange, including failed ones
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use last_fm_rs::Client;
+  ///
+  /// let client = Client::new("api_key", "secret").on_response(|info| {
+  ///   println!("{} {} -> {:?} in {:?}", info.method, info.url.path(), info.status, info.elapsed);
+  /// });
+  /// ```
+  pub fn on_response(mut self, hook: impl Fn(&ResponseInfo<'_>) + Send + Sync + 'static) -> Self {
+    self.inner_mut().hooks.add_response(Arc::new(hook));
+    self
+  }
+
+  /// Run `hook` with the signing details of every signed call
+  ///
+  /// For diagnosing "Invalid method signature" (error 13) responses: the
+  /// hook sees the canonical message, its [masked](SignatureInfo::masked)
+  /// form and the resulting signature before the request is sent. Costs
+  /// nothing when no hook is registered.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use last_fm_rs::Client;
+  ///
+  /// let client = Client::new("api_key", "secret").on_signature(|info| {
+  ///   eprintln!("{}: {} -> {}", info.method, info.masked, info.signature);
+  /// });
+  /// ```
+  pub fn on_signature(mut self, hook: impl Fn(&SignatureInfo<'_>) + Send + Sync + 'static) -> Self {
+    self.inner_mut().hooks.add_signature(Arc::new(hook));
+    self
+  }
+
+  /// Sign `params` with the client's signer, telling signature hooks
+  ///
+  /// The signature is computed once, so hooks see exactly the `api_sig`
+  /// that is sent.
+  #[cfg(any(feature = "scrobble", feature = "auth-flow"))]
+  fn sign(&self, params: &mut Params<'_>, secret: &str) {
+    let message = params.signing_message();
+    let signature = self.inner.signer.sign(&message, secret);
+    self.inner.hooks.signed(params, &message, &signature);
+    params.push("api_sig", signature);
+  }
+
+  /// Start a GET request with the client's per-request settings applied
+  #[cfg(any(feature = "auth-flow", feature = "metadata"))]
+  fn get(&self, url: impl reqwest::IntoUrl) -> reqwest::RequestBuilder {
+    self.apply_settings(self.inner.http_client.get(url))
+  }
+
+  /// Start a POST request with the client's per-request settings applied
+  #[cfg(feature = "scrobble")]
+  fn post(&self, url: impl reqwest::IntoUrl) -> reqwest::RequestBuilder {
+    self.apply_settings(self.inner.http_client.post(url))
+  }
+
+  /// Send a request through the client's hooks
+  #[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+  async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
     self
+      .inner
+      .hooks
+      .execute(&self.inner.http_client, request.build()?)
+      .await
+  }
+
+  /// Run a request under the client's retry policy, naming the call in any
+  /// error it returns
+  #[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+  async fn retrying<T, F, Fut>(&self, params: &Params<'_>, call: F) -> Result<T>
+  where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+  {
+    let method = params.method();
+    #[cfg(feature = "log")]
+    log::debug!("{}: sending", method);
+    let run = retry::run(self.inner.retry_policy.as_ref(), method, call);
+    let result = match self.inner.deadline {
+      Some(deadline) => tokio::time::timeout(deadline, run)
+        .await
+        .unwrap_or(Err(Error::DeadlineExceeded)),
+      None => run.await,
+    };
+    let result = result.map_err(|error| error.with_context(params));
+    #[cfg(feature = "log")]
+    match &result {
+      Ok(_) => log::debug!("{}: ok", method),
+      Err(error) => log::warn!("{} failed: {}", method, error.redacted()),
+    }
+    result
+  }
+
+  /// POST a signed form body to the API and return the response text
+  #[cfg(feature = "scrobble")]
+  async fn post_form(&self, body: &str) -> Result<String> {
+    let request = self
+      .post(self.inner.api_base.clone())
+      .header(reqwest::header::CONTENT_TYPE, FORM_CONTENT_TYPE)
+      .body(body.to_string());
+    response::text(self.send(request).await?).await
+  }
+
+  #[cfg(feature = "metadata")]
+  /// Run a read-only call with the primary API key, or across the key pool
+  async fn with_read_key<'a, T, F, Fut>(&'a self, api_key: &'a str, call: F) -> Result<T>
+  where
+    F: Fn(&'a str) -> Fut,
+    Fut: Future<Output = Result<T>>,
+  {
+    match &self.inner.key_pool {
+      Some(pool) => pool.run(call).await,
+      None => call(api_key).await,
+    }
+  }
+
+  #[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+  fn apply_settings(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    match self.inner.timeout {
+      Some(timeout) => request.timeout(timeout),
+      None => request,
+    }
+  }
+}
+
+impl Client {
+  /// Replace the session key on a running client
+  ///
+  /// Unlike [`with_session_key`](Self::with_session_key), this takes effect
+  /// for every clone sharing this client's configuration, from the next call
+  /// on. Lets a long-lived client finish authentication after startup.
+  /// Ignored in token mode.
+  ///
+  /// Typed clients took their own copy of the session when they were
+  /// created, so this doesn't reach them.
+  pub fn set_session_key(&self, session_key: impl Into<String>) {
+    self.store_session(session_key.into(), None);
+  }
+
+  /// [`set_session_key`](Self::set_session_key), also remembering the
+  /// user's name
+  pub fn set_session(&self, session: SessionKey) {
+    self.store_session(session.key, Some(session.name));
+  }
+
+  /// Forget the session key, e.g. on logout, for every clone sharing this
+  /// client's configuration
+  ///
+  /// [`Authenticated`] clients keep their own copy of the session; use
+  /// [`logout`](Client::logout) to end one.
+  pub fn clear_session(&self) {
+    self.inner.session.set(None);
+  }
+}
+
+impl Client<Authenticated> {
+  /// Forget the session key, leaving a client that can only make
+  /// unauthenticated calls
+  ///
+  /// Other [`Authenticated`] clones keep the key.
+  pub fn logout(self) -> Client<Unauthenticated> {
+    let client = self.with_state::<Unauthenticated>();
+    client.inner.session.set(None);
+    client
   }
+}
 
+impl Client {
   /// Create a client for token-based authentication with a custom server
   ///
   /// This mode bypasses Last.fm's authentication flow and instead uses:
@@ -50,180 +691,213 @@ impl Client {
   /// # Ok(())
   /// # }
   /// ```
-  pub fn with_token(
-    base_url: impl AsRef<str>,
-    token: impl Into<String>,
-  ) -> Result<Self> {
-    let url = url::Url::parse(base_url.as_ref())?;
-    Ok(Self {
-      auth: AuthMode::token(url, token),
-      http_client: reqwest::Client::new(),
-    })
+  pub fn with_token(base_url: impl AsRef<str>, token: impl Into<String>) -> Result<Self> {
+    Self::token_builder(base_url, token)?.build()
   }
+}
 
+#[cfg(feature = "auth-flow")]
+impl<S: ClientState> Client<S> {
   /// Step 1: Get authentication token (Last.fm mode only)
   pub async fn get_token(&self) -> Result<AuthToken> {
-    let (api_key, secret) = match &self.auth {
-      AuthMode::LastFm { api_key, api_secret, .. } => (api_key, api_secret),
+    let (api_key, secret) = match &self.inner.auth {
+      AuthMode::LastFm {
+        api_key,
+        api_secret,
+      } => (api_key, require_secret(api_secret)?),
       AuthMode::Token { .. } => {
-        return Err(Error::Auth(
-          "get_token() is only available in Last.fm mode".to_string()
-        ))
+        return Err(Error::auth("get_token() is only available in Last.fm mode"))
       }
     };
 
-    let mut params = BTreeMap::new();
-    params.insert("method".to_string(), "auth.getToken".to_string());
-    params.insert("api_key".to_string(), api_key.clone());
+    let mut params = Params::new("auth.getToken");
+    params.push("api_key", api_key);
+    self.sign(&mut params, secret);
+    let url = params.url(self.inner.api_base.as_str());
 
-    let sig = signature::generate(&params, secret);
-    params.insert("api_sig".to_string(), sig);
-    params.insert("format".to_string(), "json".to_string());
-
-    let resp = self
-      .http_client
-      .get(API_BASE)
-      .query(&params)
-      .send()
-      .await?
-      .error_for_status()?;
-
-    let json: serde_json::Value = resp.json().await?;
+    self
+      .retrying(&params, || async {
+        let resp = self.send(self.get(&url)).await?;
 
-    if let Some(token) = json.get("token") {
-      Ok(AuthToken {
-        token: token.as_str().unwrap().to_string(),
+        let token: TokenResponse = response::parse(&response::text(resp).await?)?;
+        Ok(token.into())
       })
-    } else if let Some(error) = json.get("error") {
-      Err(Error::Api(error.to_string()))
-    } else {
-      Err(Error::Api("Unexpected response format".to_string()))
-    }
+      .await
+      .map_err(|error| error.with_signed(&params))
   }
 
   /// Step 2: Generate authorization URL (Last.fm mode only)
   pub fn get_auth_url(&self, token: &AuthToken) -> Result<String> {
-    let api_key = match &self.auth {
+    let api_key = match &self.inner.auth {
       AuthMode::LastFm { api_key, .. } => api_key,
       AuthMode::Token { .. } => {
-        return Err(Error::Auth(
-          "get_auth_url() is only available in Last.fm mode".to_string()
+        return Err(Error::auth(
+          "get_auth_url() is only available in Last.fm mode",
         ))
       }
     };
 
-    Ok(format!("{}?api_key={}&token={}", AUTH_URL, api_key, token.token))
+    Ok(format!(
+      "{}?api_key={}&token={}",
+      self.inner.auth_url, api_key, token.token
+    ))
   }
 
   /// Step 3: Exchange token for session key (Last.fm mode only)
+  ///
+  /// Last.fm rejecting the token, for example because the user hasn't
+  /// authorized it yet (error 14), fails with [`Error::Auth`] carrying the
+  /// error code.
   pub async fn get_session(&self, token: &AuthToken) -> Result<SessionKey> {
-    let (api_key, secret) = match &self.auth {
-      AuthMode::LastFm { api_key, api_secret, .. } => (api_key, api_secret),
+    let (api_key, secret) = match &self.inner.auth {
+      AuthMode::LastFm {
+        api_key,
+        api_secret,
+      } => (api_key, require_secret(api_secret)?),
       AuthMode::Token { .. } => {
-        return Err(Error::Auth(
-          "get_session() is only available in Last.fm mode".to_string()
+        return Err(Error::auth(
+          "get_session() is only available in Last.fm mode",
         ))
       }
     };
 
-    let mut params = BTreeMap::new();
-    params.insert("method".to_string(), "auth.getSession".to_string());
-    params.insert("api_key".to_string(), api_key.clone());
-    params.insert("token".to_string(), token.token.clone());
+    let mut params = Params::new("auth.getSession");
+    params.push("api_key", api_key).push("token", &token.token);
+    self.sign(&mut params, secret);
+    let url = params.url(self.inner.api_base.as_str());
 
-    let sig = signature::generate(&params, secret);
-    params.insert("api_sig".to_string(), sig);
-    params.insert("format".to_string(), "json".to_string());
+    self
+      .retrying(&params, || async {
+        let resp = self.send(self.get(&url)).await?;
 
-    let resp = self
-      .http_client
-      .get(API_BASE)
-      .query(&params)
-      .send()
-      .await?
-      .error_for_status()?;
+        let session: SessionResponse = response::parse(&response::text(resp).await?)?;
+        Ok(session.into())
+      })
+      .await
+      .map_err(|error| error.with_signed(&params).into_auth())
+  }
 
-    let json: serde_json::Value = resp.json().await?;
+  /// Poll [`get_session`](Self::get_session) until the user authorizes
+  /// `token`
+  ///
+  /// Asks again while Last.fm reports the token as not yet authorized,
+  /// first after `interval` and then backing off, doubling the pause up to
+  /// four times `interval`. If the user hasn't approved the token within
+  /// `timeout`, returns the last "unauthorized token" [`Error::Auth`]
+  /// (code 14). Other errors, such as an expired token, are returned
+  /// straight away.
+  pub async fn wait_for_session(
+    &self,
+    token: &AuthToken,
+    interval: Duration,
+    timeout: Duration,
+  ) -> Result<SessionKey> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut pause = interval;
+    loop {
+      let error = match self.get_session(token).await {
+        Err(
+          error @ Error::Auth {
+            code: Some(UNAUTHORIZED_TOKEN),
+            ..
+          },
+        ) => error,
+        result => return result,
+      };
 
-    if let Some(session) = json.get("session") {
-      Ok(SessionKey {
-        key: session["key"].as_str().unwrap().to_string(),
-        name: session["name"].as_str().unwrap().to_string(),
-      })
-    } else if let Some(error) = json.get("error") {
-      Err(Error::Auth(error.to_string()))
-    } else {
-      Err(Error::Auth("Unexpected response format".to_string()))
+      let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+      if remaining.is_zero() {
+        return Err(error);
+      }
+      tokio::time::sleep(pause.min(remaining)).await;
+      pause = (pause * 2).min(interval * MAX_SESSION_POLL);
     }
   }
+}
+
+#[cfg(feature = "scrobble")]
+impl<S: Authorized> Client<S> {
+  /// Session key for signed calls, or an auth error when there is none
+  fn session_key(&self) -> Result<String> {
+    self
+      .inner
+      .session
+      .get()
+      .map(|session| session.key)
+      .ok_or_else(|| Error::auth("Session key required"))
+  }
 
   /// Update "Now Playing" status
   pub async fn update_now_playing(&self, now_playing: &NowPlaying) -> Result<()> {
-    match &self.auth {
-      AuthMode::LastFm { api_key, api_secret, session_key } => {
-        let sk = session_key
-          .as_ref()
-          .ok_or_else(|| Error::Auth("Session key required".to_string()))?;
-
-        let mut params = BTreeMap::new();
-        params.insert("method".to_string(), "track.updateNowPlaying".to_string());
-        params.insert("api_key".to_string(), api_key.clone());
-        params.insert("sk".to_string(), sk.clone());
-        params.insert("artist".to_string(), now_playing.artist.clone());
-        params.insert("track".to_string(), now_playing.track.clone());
-
-        if let Some(album) = &now_playing.album {
-          params.insert("album".to_string(), album.clone());
-        }
-        if let Some(track_number) = now_playing.track_number {
-          params.insert("trackNumber".to_string(), track_number.to_string());
-        }
-        if let Some(duration) = now_playing.duration {
-          params.insert("duration".to_string(), duration.to_string());
-        }
-        if let Some(album_artist) = &now_playing.album_artist {
-          params.insert("albumArtist".to_string(), album_artist.clone());
-        }
-
-        let sig = signature::generate(&params, api_secret);
-        params.insert("api_sig".to_string(), sig);
-        params.insert("format".to_string(), "json".to_string());
+    match &self.inner.auth {
+      AuthMode::LastFm {
+        api_key,
+        api_secret,
+      } => {
+        let sk = self.session_key()?;
 
-        let resp = self
-          .http_client
-          .post(API_BASE)
-          .form(&params)
-          .send()
-          .await?
-          .error_for_status()?;
+        let mut params = Params::new("track.updateNowPlaying");
+        params
+          .push("api_key", api_key)
+          .push("sk", sk)
+          .push("artist", &now_playing.artist)
+          .push("track", &now_playing.track)
+          .push_opt("album", now_playing.album.as_ref())
+          .push_opt("trackNumber", now_playing.track_number)
+          .push_opt("duration", now_playing.duration.map(|d| d.as_secs()))
+          .push_opt("albumArtist", now_playing.album_artist.as_ref())
+          .push_opt("mbid", now_playing.mbid.as_ref().map(Mbid::as_str))
+          .push_opt("context", now_playing.player.as_ref());
+        self.sign(&mut params, require_secret(api_secret)?);
+        let body = params.encode();
 
-        let json: serde_json::Value = resp.json().await?;
-
-        if json.get("error").is_some() {
-          Err(Error::Api(json["message"].as_str().unwrap().to_string()))
-        } else {
-          Ok(())
-        }
+        self
+          .retrying(&params, || async {
+            response::parse::<serde::de::IgnoredAny>(&self.post_form(&body).await?)?;
+            Ok(())
+          })
+          .await
+          .map_err(|error| error.with_signed(&params))
       }
       AuthMode::Token { base_url, token } => {
         let url = base_url.join("now")?;
 
         self
-          .http_client
-          .post(url)
-          .bearer_auth(token)
-          .json(now_playing)
-          .send()
-          .await?
-          .error_for_status()?;
+          .retrying(&Params::new("now"), || async {
+            let request = self.post(url.clone()).bearer_auth(token).json(now_playing);
+            response::text(self.send(request).await?).await?;
 
-        Ok(())
+            Ok(())
+          })
+          .await
       }
     }
   }
 
   /// Submit scrobble(s)
-  pub async fn scrobble(&self, scrobbles: &[Scrobble]) -> Result<ScrobbleResponse> {
+  ///
+  /// Accepts anything that iterates over scrobbles or references to them:
+  /// slices, `Vec`s, or iterator pipelines.
+  ///
+  /// ```no_run
+  /// # async fn example(client: &last_fm_rs::Client, history: Vec<last_fm_rs::Scrobble>) -> last_fm_rs::Result<()> {
+  /// let recent = history.iter().filter(|scrobble| scrobble.timestamp > 1_700_000_000);
+  /// client.scrobble(recent).await?;
+  /// # Ok(())
+  /// # }
+  /// ```
+  ///
+  /// Fails with [`Error::UnexpectedResponse`] if Last.fm's response doesn't
+  /// have an entry for every scrobble.
+  pub async fn scrobble<I>(&self, scrobbles: I) -> Result<ScrobbleResponse>
+  where
+    I: IntoIterator,
+    I::Item: Borrow<Scrobble>,
+  {
+    // One past the limit is enough to reject oversized batches
+    let items: Vec<I::Item> = scrobbles.into_iter().take(51).collect();
+    let scrobbles: Vec<&Scrobble> = items.iter().map(Borrow::borrow).collect();
+
     if scrobbles.is_empty() {
       return Err(Error::InvalidParameter("No scrobbles provided".to_string()));
     }
@@ -233,71 +907,49 @@ impl Client {
       ));
     }
 
-    match &self.auth {
-      AuthMode::LastFm { api_key, api_secret, session_key } => {
-        let sk = session_key
-          .as_ref()
-          .ok_or_else(|| Error::Auth("Session key required".to_string()))?;
-
-        let mut params = BTreeMap::new();
-        params.insert("method".to_string(), "track.scrobble".to_string());
-        params.insert("api_key".to_string(), api_key.clone());
-        params.insert("sk".to_string(), sk.clone());
-
-        for (i, scrobble) in scrobbles.iter().enumerate() {
-          params.insert(format!("artist[{}]", i), scrobble.artist.clone());
-          params.insert(format!("track[{}]", i), scrobble.track.clone());
-          params.insert(format!("timestamp[{}]", i), scrobble.timestamp.to_string());
-
-          if let Some(album) = &scrobble.album {
-            params.insert(format!("album[{}]", i), album.clone());
-          }
-          if let Some(track_number) = scrobble.track_number {
-            params.insert(format!("trackNumber[{}]", i), track_number.to_string());
-          }
-          if let Some(duration) = scrobble.duration {
-            params.insert(format!("duration[{}]", i), duration.to_string());
-          }
-          if let Some(album_artist) = &scrobble.album_artist {
-            params.insert(format!("albumArtist[{}]", i), album_artist.clone());
-          }
-        }
-
-        let sig = signature::generate(&params, api_secret);
-        params.insert("api_sig".to_string(), sig);
-        params.insert("format".to_string(), "json".to_string());
+    match &self.inner.auth {
+      AuthMode::LastFm {
+        api_key,
+        api_secret,
+      } => {
+        let sk = self.session_key()?;
 
-        let resp = self
-          .http_client
-          .post(API_BASE)
-          .form(&params)
-          .send()
-          .await?
-          .error_for_status()?;
+        let mut params = crate::scrobble::batch_params(&scrobbles, api_key, &sk);
+        self.sign(&mut params, require_secret(api_secret)?);
+        let body = params.encode();
 
-        let json: serde_json::Value = resp.json().await?;
-
-        if let Some(error) = json.get("error") {
-          Err(Error::Api(error.to_string()))
-        } else {
-          Ok(serde_json::from_value(json)?)
-        }
+        self
+          .retrying(&params, || async {
+            let reply: ScrobbleResponse = response::parse(&self.post_form(&body).await?)?;
+            let entries = reply.scrobbles.results.len();
+            if entries != scrobbles.len() {
+              return Err(Error::unexpected_response(format!(
+                "{} scrobble entries for {} scrobbles",
+                entries,
+                scrobbles.len()
+              )));
+            }
+            Ok(reply)
+          })
+          .await
+          .map_err(|error| error.with_signed(&params))
       }
       AuthMode::Token { base_url, token } => {
         let url = base_url.join("scrob")?;
 
         self
-          .http_client
-          .post(url)
-          .bearer_auth(token)
-          .json(&scrobbles)
-          .send()
-          .await?
-          .error_for_status()?;
+          .retrying(&Params::new("scrob"), || async {
+            let request = self.post(url.clone()).bearer_auth(token).json(&scrobbles);
+            response::text(self.send(request).await?).await?;
+
+            Ok(())
+          })
+          .await?;
 
         // Token mode: return a synthetic success response
         Ok(ScrobbleResponse {
           scrobbles: crate::scrobble::ScrobbleData {
+            results: Vec::new(),
             attr: crate::scrobble::ScrobbleAttr {
               accepted: scrobbles.len() as u32,
               ignored: 0,
@@ -308,19 +960,91 @@ impl Client {
     }
   }
 
-  /// Get track information
-  ///
-  /// Retrieves metadata for a track including playcount, listeners, tags, and wiki content.
-  /// This is a public endpoint that doesn't require authentication, but can optionally
-  /// include user-specific data (playcount, loved status) if a username is provided.
-  ///
-  /// Only available in Last.fm mode.
+  /// Submit a single scrobble and report what happened to it
   ///
   /// # Example
   ///
   /// ```no_run
-  /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
-  /// use last_fm_rs::Client;
+  /// # async fn example(client: &last_fm_rs::Client) -> last_fm_rs::Result<()> {
+  /// use last_fm_rs::Scrobble;
+  ///
+  /// let outcome = client.scrobble_one(&Scrobble::now("Cher", "Believe")).await?;
+  /// if let Some(ignored) = outcome.ignored {
+  ///   println!("Ignored: {}", ignored.text);
+  /// } else if let Some(artist) = outcome.corrections.artist {
+  ///   println!("Scrobbled as {}", artist);
+  /// }
+  /// # Ok(())
+  /// # }
+  /// ```
+  ///
+  /// Fails with [`Error::UnexpectedResponse`] if the response has no entry
+  /// for the scrobble.
+  pub async fn scrobble_one(&self, scrobble: &Scrobble) -> Result<ScrobbleOutcome> {
+    // `scrobble` only succeeds with one outcome per submitted scrobble
+    let mut outcomes = self.scrobble([scrobble]).await?.outcomes();
+    Ok(outcomes.swap_remove(0))
+  }
+
+  /// Love a track for the authenticated user
+  ///
+  /// Only available in Last.fm mode, and requires a session key.
+  pub async fn love_track(&self, artist: &str, track: &str) -> Result<()> {
+    match &self.inner.auth {
+      AuthMode::LastFm {
+        api_key,
+        api_secret,
+      } => {
+        let sk = self.session_key()?;
+
+        let mut params = Params::new("track.love");
+        params
+          .push("api_key", api_key)
+          .push("sk", sk)
+          .push("artist", artist)
+          .push("track", track);
+        self.sign(&mut params, require_secret(api_secret)?);
+        let body = params.encode();
+
+        self
+          .retrying(&params, || async {
+            response::parse::<serde::de::IgnoredAny>(&self.post_form(&body).await?)?;
+            Ok(())
+          })
+          .await
+          .map_err(|error| error.with_signed(&params))
+      }
+      AuthMode::Token { .. } => Err(Error::InvalidParameter(
+        "track.love is only available in Last.fm mode".to_string(),
+      )),
+    }
+  }
+}
+
+impl<S: ClientState> Client<S> {
+  #[cfg(feature = "metadata")]
+  /// Get track information
+  ///
+  /// Retrieves metadata for a track including playcount, listeners, tags, and wiki content.
+  /// This is a public endpoint that doesn't require authentication, but can optionally
+  /// include user-specific data (playcount, loved status) if a username is provided.
+  ///
+  /// Wiki content is localized when the client was built with [`Client::with_lang`].
+  ///
+  /// Concurrent identical lookups (e.g. from several tasks rendering the same
+  /// playlist) are coalesced into a single request whose result is shared.
+  /// Clients built with a [track info
+  /// cache](crate::ClientBuilder::track_info_cache) also answer repeated
+  /// lookups from memory, and ones with a [disk
+  /// cache](crate::ClientBuilder::disk_cache) from earlier runs.
+  ///
+  /// Only available in Last.fm mode.
+  ///
+  /// # Example
+  ///
+  /// ```no_run
+  /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+  /// use last_fm_rs::Client;
   ///
   /// let client = Client::new("api_key", "secret");
   ///
@@ -342,66 +1066,1328 @@ impl Client {
     artist: &str,
     track: &str,
     username: Option<&str>,
-  ) -> Result<crate::track::TrackInfo> {
-    match &self.auth {
-      AuthMode::LastFm { api_key, .. } => {
-        let mut params = BTreeMap::new();
-        params.insert("method".to_string(), "track.getInfo".to_string());
-        params.insert("api_key".to_string(), api_key.clone());
-        params.insert("artist".to_string(), artist.to_string());
-        params.insert("track".to_string(), track.to_string());
-        params.insert("format".to_string(), "json".to_string());
-
-        if let Some(username) = username {
-          params.insert("username".to_string(), username.to_string());
-        }
+  ) -> Result<TrackInfo> {
+    self.paced_track_info(artist, track, username, None).await
+  }
 
-        let response = self
-          .http_client
-          .get(API_BASE)
-          .query(&params)
-          .send()
-          .await?;
+  #[cfg(feature = "metadata")]
+  /// [`track_get_info`](Self::track_get_info), waiting for `pacer` before
+  /// asking Last.fm; cache hits and coalesced lookups don't wait
+  pub(crate) async fn paced_track_info(
+    &self,
+    artist: &str,
+    track: &str,
+    username: Option<&str>,
+    pacer: Option<&Pacer>,
+  ) -> Result<TrackInfo> {
+    let api_key = match &self.inner.auth {
+      AuthMode::LastFm { api_key, .. } => api_key,
+      AuthMode::Token { .. } => {
+        return Err(Error::InvalidParameter(
+          "track.getInfo is only available in Last.fm mode".to_string(),
+        ))
+      }
+    };
 
-        let response_text = response.text().await?;
-        let track_response: crate::track::TrackInfoResponse = serde_json::from_str(&response_text)
-          .map_err(|e| Error::Json(e))?;
+    let key = TrackInfoKey {
+      artist: artist.to_string(),
+      track: track.to_string(),
+      username: username.map(str::to_string),
+      lang: self.inner.lang.clone(),
+    };
 
-        Ok(track_response.track)
+    let cache = self.inner.track_info_cache.as_ref();
+    if let Some(info) = cache.and_then(|cache| cache.get(&key)) {
+      return Ok(info);
+    }
+    #[cfg(feature = "disk-cache")]
+    let disk_cache = self
+      .inner
+      .disk_cache
+      .as_deref()
+      .map(|disk_cache| (disk_cache, key.to_bytes()));
+    #[cfg(feature = "disk-cache")]
+    if let Some(info) = disk_cache
+      .as_ref()
+      .and_then(|(disk_cache, bytes)| disk_cache.get(bytes))
+    {
+      if let Some(cache) = cache {
+        cache.insert(key, info.clone());
       }
+      return Ok(info);
+    }
+
+    let params = self.track_info_params(artist, track, username);
+    let info = self
+      .inner
+      .track_info_requests
+      .run(key.clone(), || async {
+        if let Some(pacer) = pacer {
+          pacer.wait_turn().await;
+        }
+        self
+          .retrying(&params, || {
+            self.with_read_key(api_key, |api_key| {
+              self.get_read(&params, api_key, |body| {
+                response::parse::<TrackInfoResponse>(body).map(|response| response.track)
+              })
+            })
+          })
+          .await
+      })
+      .await?;
+    #[cfg(feature = "disk-cache")]
+    if let Some((disk_cache, bytes)) = &disk_cache {
+      // A write failure only means the next run asks Last.fm again
+      let _ = disk_cache.insert(bytes, &info);
+    }
+    if let Some(cache) = cache {
+      cache.insert(key, info.clone());
+    }
+    Ok(info)
+  }
+
+  #[cfg(feature = "metadata")]
+  /// [`track_get_info`](Self::track_get_info), keeping the response JSON
+  ///
+  /// Lookups made this way are not coalesced.
+  pub async fn track_get_info_raw(
+    &self,
+    artist: &str,
+    track: &str,
+    username: Option<&str>,
+  ) -> Result<ResponseParts<TrackInfo>> {
+    let api_key = match &self.inner.auth {
+      AuthMode::LastFm { api_key, .. } => api_key,
       AuthMode::Token { .. } => {
-        Err(Error::InvalidParameter(
+        return Err(Error::InvalidParameter(
           "track.getInfo is only available in Last.fm mode".to_string(),
         ))
       }
+    };
+
+    let params = self.track_info_params(artist, track, username);
+    self
+      .retrying(&params, || {
+        self.with_read_key(api_key, |api_key| {
+          self.get_read(&params, api_key, |body| {
+            Ok(response::parse_parts::<TrackInfoResponse>(body)?.map(|response| response.track))
+          })
+        })
+      })
+      .await
+  }
+
+  #[cfg(feature = "metadata")]
+  /// Look up [`track_get_info`](Self::track_get_info) for many (artist,
+  /// track) pairs, running up to `concurrency` requests at once
+  ///
+  /// Requests are started no faster than five per second, Last.fm's limit
+  /// for one API key. The results are in the same order as `items`; a failed
+  /// lookup only fails its own entry, so one missing track doesn't lose the
+  /// rest of a playlist.
+  ///
+  /// Only available in Last.fm mode.
+  ///
+  /// # Example
+  ///
+  /// ```no_run
+  /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+  /// use last_fm_rs::Client;
+  ///
+  /// let client = Client::new("api_key", "secret");
+  ///
+  /// let playlist = [("Cher", "Believe"), ("Madonna", "Frozen")];
+  /// for (item, result) in playlist.iter().zip(client.hydrate_tracks(playlist, 4).await) {
+  ///   match result {
+  ///     Ok(track) => println!("{}: {} listeners", track, track.listeners),
+  ///     Err(error) => println!("{} - {}: {}", item.0, item.1, error),
+  ///   }
+  /// }
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub async fn hydrate_tracks<I, A, T>(
+    &self,
+    items: I,
+    concurrency: usize,
+  ) -> Vec<Result<TrackInfo>>
+  where
+    I: IntoIterator<Item = (A, T)>,
+    A: AsRef<str>,
+    T: AsRef<str>,
+  {
+    hydrate::tracks(self, items.into_iter().collect(), concurrency).await
+  }
+
+  #[cfg(feature = "metadata")]
+  /// Parameters for `track.getInfo`, less the API key
+  fn track_info_params<'a>(
+    &'a self,
+    artist: &'a str,
+    track: &'a str,
+    username: Option<&'a str>,
+  ) -> Params<'a> {
+    let mut params = Params::new("track.getInfo");
+    params
+      .push("artist", artist)
+      .push("track", track)
+      .push_opt("username", username)
+      .push_opt("lang", self.inner.lang.as_ref());
+    params
+  }
+
+  #[cfg(feature = "metadata")]
+  /// Make a read-only GET call with `api_key` and parse the response
+  async fn get_read<R>(
+    &self,
+    params: &Params<'_>,
+    api_key: &str,
+    parse: fn(&str) -> Result<R>,
+  ) -> Result<R> {
+    let mut params = params.clone();
+    params.push("api_key", api_key);
+
+    let resp = self
+      .send(self.get(params.url(self.inner.api_base.as_str())))
+      .await?;
+
+    parse(&response::text(resp).await?)
+  }
+
+  #[cfg(feature = "metadata")]
+  /// Search for tracks by name, optionally narrowed by artist
+  ///
+  /// `page` is one-based. The returned [`SearchPage`] carries the total number
+  /// of matches so callers know how many pages exist.
+  ///
+  /// Only available in Last.fm mode.
+  ///
+  /// # Example
+  ///
+  /// ```no_run
+  /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+  /// use last_fm_rs::Client;
+  ///
+  /// let client = Client::new("api_key", "secret");
+  ///
+  /// let results = client.track_search("Believe", Some("Cher"), 1, 30).await?;
+  /// println!("Page {} of {}", results.page(), results.total_pages());
+  /// for track in &results.items {
+  ///   println!("{} - {}", track.artist, track.name);
+  /// }
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub async fn track_search(
+    &self,
+    track: &str,
+    artist: Option<&str>,
+    page: u32,
+    limit: u32,
+  ) -> Result<SearchPage<TrackMatch>> {
+    self
+      .track_search_with(track, artist, page, limit, |body| {
+        response::parse::<TrackSearchResponse>(body).map(Into::into)
+      })
+      .await
+  }
+
+  #[cfg(feature = "metadata")]
+  /// [`track_search`](Self::track_search), keeping the response JSON
+  pub async fn track_search_raw(
+    &self,
+    track: &str,
+    artist: Option<&str>,
+    page: u32,
+    limit: u32,
+  ) -> Result<ResponseParts<SearchPage<TrackMatch>>> {
+    self
+      .track_search_with(track, artist, page, limit, |body| {
+        Ok(response::parse_parts::<TrackSearchResponse>(body)?.map(Into::into))
+      })
+      .await
+  }
+
+  #[cfg(feature = "metadata")]
+  async fn track_search_with<R>(
+    &self,
+    track: &str,
+    artist: Option<&str>,
+    page: u32,
+    limit: u32,
+    parse: fn(&str) -> Result<R>,
+  ) -> Result<R> {
+    match &self.inner.auth {
+      AuthMode::LastFm { api_key, .. } => {
+        let mut params = Params::new("track.search");
+        params
+          .push("track", track)
+          .push("page", page)
+          .push("limit", limit)
+          .push_opt("artist", artist);
+
+        self
+          .retrying(&params, || {
+            self.with_read_key(api_key, |api_key| self.get_read(&params, api_key, parse))
+          })
+          .await
+      }
+      AuthMode::Token { .. } => Err(Error::InvalidParameter(
+        "track.search is only available in Last.fm mode".to_string(),
+      )),
+    }
+  }
+
+  #[cfg(feature = "metadata")]
+  /// Fetch one page of a user's listening history, newest first
+  ///
+  /// `page` is one-based. The track currently playing, if any, is left out.
+  ///
+  /// Only available in Last.fm mode.
+  pub async fn recent_tracks(
+    &self,
+    user: &str,
+    options: &RecentTracksOptions,
+    page: u32,
+  ) -> Result<SearchPage<RecentTrack>> {
+    self
+      .recent_tracks_with(user, options, page, |body| {
+        response::parse::<RecentTracksResponse>(body).map(Into::into)
+      })
+      .await
+  }
+
+  #[cfg(feature = "metadata")]
+  /// [`recent_tracks`](Self::recent_tracks), keeping the response JSON
+  pub async fn recent_tracks_raw(
+    &self,
+    user: &str,
+    options: &RecentTracksOptions,
+    page: u32,
+  ) -> Result<ResponseParts<SearchPage<RecentTrack>>> {
+    self
+      .recent_tracks_with(user, options, page, |body| {
+        Ok(response::parse_parts::<RecentTracksResponse>(body)?.map(Into::into))
+      })
+      .await
+  }
+
+  #[cfg(feature = "metadata")]
+  async fn recent_tracks_with<R>(
+    &self,
+    user: &str,
+    options: &RecentTracksOptions,
+    page: u32,
+    parse: fn(&str) -> Result<R>,
+  ) -> Result<R> {
+    match &self.inner.auth {
+      AuthMode::LastFm { api_key, .. } => {
+        let mut params = Params::new("user.getRecentTracks");
+        params
+          .push("user", user)
+          .push("page", page)
+          .push("limit", options.limit)
+          .push_opt("from", options.from)
+          .push_opt("to", options.to);
+
+        self
+          .retrying(&params, || {
+            self.with_read_key(api_key, |api_key| self.get_read(&params, api_key, parse))
+          })
+          .await
+      }
+      AuthMode::Token { .. } => Err(Error::InvalidParameter(
+        "user.getRecentTracks is only available in Last.fm mode".to_string(),
+      )),
+    }
+  }
+
+  #[cfg(feature = "metadata")]
+  /// Fetch the track a user is listening to right now, if any
+  ///
+  /// Only available in Last.fm mode.
+  pub async fn now_playing(&self, user: &str) -> Result<Option<RecentTrack>> {
+    let options = RecentTracksOptions::new().with_limit(1);
+    self
+      .recent_tracks_with(user, &options, 1, |body| {
+        response::parse::<RecentTracksResponse>(body).map(RecentTracksResponse::now_playing)
+      })
+      .await
+  }
+
+  #[cfg(feature = "metadata")]
+  /// Watch what a user is listening to, polling every `poll_interval`
+  ///
+  /// Yields `Some(track)` each time a different track starts playing and
+  /// `None` when playback stops; polls that find no change yield nothing.
+  /// Errors are yielded without ending the stream, and while Last.fm reports
+  /// rate limiting the interval doubles, up to 16 times `poll_interval`.
+  /// Last.fm only updates now-playing state every few seconds, so intervals
+  /// under about 10 seconds mostly waste requests.
+  ///
+  /// Only available in Last.fm mode.
+  ///
+  /// # Example
+  ///
+  /// ```no_run
+  /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+  /// use std::time::Duration;
+  /// use futures::StreamExt;
+  /// use last_fm_rs::Client;
+  ///
+  /// let client = Client::new("api_key", "secret");
+  /// let watcher = client.watch_now_playing("rj", Duration::from_secs(15));
+  /// let mut watcher = std::pin::pin!(watcher);
+  ///
+  /// while let Some(change) = watcher.next().await {
+  ///   match change? {
+  ///     Some(track) => println!("Listening to {}", track),
+  ///     None => println!("Stopped"),
+  ///   }
+  /// }
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub fn watch_now_playing(
+    &self,
+    user: impl Into<String>,
+    poll_interval: Duration,
+  ) -> impl Stream<Item = Result<Option<RecentTrack>>> + Send + 'static {
+    watch::now_playing(self.to_dynamic(), user.into(), poll_interval)
+  }
+
+  #[cfg(feature = "metadata")]
+  /// Stream a user's new scrobbles as they appear, polling every
+  /// `poll_interval`
+  ///
+  /// The first poll only marks where the history ends; after that, each
+  /// scrobble newer than the mark is yielded exactly once, oldest first.
+  /// Errors are yielded without ending the stream and are retried on the
+  /// next poll from the same point, backing off like
+  /// [`watch_now_playing`](Self::watch_now_playing) while rate limited.
+  ///
+  /// Only available in Last.fm mode.
+  pub fn watch_scrobbles(
+    &self,
+    user: impl Into<String>,
+    poll_interval: Duration,
+  ) -> impl Stream<Item = Result<RecentTrack>> + Send + 'static {
+    watch::scrobbles(self.to_dynamic(), user.into(), poll_interval)
+  }
+
+  #[cfg(feature = "metadata")]
+  /// Stream a user's listening history, newest first
+  ///
+  /// Pages are fetched lazily as the stream is polled, so dropping it (or
+  /// stopping with `take_while`) stops further requests. Pages are retried
+  /// according to the client's [`RetryPolicy`]; an error that survives it is
+  /// yielded once and ends the stream.
+  ///
+  /// # Example
+  ///
+  /// ```no_run
+  /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+  /// use futures::StreamExt;
+  /// use last_fm_rs::{Client, RecentTracksOptions};
+  ///
+  /// let client = Client::new("api_key", "secret");
+  ///
+  /// let cutoff = 1_700_000_000;
+  /// let history = client
+  ///   .recent_tracks_stream("rj", RecentTracksOptions::new())
+  ///   .take_while(|track| {
+  ///     let keep = track.as_ref().map_or(true, |t| t.timestamp() >= Some(cutoff));
+  ///     async move { keep }
+  ///   });
+  /// let mut history = std::pin::pin!(history);
+  ///
+  /// while let Some(track) = history.next().await {
+  ///   let track = track?;
+  ///   println!("{} - {}", track.artist.name, track.name);
+  /// }
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub fn recent_tracks_stream(
+    &self,
+    user: impl Into<String>,
+    options: RecentTracksOptions,
+  ) -> impl Stream<Item = Result<RecentTrack>> + Send + 'static {
+    recent::stream(self.to_dynamic(), user.into(), options)
+  }
+
+  #[cfg(feature = "metadata")]
+  /// Back up a user's listening history to `writer`
+  ///
+  /// Streams every scrobble matching `options` through an [`Exporter`], so
+  /// memory use stays flat regardless of history size. Returns the number of
+  /// records written.
+  ///
+  /// # Example
+  ///
+  /// ```no_run
+  /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+  /// use std::fs::File;
+  /// use std::io::BufWriter;
+  /// use last_fm_rs::{Client, ExportFormat, RecentTracksOptions};
+  ///
+  /// let client = Client::new("api_key", "secret");
+  /// let file = BufWriter::new(File::create("scrobbles.csv")?);
+  ///
+  /// let count = client
+  ///   .export_history("rj", RecentTracksOptions::new(), file, ExportFormat::Csv)
+  ///   .await?;
+  /// println!("Exported {} scrobbles", count);
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub async fn export_history<W: Write>(
+    &self,
+    user: &str,
+    options: RecentTracksOptions,
+    writer: W,
+    format: ExportFormat,
+  ) -> Result<u64> {
+    let mut exporter = Exporter::new(writer, format)?;
+    let mut history = std::pin::pin!(self.recent_tracks_stream(user, options));
+
+    while let Some(track) = history.next().await {
+      exporter.write(&ExportRecord::from(&track?))?;
+    }
+
+    let written = exporter.written();
+    exporter.finish()?;
+    Ok(written)
+  }
+
+  #[cfg(feature = "metadata")]
+  /// Fetch one page of a user's loved tracks, most recently loved first
+  ///
+  /// `page` is one-based; `limit` is capped at 1000 by Last.fm.
+  ///
+  /// Only available in Last.fm mode.
+  pub async fn loved_tracks(
+    &self,
+    user: &str,
+    page: u32,
+    limit: u32,
+  ) -> Result<SearchPage<LovedTrack>> {
+    self
+      .loved_tracks_with(user, page, limit, |body| {
+        response::parse::<LovedTracksResponse>(body).map(Into::into)
+      })
+      .await
+  }
+
+  #[cfg(feature = "metadata")]
+  /// [`loved_tracks`](Self::loved_tracks), keeping the response JSON
+  pub async fn loved_tracks_raw(
+    &self,
+    user: &str,
+    page: u32,
+    limit: u32,
+  ) -> Result<ResponseParts<SearchPage<LovedTrack>>> {
+    self
+      .loved_tracks_with(user, page, limit, |body| {
+        Ok(response::parse_parts::<LovedTracksResponse>(body)?.map(Into::into))
+      })
+      .await
+  }
+
+  #[cfg(feature = "metadata")]
+  async fn loved_tracks_with<R>(
+    &self,
+    user: &str,
+    page: u32,
+    limit: u32,
+    parse: fn(&str) -> Result<R>,
+  ) -> Result<R> {
+    match &self.inner.auth {
+      AuthMode::LastFm { api_key, .. } => {
+        let mut params = Params::new("user.getLovedTracks");
+        params
+          .push("user", user)
+          .push("page", page)
+          .push("limit", limit);
+
+        self
+          .retrying(&params, || {
+            self.with_read_key(api_key, |api_key| self.get_read(&params, api_key, parse))
+          })
+          .await
+      }
+      AuthMode::Token { .. } => Err(Error::InvalidParameter(
+        "user.getLovedTracks is only available in Last.fm mode".to_string(),
+      )),
+    }
+  }
+
+  #[cfg(all(feature = "metadata", feature = "scrobble"))]
+  /// Love every track loved by `source_user` on this client that
+  /// `target_user` hasn't loved on `target`
+  ///
+  /// Tracks are matched by artist and title, ignoring case, and loved on the
+  /// target oldest first so its history keeps the same order. `target` must
+  /// have a session key for `target_user` unless `options.dry_run` is set.
+  ///
+  /// A track that can't be loved doesn't stop the sync: it is listed in
+  /// [`SyncReport::failed`] and the rest are still loved. Rate limiting is
+  /// retried under `target`'s [`RetryPolicy`](crate::RetryPolicy) first.
+  /// Errors fetching either user's loved tracks are returned before
+  /// anything is changed.
+  ///
+  /// # Example
+  ///
+  /// ```no_run
+  /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+  /// use last_fm_rs::{Client, SyncOptions};
+  ///
+  /// let source = Client::new("api_key", "secret");
+  /// let target = Client::new("api_key", "secret").with_session_key("target_session");
+  ///
+  /// let report = source
+  ///   .sync_loved_tracks("old_account", &target, "new_account", &SyncOptions::new().with_dry_run(true))
+  ///   .await?;
+  /// for track in &report.missing {
+  ///   println!("Would love {} - {}", track.artist.name, track.name);
+  /// }
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub async fn sync_loved_tracks(
+    &self,
+    source_user: &str,
+    target: &Client<impl Authorized>,
+    target_user: &str,
+    options: &SyncOptions,
+  ) -> Result<SyncReport> {
+    let source_tracks = loved::all(self, source_user).await?;
+    let target_tracks = loved::all(target, target_user).await?;
+    let mut report = SyncReport {
+      missing: loved::missing(source_tracks, &target_tracks),
+      ..SyncReport::default()
+    };
+    if !options.dry_run {
+      let missing = std::mem::take(&mut report.missing);
+      loved::love_all(target, &missing, options.delay, &mut report).await;
+      report.missing = missing;
+    }
+    Ok(report)
+  }
+
+  #[cfg(feature = "metadata")]
+  /// Download an image with the client's HTTP connection pool
+  ///
+  /// Fails with [`Error::InvalidParameter`] for empty or placeholder URLs;
+  /// see [`Image::is_available`].
+  pub async fn fetch_image(&self, image: &Image) -> Result<Cover> {
+    if !image.is_available() {
+      return Err(Error::InvalidParameter(
+        "Image has no artwork to download".to_string(),
+      ));
     }
+
+    let resp = self
+      .send(self.get(image.url.trim()))
+      .await?
+      .error_for_status()?;
+
+    let content_type = resp
+      .headers()
+      .get(reqwest::header::CONTENT_TYPE)
+      .and_then(|value| value.to_str().ok())
+      .unwrap_or("application/octet-stream")
+      .to_string();
+
+    Ok(Cover {
+      content_type,
+      data: resp.bytes().await?.to_vec(),
+    })
   }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::builder::Endpoint;
+  #[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+  use crate::test_server::{Reply, TestServer};
 
   #[test]
   fn test_client_creation() {
     let client = Client::new("test_key", "test_secret");
-    assert!(client.auth.is_lastfm());
-    assert_eq!(client.auth.api_key(), Some("test_key"));
-    assert_eq!(client.auth.api_secret(), Some("test_secret"));
-    assert!(client.auth.session_key().is_none());
+    assert!(client.inner.auth.is_lastfm());
+    assert_eq!(client.inner.auth.api_key(), Some("test_key"));
+    assert_eq!(client.inner.auth.api_secret(), Some("test_secret"));
+    assert!(!client.has_session_key());
+  }
+
+  #[test]
+  fn test_builder_debug_hides_credentials() {
+    let debug = format!("{:?}", Client::builder("test_key", "test_secret"));
+    assert!(debug.contains("test_key"));
+    assert!(!debug.contains("test_secret"));
+
+    let builder = Client::token_builder("https://scrob.example.com/api/", "my_token").unwrap();
+    let debug = format!("{:?}", builder);
+    assert!(debug.contains("https://scrob.example.com/api/"));
+    assert!(!debug.contains("my_token"));
+  }
+
+  #[cfg(all(feature = "scrobble", feature = "auth-flow"))]
+  #[tokio::test]
+  async fn test_public_client_cannot_sign() {
+    let client = Client::new_public("test_key");
+    assert_eq!(client.api_key(), Some("test_key"));
+    assert_eq!(client.inner.auth.api_secret(), None);
+
+    assert!(matches!(client.get_token().await, Err(Error::Auth { .. })));
+    let client = client.with_session_key("sk");
+    let result = client
+      .scrobble([Scrobble::new("Cher", "Believe", 1_700_000_000)])
+      .await;
+    assert!(matches!(result, Err(Error::Auth { .. })));
+  }
+
+  #[test]
+  fn test_client_is_cheaply_cloneable() {
+    fn assert_send_sync<T: Send + Sync + Clone + 'static>() {}
+    assert_send_sync::<Client>();
+
+    let client = Client::new("test_key", "test_secret");
+    let clone = client.clone();
+    assert!(Arc::ptr_eq(&client.inner, &clone.inner));
+
+    let patient = clone.with_timeout(Duration::from_secs(60));
+    assert!(client.inner.timeout.is_none());
+    assert_eq!(patient.inner.timeout, Some(Duration::from_secs(60)));
+  }
+
+  #[test]
+  fn test_client_builder() {
+    let builder = Client::builder("test_key", "test_secret");
+    #[cfg(feature = "compression")]
+    let builder = builder.gzip(false).brotli(false);
+    let client = builder.build().expect("valid client");
+    assert!(client.inner.auth.is_lastfm());
+
+    let client = Client::token_builder("https://scrob.example.com/api/", "token")
+      .expect("valid URL")
+      .build()
+      .expect("valid client");
+    assert!(client.inner.auth.is_token());
+  }
+
+  #[test]
+  fn test_client_endpoint() {
+    let client = Client::builder("test_key", "test_secret")
+      .api_base("http://localhost:1/2.0/")
+      .endpoint(Endpoint::librefm())
+      .build()
+      .expect("valid client");
+    assert_eq!(client.base_url().as_str(), "https://libre.fm/2.0/");
+    assert_eq!(client.inner.auth_url.as_str(), "https://libre.fm/api/auth/");
+
+    let mock = Endpoint::new(
+      "mock",
+      "https://127.0.0.1:8443/2.0/",
+      "https://127.0.0.1:8443/auth/",
+    )
+    .danger_accept_invalid_certs(true);
+    assert_eq!(mock.name(), "mock");
+    let client = Client::builder("test_key", "test_secret")
+      .endpoint(mock)
+      .build()
+      .unwrap();
+    assert_eq!(client.base_url().port(), Some(8443));
+
+    let invalid = Endpoint::new("broken", "not a url", "https://example.com/");
+    assert!(Client::builder("test_key", "test_secret")
+      .endpoint(invalid)
+      .build()
+      .is_err());
+    assert_eq!(Endpoint::default(), Endpoint::lastfm());
+  }
+
+  #[cfg(feature = "metadata")]
+  #[test]
+  fn test_client_key_pool() {
+    let client = Client::builder("test_key", "test_secret")
+      .api_key_pool(["second_key"], crate::KeySelection::Failover)
+      .build()
+      .expect("valid client");
+    assert!(client.inner.key_pool.is_some());
+    assert!(Client::new("test_key", "test_secret")
+      .inner
+      .key_pool
+      .is_none());
+
+    let client = Client::token_builder("https://scrob.example.com/api/", "token")
+      .expect("valid URL")
+      .api_key_pool(["second_key"], crate::KeySelection::Failover)
+      .build()
+      .expect("valid client");
+    assert!(client.inner.key_pool.is_none());
+  }
+
+  #[test]
+  fn test_client_with_timeout() {
+    let client = Client::builder("test_key", "test_secret")
+      .timeout(Duration::from_secs(30))
+      .build()
+      .expect("valid client");
+    assert_eq!(client.inner.timeout, Some(Duration::from_secs(30)));
+
+    let quick = client.clone().with_timeout(Duration::from_secs(2));
+    assert_eq!(quick.inner.timeout, Some(Duration::from_secs(2)));
+    assert_eq!(client.inner.timeout, Some(Duration::from_secs(30)));
+  }
+
+  #[cfg(feature = "scrobble")]
+  #[tokio::test]
+  async fn test_timeout_maps_to_error() {
+    let server = TestServer::silent();
+    let client = Client::with_token(server.url("/"), "token")
+      .expect("valid URL")
+      .with_timeout(Duration::from_millis(100))
+      .with_retry_policy(crate::NoRetry);
+
+    let result = client
+      .update_now_playing(&NowPlaying::new("Artist", "Track"))
+      .await;
+    assert!(matches!(result, Err(Error::Timeout)), "got {:?}", result);
+  }
+
+  #[cfg(feature = "scrobble")]
+  #[tokio::test]
+  async fn test_deadline_caps_retries() {
+    let server = TestServer::silent();
+    let client = Client::token_builder(server.url("/"), "token")
+      .expect("valid URL")
+      .timeout(Duration::from_millis(50))
+      .deadline(Duration::from_millis(200))
+      .build()
+      .expect("valid client");
+
+    let start = std::time::Instant::now();
+    let result = client
+      .update_now_playing(&NowPlaying::new("Artist", "Track"))
+      .await;
+    assert!(
+      matches!(result, Err(Error::DeadlineExceeded)),
+      "got {:?}",
+      result
+    );
+    assert!(start.elapsed() < Duration::from_millis(500));
+  }
+
+  #[cfg(feature = "scrobble")]
+  #[tokio::test]
+  async fn test_app_info_headers() {
+    let server = TestServer::replies(vec![Reply::ok("")]);
+    let client = Client::token_builder(server.url("/"), "token")
+      .expect("valid URL")
+      .app_info("my-player", "2.0", "https://example.com")
+      .build()
+      .expect("valid client");
+    client
+      .update_now_playing(&NowPlaying::new("Artist", "Track"))
+      .await
+      .expect("accepted");
+
+    let request = server.requests()[0].to_lowercase();
+    assert!(request.contains(&format!(
+      "user-agent: my-player/2.0 (https://example.com) last-fm-rs/{}\r\n",
+      env!("CARGO_PKG_VERSION")
+    )));
+    assert!(request.contains("x-client-id: my-player/2.0\r\n"));
+  }
+
+  #[cfg(feature = "scrobble")]
+  #[tokio::test]
+  async fn test_transient_failures_are_retried() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // Every other request fails
+    let count = AtomicUsize::new(0);
+    let server = TestServer::start(move |_| match count.fetch_add(1, Ordering::Relaxed) % 2 {
+      0 => Reply::status("503 Service Unavailable", ""),
+      _ => Reply::ok(""),
+    });
+
+    let client = Client::token_builder(server.url("/"), "token")
+      .expect("valid URL")
+      .retry_policy(crate::ExponentialBackoff::new().with_initial_delay(Duration::from_millis(1)))
+      .build()
+      .expect("valid client");
+    let now_playing = NowPlaying::new("Artist", "Track");
+    client
+      .update_now_playing(&now_playing)
+      .await
+      .expect("retried");
+
+    let client = client.with_retry_policy(crate::NoRetry);
+    let result = client.update_now_playing(&now_playing).await;
+    assert!(
+      matches!(&result, Err(err) if err.is_transient()),
+      "got {:?}",
+      result
+    );
+  }
+
+  #[cfg(feature = "scrobble")]
+  #[tokio::test]
+  async fn test_error_status_body_is_parsed() {
+    let server = TestServer::replies(vec![
+      Reply::status(
+        "403 Forbidden",
+        r#"{"error":9,"message":"Invalid session key"}"#,
+      ),
+      Reply::status(
+        "503 Service Unavailable",
+        r#"{"error":16,"message":"Try again later"}"#,
+      ),
+      Reply::status("404 Not Found", "<html>Not Found</html>"),
+    ]);
+
+    let client = Client::with_token(server.url("/"), "token")
+      .expect("valid URL")
+      .with_retry_policy(crate::NoRetry);
+    let now_playing = NowPlaying::new("Artist", "Track");
+
+    let result = client.update_now_playing(&now_playing).await;
+    assert!(
+      matches!(&result, Err(Error::Api { method, code: 9, message, .. }) if method == "now" && message == "Invalid session key"),
+      "got {:?}",
+      result
+    );
+
+    let result = client.update_now_playing(&now_playing).await;
+    assert!(matches!(&result, Err(err @ Error::Api { code: 16, .. }) if err.is_transient()));
+
+    let result = client.update_now_playing(&now_playing).await;
+    assert!(
+      matches!(&result, Err(Error::Http { source: err, .. }) if err.status() == Some(reqwest::StatusCode::NOT_FOUND))
+    );
+  }
+
+  #[cfg(feature = "auth-flow")]
+  #[tokio::test]
+  async fn test_signature_hooks() {
+    let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let hook_seen = seen.clone();
+    let client = Client::new("key", "secret")
+      .on_signature(move |info| {
+        hook_seen.lock().unwrap().push((
+          info.method.to_string(),
+          info.masked.to_string(),
+          info.signature.to_string(),
+        ));
+      })
+      .on_request(|_| Err(Error::InvalidParameter("offline".to_string())));
+
+    assert!(client.get_token().await.is_err());
+    let expected = format!("{:x}", md5::compute("api_keykeymethodauth.getTokensecret"));
+    assert_eq!(
+      *seen.lock().unwrap(),
+      [(
+        "auth.getToken".to_string(),
+        "api_keykeymethodauth.getToken<secret>".to_string(),
+        expected
+      )]
+    );
+  }
+
+  #[cfg(feature = "auth-flow")]
+  #[tokio::test]
+  async fn test_signature_hooks_see_the_sent_signature() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Counting(AtomicUsize);
+
+    impl crate::Signer for Counting {
+      fn sign(&self, _message: &str, _secret: &str) -> String {
+        format!("sig{}", self.0.fetch_add(1, Ordering::Relaxed))
+      }
+    }
+
+    let hooked = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let sent = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let (hook_hooked, hook_sent) = (hooked.clone(), sent.clone());
+    let client = Client::new("key", "secret")
+      .with_signer(Counting(AtomicUsize::new(0)))
+      .with_retry_policy(crate::NoRetry)
+      .on_signature(move |info| hook_hooked.lock().unwrap().push(info.signature.to_string()))
+      .on_request(move |request| {
+        let sig = request
+          .url()
+          .query_pairs()
+          .find(|(name, _)| name == "api_sig");
+        hook_sent
+          .lock()
+          .unwrap()
+          .push(sig.map(|(_, value)| value.into_owned()));
+        Err(Error::InvalidParameter("offline".to_string()))
+      });
+
+    assert!(client.get_token().await.is_err());
+    assert_eq!(*hooked.lock().unwrap(), ["sig0"]);
+    assert_eq!(*sent.lock().unwrap(), [Some("sig0".to_string())]);
+  }
+
+  #[cfg(feature = "scrobble")]
+  #[tokio::test]
+  async fn test_invalid_signature_is_explained() {
+    let client = Client::new("key", "secret")
+      .with_session_key("session")
+      .with_retry_policy(crate::NoRetry)
+      .on_request(|_| {
+        Err(Error::Api {
+          method: String::new(),
+          params: String::new(),
+          code: 13,
+          message: "Invalid method signature supplied".to_string(),
+        })
+      });
+
+    let error = client.love_track("Beyoncé", "Halo").await.unwrap_err();
+    let Error::InvalidSignature {
+      method,
+      signed,
+      hints,
+      ..
+    } = &error
+    else {
+      panic!("unexpected error: {:?}", error);
+    };
+    assert_eq!(method, "track.love");
+    assert_eq!(signed, &["api_key", "artist", "method", "sk", "track"]);
+    assert_eq!(hints.len(), 4);
+    assert!(error
+      .to_string()
+      .contains("signed api_key, artist, method, sk, track;"));
+  }
+
+  #[cfg(feature = "scrobble")]
+  #[tokio::test]
+  async fn test_errors_name_the_call() {
+    let client = Client::new("key", "secret")
+      .with_session_key("session")
+      .with_retry_policy(crate::NoRetry)
+      .on_request(|_| Err(Error::rate_limited()));
+
+    let error = client.love_track("Cher", "Believe").await.unwrap_err();
+    let Error::RateLimited { method, params } = &error else {
+      panic!("unexpected error: {:?}", error);
+    };
+    assert_eq!(method, "track.love");
+    assert_eq!(params, "artist=Cher, track=Believe");
+    assert_eq!(
+      error.to_string(),
+      "Rate limit exceeded from track.love (artist=Cher, track=Believe)"
+    );
+
+    let client = Client::new("key", "secret")
+      .with_session_key("session")
+      .with_retry_policy(crate::NoRetry)
+      .on_request(|_| {
+        Err(Error::Auth {
+          method: String::new(),
+          params: String::new(),
+          code: Some(26),
+          message: "Suspended API key".to_string(),
+        })
+      });
+    let error = client.love_track("Cher", "Believe").await.unwrap_err();
+    assert!(
+      matches!(&error, Error::Auth { method, code: Some(26), .. } if method == "track.love"),
+      "got {:?}",
+      error
+    );
+  }
+
+  #[cfg(feature = "scrobble")]
+  #[tokio::test]
+  async fn test_hooks_see_every_request() {
+    use std::sync::Mutex;
+
+    let server = TestServer::replies(vec![Reply::status("202 Accepted", "")]);
+    let statuses = Arc::new(Mutex::new(Vec::new()));
+    let seen = statuses.clone();
+    let client = Client::with_token(server.url("/"), "token")
+      .expect("valid URL")
+      .with_retry_policy(crate::NoRetry)
+      .on_request(|request| {
+        request.headers_mut().insert("x-test", "1".parse().unwrap());
+        Ok(())
+      })
+      .on_response(move |info| {
+        seen
+          .lock()
+          .unwrap()
+          .push((info.url.path().to_string(), info.status));
+      });
+    let now_playing = NowPlaying::new("Artist", "Track");
+    client
+      .update_now_playing(&now_playing)
+      .await
+      .expect("accepted");
+
+    assert!(server.requests()[0]
+      .to_lowercase()
+      .contains("x-test: 1\r\n"));
+    assert_eq!(
+      *statuses.lock().unwrap(),
+      [("/now".to_string(), Some(reqwest::StatusCode::ACCEPTED))]
+    );
+
+    // A failing request hook stops the request before it is sent
+    let client = client.on_request(|_| Err(Error::InvalidParameter("injected".to_string())));
+    let result = client.update_now_playing(&now_playing).await;
+    assert!(matches!(&result, Err(Error::InvalidParameter(message)) if message == "injected"));
+    assert_eq!(statuses.lock().unwrap().len(), 1);
+  }
+
+  #[cfg(feature = "scrobble")]
+  #[tokio::test]
+  async fn test_scrobble_one_needs_an_entry() {
+    let server = TestServer::replies(vec![Reply::ok(
+      r#"{"scrobbles":{"@attr":{"accepted":"0","ignored":"0"}}}"#,
+    )]);
+    let client = Client::builder("key", "secret")
+      .api_base(server.url("/2.0/"))
+      .build()
+      .unwrap()
+      .with_session_key("session");
+
+    let error = client
+      .scrobble_one(&Scrobble::new("Cher", "Believe", 1_700_000_000))
+      .await;
+    let error = error.unwrap_err();
+    assert!(
+      matches!(&error, Error::UnexpectedResponse { method, .. } if method == "track.scrobble"),
+      "{:?}",
+      error
+    );
+    assert_eq!(
+      error.to_string(),
+      "Unexpected response from track.scrobble \
+       (artist[0]=Cher, timestamp[0]=1700000000, track[0]=Believe): \
+       0 scrobble entries for 1 scrobbles"
+    );
+  }
+
+  #[cfg(feature = "scrobble")]
+  #[tokio::test]
+  async fn test_scrobble_batch_limits() {
+    let client = Client::new("test_key", "test_secret").with_session_key("sk");
+    let scrobbles = (1..=51).map(|i| Scrobble::new("Artist", "Track", 1_700_000_000 + i));
+
+    let result = client.scrobble(scrobbles).await;
+    assert!(matches!(result, Err(Error::InvalidParameter(_))));
+    let result = client.scrobble(Vec::<Scrobble>::new()).await;
+    assert!(matches!(result, Err(Error::InvalidParameter(_))));
+  }
+
+  #[cfg(feature = "scrobble")]
+  #[tokio::test]
+  async fn test_player_is_sent_as_context() {
+    let bodies = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let seen = bodies.clone();
+    let client = Client::new("key", "secret")
+      .with_session_key("session")
+      .with_retry_policy(crate::NoRetry)
+      .on_request(move |request| {
+        let body = request
+          .body()
+          .and_then(reqwest::Body::as_bytes)
+          .unwrap_or_default();
+        seen
+          .lock()
+          .unwrap()
+          .push(String::from_utf8_lossy(body).into_owned());
+        Err(Error::InvalidParameter("not sent".to_string()))
+      });
+
+    let now_playing = NowPlaying::new("Artist", "Track").with_player("my-player");
+    let _ = client.update_now_playing(&now_playing).await;
+    let scrobble = Scrobble::new("Artist", "Track", 1_700_000_000).with_player("my-player");
+    let _ = client.scrobble([scrobble]).await;
+
+    let bodies = bodies.lock().unwrap();
+    assert!(bodies[0].contains("&context=my-player&"));
+    assert!(bodies[1].contains("&context%5B0%5D=my-player&"));
   }
 
+  #[test]
+  fn test_app_info_rejects_invalid_header() {
+    let result = Client::builder("test_key", "test_secret")
+      .app_info("player\n", "1.0", "me@example.com")
+      .build();
+    assert!(matches!(result, Err(Error::InvalidParameter(_))));
+  }
+
+  #[cfg(feature = "scrobble")]
   #[test]
   fn test_client_with_session_key() {
     let client = Client::new("test_key", "test_secret").with_session_key("session123");
-    assert_eq!(client.auth.session_key(), Some("session123"));
+    assert_eq!(client.session_key().unwrap(), "session123");
+  }
+
+  #[cfg(feature = "scrobble")]
+  #[test]
+  fn test_auth_state() {
+    let client = Client::new("test_key", "test_secret");
+    assert_eq!(client.auth_mode(), AuthKind::LastFm);
+    assert!(!client.is_authenticated());
+    assert!(!client.has_session_key());
+    assert_eq!(client.username(), None);
+    assert_eq!(client.api_key(), Some("test_key"));
+    assert_eq!(
+      client.base_url().as_str(),
+      "https://ws.audioscrobbler.com/2.0/"
+    );
+
+    let client = client.with_session(SessionKey {
+      key: "session123".to_string(),
+      name: "alice".to_string(),
+    });
+    assert!(client.is_authenticated());
+    assert!(client.has_session_key());
+    assert_eq!(client.username().as_deref(), Some("alice"));
+
+    let shared = client.clone();
+    shared.set_session_key("session789");
+    assert_eq!(client.session_key().unwrap(), "session789");
+    let detached = shared.clone().with_session_key("detached");
+    assert_eq!(client.session_key().unwrap(), "session789");
+    assert_eq!(detached.session_key().unwrap(), "detached");
+    shared.clear_session();
+    assert!(!client.is_authenticated());
+    assert!(detached.is_authenticated());
+
+    let client = client.with_session_key("session456");
+    assert!(client.is_authenticated());
+    assert_eq!(client.username(), None);
+
+    let client =
+      Client::with_token("https://scrob.example.com/api/", "my_token").expect("valid URL");
+    assert_eq!(client.auth_mode(), AuthKind::Token);
+    assert!(client.is_authenticated());
+    assert!(!client.has_session_key());
+    assert_eq!(client.api_key(), None);
+    assert_eq!(client.base_url().as_str(), "https://scrob.example.com/api/");
+  }
+
+  #[cfg(feature = "scrobble")]
+  #[test]
+  fn test_typed_states() {
+    let client = Client::new("test_key", "test_secret").into_unauthenticated();
+    assert!(matches!(
+      client.clone().authenticate(),
+      Err(Error::Auth { .. })
+    ));
+
+    let authenticated = client.clone().login(SessionKey {
+      key: "session123".to_string(),
+      name: "alice".to_string(),
+    });
+    assert_eq!(authenticated.username().as_deref(), Some("alice"));
+    // Other handles can't empty an authenticated client's session
+    let dynamic = authenticated.clone().into_dynamic();
+    dynamic.clear_session();
+    assert!(matches!(dynamic.session_key(), Err(Error::Auth { .. })));
+    assert_eq!(authenticated.session_key().unwrap(), "session123");
+
+    let shared = Client::new("test_key", "test_secret").with_session_key("session123");
+    let snapshot = shared.clone().authenticate().unwrap();
+    shared.clear_session();
+    assert_eq!(snapshot.session_key().unwrap(), "session123");
+
+    let logged_out = authenticated.clone().logout();
+    assert!(!logged_out.is_authenticated());
+    assert_eq!(authenticated.session_key().unwrap(), "session123");
+
+    let client = client
+      .with_session_key("session456")
+      .authenticate()
+      .unwrap();
+    assert_eq!(client.session_key().unwrap(), "session456");
+
+    let client = Client::with_token("https://scrob.example.com/api/", "my_token").unwrap();
+    assert!(client.authenticate().is_ok());
+  }
+
+  #[cfg(feature = "metadata")]
+  #[test]
+  fn test_client_with_lang() {
+    let client = Client::new("test_key", "test_secret").with_lang("de");
+    assert_eq!(client.inner.lang.as_deref(), Some("de"));
+  }
+
+  #[cfg(feature = "metadata")]
+  #[tokio::test]
+  async fn test_track_info_cache() {
+    let server = TestServer::start(|request| {
+      Reply::ok(if request.contains("lang=de") {
+        r#"{"track":{"name":"Believe","artist":{"name":"Cher"},"wiki":{"summary":"Lied"}}}"#
+      } else {
+        r#"{"track":{"name":"Believe","artist":{"name":"Cher"}}}"#
+      })
+    });
+
+    let client = Client::builder("key", "secret")
+      .api_base(server.url("/2.0/"))
+      .track_info_cache(10)
+      .build()
+      .unwrap();
+    let first = client
+      .track_get_info("Cher", "Believe", None)
+      .await
+      .unwrap();
+    let clone = client.clone().with_session_key("session");
+    assert_eq!(
+      clone.track_get_info("Cher", "Believe", None).await.unwrap(),
+      first
+    );
+
+    let german = client.clone().with_lang("de");
+    let info = german
+      .track_get_info("Cher", "Believe", None)
+      .await
+      .unwrap();
+    assert!(info.wiki.is_some());
+    assert!(german
+      .track_get_info("Cher", "Believe", None)
+      .await
+      .unwrap()
+      .wiki
+      .is_some());
+    assert_eq!(server.requests().len(), 2);
+
+    let cache = client.track_info_cache().unwrap();
+    assert_eq!((cache.stats().hits, cache.stats().misses), (2, 2));
+    assert_eq!(
+      german
+        .track_info_cache()
+        .unwrap()
+        .invalidate("Cher", "Believe"),
+      2
+    );
+    client
+      .track_get_info("Cher", "Believe", None)
+      .await
+      .unwrap();
+    assert_eq!(server.requests().len(), 3);
+    assert_eq!(cache.stats().len, 1);
   }
 
   #[test]
   fn test_client_with_token() {
-    let client = Client::with_token("https://scrob.example.com/api/", "my_token")
-      .expect("valid URL");
-    assert!(client.auth.is_token());
+    let client =
+      Client::with_token("https://scrob.example.com/api/", "my_token").expect("valid URL");
+    assert!(client.inner.auth.is_token());
   }
 
   #[test]
@@ -410,6 +2396,7 @@ mod tests {
     assert!(result.is_err());
   }
 
+  #[cfg(feature = "auth-flow")]
   #[test]
   fn test_get_auth_url() {
     let client = Client::new("my_api_key", "secret");
@@ -419,14 +2406,96 @@ mod tests {
     let url = client.get_auth_url(&token).expect("valid auth URL");
     assert_eq!(
       url,
-      "http://www.last.fm/api/auth/?api_key=my_api_key&token=test_token"
+      "https://www.last.fm/api/auth/?api_key=my_api_key&token=test_token"
+    );
+
+    let client = Client::builder("my_api_key", "secret")
+      .auth_url("https://libre.fm/api/auth/")
+      .build()
+      .expect("valid client");
+    let url = client.get_auth_url(&token).expect("valid auth URL");
+    assert_eq!(
+      url,
+      "https://libre.fm/api/auth/?api_key=my_api_key&token=test_token"
+    );
+  }
+
+  #[cfg(feature = "auth-flow")]
+  #[tokio::test]
+  async fn test_custom_api_base() {
+    let server = TestServer::replies(vec![
+      Reply::ok(r#"{"error":14,"message":"Unauthorized Token"}"#),
+      Reply::ok(r#"{"session":{"name":"alice","key":"session123","subscriber":0}}"#),
+    ]);
+
+    let client = Client::builder("key", "secret")
+      .api_base(server.url("/2.0/"))
+      .build()
+      .expect("valid client");
+    let token = AuthToken {
+      token: "test_token".to_string(),
+    };
+    let session = client
+      .wait_for_session(&token, Duration::from_millis(10), Duration::from_secs(5))
+      .await
+      .expect("authorized");
+    assert_eq!(session.name, "alice");
+
+    let requests = server.requests();
+    assert!(requests[1].starts_with("GET /2.0/?"));
+    assert!(requests[1].contains("method=auth.getSession"));
+  }
+
+  #[cfg(feature = "auth-flow")]
+  #[tokio::test(start_paused = true)]
+  async fn test_unapproved_session_backs_off_then_gives_up() {
+    let server = TestServer::start(|_| Reply::ok(r#"{"error":14,"message":"Unauthorized Token"}"#));
+    let client = Client::builder("key", "secret")
+      .api_base(server.url("/2.0/"))
+      .build()
+      .expect("valid client");
+    let token = AuthToken {
+      token: "test_token".to_string(),
+    };
+
+    let error = client
+      .wait_for_session(&token, Duration::from_secs(1), Duration::from_secs(10))
+      .await
+      .unwrap_err();
+    assert!(
+      matches!(error, Error::Auth { code: Some(14), .. }),
+      "got {:?}",
+      error
+    );
+    assert!(!error.is_transient());
+    // Polls at 0s, 1s, 3s, 7s and, capped by the timeout, 10s
+    assert_eq!(server.requests().len(), 5);
+  }
+
+  #[cfg(feature = "auth-flow")]
+  #[tokio::test]
+  async fn test_rejected_session_is_an_auth_error() {
+    let server = TestServer::replies(vec![Reply::ok(r#"{"error":15,"message":"Token expired"}"#)]);
+    let client = Client::builder("key", "secret")
+      .api_base(server.url("/2.0/"))
+      .build()
+      .expect("valid client");
+    let token = AuthToken {
+      token: "test_token".to_string(),
+    };
+
+    let error = client.get_session(&token).await.unwrap_err();
+    assert!(
+      matches!(&error, Error::Auth { method, code: Some(15), .. } if method == "auth.getSession"),
+      "got {:?}",
+      error
     );
   }
 
+  #[cfg(feature = "auth-flow")]
   #[test]
   fn test_get_auth_url_fails_in_token_mode() {
-    let client = Client::with_token("https://scrob.example.com/api/", "token")
-      .expect("valid URL");
+    let client = Client::with_token("https://scrob.example.com/api/", "token").expect("valid URL");
     let token = AuthToken {
       token: "test_token".to_string(),
     };
@@ -434,18 +2503,56 @@ mod tests {
     assert!(result.is_err());
   }
 
+  #[cfg(all(feature = "scrobble", feature = "auth-flow"))]
+  #[test]
+  fn test_malformed_responses_do_not_panic() {
+    assert!(response::parse::<TokenResponse>(r#"{"token":null}"#).is_err());
+    assert!(response::parse::<SessionResponse>(r#"{"session":{}}"#).is_err());
+    assert!(response::parse::<ScrobbleResponse>("not json").is_err());
+  }
+
   #[test]
   fn test_url_join_behavior() {
     use url::Url;
 
     // Test with trailing slash
     let base_with_slash = Url::parse("http://localhost:3000/").unwrap();
-    assert_eq!(base_with_slash.join("now").unwrap().as_str(), "http://localhost:3000/now");
-    assert_eq!(base_with_slash.join("scrob").unwrap().as_str(), "http://localhost:3000/scrob");
+    assert_eq!(
+      base_with_slash.join("now").unwrap().as_str(),
+      "http://localhost:3000/now"
+    );
+    assert_eq!(
+      base_with_slash.join("scrob").unwrap().as_str(),
+      "http://localhost:3000/scrob"
+    );
 
     // Test without trailing slash
     let base_no_slash = Url::parse("http://localhost:3000").unwrap();
-    assert_eq!(base_no_slash.join("now").unwrap().as_str(), "http://localhost:3000/now");
-    assert_eq!(base_no_slash.join("scrob").unwrap().as_str(), "http://localhost:3000/scrob");
+    assert_eq!(
+      base_no_slash.join("now").unwrap().as_str(),
+      "http://localhost:3000/now"
+    );
+    assert_eq!(
+      base_no_slash.join("scrob").unwrap().as_str(),
+      "http://localhost:3000/scrob"
+    );
+  }
+
+  #[cfg(all(
+    feature = "log",
+    any(feature = "scrobble", feature = "auth-flow", feature = "metadata")
+  ))]
+  #[tokio::test]
+  async fn test_logged_errors_hide_the_query() {
+    // Answers nothing, so the request fails
+    let server = TestServer::replies(Vec::new());
+    let error = Error::from(
+      reqwest::get(server.url("/2.0/?api_key=secret"))
+        .await
+        .unwrap_err(),
+    );
+    let message = error.redacted();
+    assert!(!message.contains("secret"), "{}", message);
+    assert!(message.contains("/2.0/"), "{}", message);
   }
 }
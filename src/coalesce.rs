@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::OnceCell;
+
+/// Shares one in-flight computation between concurrent callers with the same key
+///
+/// Results are not cached: once the shared call completes, the next caller
+/// for that key starts a fresh one. If the task driving the call is dropped,
+/// one of the waiting callers takes over; if every caller is dropped, the
+/// key is forgotten.
+pub(crate) struct Coalescer<K, V> {
+  inflight: Mutex<HashMap<K, Arc<OnceCell<V>>>>,
+}
+
+impl<K, V> Coalescer<K, V>
+where
+  K: Eq + Hash + Clone,
+  V: Clone,
+{
+  pub fn new() -> Self {
+    Self {
+      inflight: Mutex::new(HashMap::new()),
+    }
+  }
+
+  /// Run `call` unless an identical call is already in flight, then share its result
+  pub async fn run<F, Fut>(&self, key: K, call: F) -> V
+  where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = V>,
+  {
+    let cell = self
+      .lock()
+      .entry(key.clone())
+      .or_insert_with(|| Arc::new(OnceCell::new()))
+      .clone();
+    let entry = Entry {
+      coalescer: self,
+      key,
+      cell,
+    };
+
+    entry.cell.get_or_init(call).await.clone()
+  }
+
+  fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<K, Arc<OnceCell<V>>>> {
+    // The map is only touched in short non-panicking sections
+    self
+      .inflight
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+}
+
+/// A caller's hold on an in-flight key, released when it finishes or is dropped
+struct Entry<'a, K, V>
+where
+  K: Eq + Hash + Clone,
+  V: Clone,
+{
+  coalescer: &'a Coalescer<K, V>,
+  key: K,
+  cell: Arc<OnceCell<V>>,
+}
+
+impl<K, V> Drop for Entry<'_, K, V>
+where
+  K: Eq + Hash + Clone,
+  V: Clone,
+{
+  fn drop(&mut self) {
+    let mut inflight = self.coalescer.lock();
+    let Some(current) = inflight.get(&self.key) else {
+      return;
+    };
+    // Counted under the lock: one reference in the map plus one per caller
+    let last_caller = Arc::strong_count(&self.cell) <= 2;
+    if Arc::ptr_eq(current, &self.cell) && (self.cell.initialized() || last_caller) {
+      inflight.remove(&self.key);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+  use std::time::Duration;
+
+  #[tokio::test]
+  async fn test_concurrent_calls_share_one_execution() {
+    let coalescer = Arc::new(Coalescer::<&str, u32>::new());
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let tasks: Vec<_> = (0..8)
+      .map(|_| {
+        let coalescer = coalescer.clone();
+        let calls = calls.clone();
+        tokio::spawn(async move {
+          coalescer
+            .run("key", || async move {
+              calls.fetch_add(1, Ordering::SeqCst);
+              tokio::time::sleep(Duration::from_millis(50)).await;
+              42
+            })
+            .await
+        })
+      })
+      .collect();
+
+    for task in tasks {
+      assert_eq!(task.await.unwrap(), 42);
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert!(coalescer.lock().is_empty());
+  }
+
+  #[tokio::test]
+  async fn test_cancelled_calls_are_cleaned_up() {
+    let coalescer = Arc::new(Coalescer::<&str, u32>::new());
+
+    // A lone caller dropped mid-call leaves nothing behind
+    let pending = coalescer.run("key", || async {
+      tokio::time::sleep(Duration::from_secs(60)).await;
+      1
+    });
+    assert!(tokio::time::timeout(Duration::from_millis(10), pending)
+      .await
+      .is_err());
+    assert!(coalescer.lock().is_empty());
+
+    // A follower takes over when the leader is cancelled
+    let leader = {
+      let coalescer = coalescer.clone();
+      tokio::spawn(async move {
+        coalescer
+          .run("key", || async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            1
+          })
+          .await
+      })
+    };
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    let follower = {
+      let coalescer = coalescer.clone();
+      tokio::spawn(async move { coalescer.run("key", || async { 2 }).await })
+    };
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    leader.abort();
+
+    assert_eq!(follower.await.unwrap(), 2);
+    assert!(coalescer.lock().is_empty());
+  }
+
+  #[tokio::test]
+  async fn test_sequential_calls_are_not_cached() {
+    let coalescer = Coalescer::<&str, usize>::new();
+    let calls = AtomicUsize::new(0);
+
+    for expected in 1..=2 {
+      let value = coalescer
+        .run("key", || async { calls.fetch_add(1, Ordering::SeqCst) + 1 })
+        .await;
+      assert_eq!(value, expected);
+    }
+  }
+}
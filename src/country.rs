@@ -0,0 +1,388 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error::{Error, Result};
+
+/// Country for Last.fm's geo methods, validated against ISO 3166-1
+///
+/// Parsed from an alpha-2 code (`"GB"`) or the ISO short name
+/// (`"United Kingdom"`), either case-insensitively. Last.fm expects the name,
+/// so that is what [`as_str`](Self::as_str) returns and what gets sent; a
+/// misspelt country fails here instead of coming back as an empty chart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Country {
+  code: &'static str,
+  name: &'static str,
+}
+
+impl Country {
+  /// Look up `value` as an alpha-2 code or a country name
+  pub fn parse(value: &str) -> Result<Self> {
+    let value = value.trim();
+    let found = if value.len() == 2 {
+      let code = value.to_ascii_uppercase();
+      COUNTRIES
+        .binary_search_by(|(c, _)| (*c).cmp(code.as_str()))
+        .ok()
+        .map(|i| COUNTRIES[i])
+    } else {
+      COUNTRIES
+        .iter()
+        .find(|(_, name)| name.eq_ignore_ascii_case(value))
+        .copied()
+    };
+    match found {
+      Some((code, name)) => Ok(Self { code, name }),
+      None => Err(Error::InvalidParameter(format!(
+        "Unknown country: {:?}",
+        value
+      ))),
+    }
+  }
+
+  /// ISO 3166-1 alpha-2 code, such as `GB`
+  pub fn code(&self) -> &'static str {
+    self.code
+  }
+
+  /// Value sent as the `country` request parameter, such as `United Kingdom`
+  pub fn as_str(&self) -> &'static str {
+    self.name
+  }
+}
+
+impl FromStr for Country {
+  type Err = Error;
+
+  fn from_str(value: &str) -> Result<Self> {
+    Self::parse(value)
+  }
+}
+
+impl TryFrom<&str> for Country {
+  type Error = Error;
+
+  fn try_from(value: &str) -> Result<Self> {
+    Self::parse(value)
+  }
+}
+
+impl AsRef<str> for Country {
+  fn as_ref(&self) -> &str {
+    self.name
+  }
+}
+
+impl fmt::Display for Country {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.name)
+  }
+}
+
+impl Serialize for Country {
+  fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(self.name)
+  }
+}
+
+impl<'de> Deserialize<'de> for Country {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    Self::parse(&value).map_err(serde::de::Error::custom)
+  }
+}
+
+/// ISO 3166-1 alpha-2 codes and short names, sorted by code
+const COUNTRIES: &[(&str, &str)] = &[
+  ("AD", "Andorra"),
+  ("AE", "United Arab Emirates"),
+  ("AF", "Afghanistan"),
+  ("AG", "Antigua and Barbuda"),
+  ("AI", "Anguilla"),
+  ("AL", "Albania"),
+  ("AM", "Armenia"),
+  ("AO", "Angola"),
+  ("AQ", "Antarctica"),
+  ("AR", "Argentina"),
+  ("AS", "American Samoa"),
+  ("AT", "Austria"),
+  ("AU", "Australia"),
+  ("AW", "Aruba"),
+  ("AX", "Åland Islands"),
+  ("AZ", "Azerbaijan"),
+  ("BA", "Bosnia and Herzegovina"),
+  ("BB", "Barbados"),
+  ("BD", "Bangladesh"),
+  ("BE", "Belgium"),
+  ("BF", "Burkina Faso"),
+  ("BG", "Bulgaria"),
+  ("BH", "Bahrain"),
+  ("BI", "Burundi"),
+  ("BJ", "Benin"),
+  ("BL", "Saint Barthélemy"),
+  ("BM", "Bermuda"),
+  ("BN", "Brunei Darussalam"),
+  ("BO", "Bolivia, Plurinational State of"),
+  ("BQ", "Bonaire, Sint Eustatius and Saba"),
+  ("BR", "Brazil"),
+  ("BS", "Bahamas"),
+  ("BT", "Bhutan"),
+  ("BV", "Bouvet Island"),
+  ("BW", "Botswana"),
+  ("BY", "Belarus"),
+  ("BZ", "Belize"),
+  ("CA", "Canada"),
+  ("CC", "Cocos (Keeling) Islands"),
+  ("CD", "Congo, The Democratic Republic of the"),
+  ("CF", "Central African Republic"),
+  ("CG", "Congo"),
+  ("CH", "Switzerland"),
+  ("CI", "Côte d'Ivoire"),
+  ("CK", "Cook Islands"),
+  ("CL", "Chile"),
+  ("CM", "Cameroon"),
+  ("CN", "China"),
+  ("CO", "Colombia"),
+  ("CR", "Costa Rica"),
+  ("CU", "Cuba"),
+  ("CV", "Cape Verde"),
+  ("CW", "Curaçao"),
+  ("CX", "Christmas Island"),
+  ("CY", "Cyprus"),
+  ("CZ", "Czech Republic"),
+  ("DE", "Germany"),
+  ("DJ", "Djibouti"),
+  ("DK", "Denmark"),
+  ("DM", "Dominica"),
+  ("DO", "Dominican Republic"),
+  ("DZ", "Algeria"),
+  ("EC", "Ecuador"),
+  ("EE", "Estonia"),
+  ("EG", "Egypt"),
+  ("EH", "Western Sahara"),
+  ("ER", "Eritrea"),
+  ("ES", "Spain"),
+  ("ET", "Ethiopia"),
+  ("FI", "Finland"),
+  ("FJ", "Fiji"),
+  ("FK", "Falkland Islands (Malvinas)"),
+  ("FM", "Micronesia, Federated States of"),
+  ("FO", "Faroe Islands"),
+  ("FR", "France"),
+  ("GA", "Gabon"),
+  ("GB", "United Kingdom"),
+  ("GD", "Grenada"),
+  ("GE", "Georgia"),
+  ("GF", "French Guiana"),
+  ("GG", "Guernsey"),
+  ("GH", "Ghana"),
+  ("GI", "Gibraltar"),
+  ("GL", "Greenland"),
+  ("GM", "Gambia"),
+  ("GN", "Guinea"),
+  ("GP", "Guadeloupe"),
+  ("GQ", "Equatorial Guinea"),
+  ("GR", "Greece"),
+  ("GS", "South Georgia and the South Sandwich Islands"),
+  ("GT", "Guatemala"),
+  ("GU", "Guam"),
+  ("GW", "Guinea-Bissau"),
+  ("GY", "Guyana"),
+  ("HK", "Hong Kong"),
+  ("HM", "Heard Island and McDonald Islands"),
+  ("HN", "Honduras"),
+  ("HR", "Croatia"),
+  ("HT", "Haiti"),
+  ("HU", "Hungary"),
+  ("ID", "Indonesia"),
+  ("IE", "Ireland"),
+  ("IL", "Israel"),
+  ("IM", "Isle of Man"),
+  ("IN", "India"),
+  ("IO", "British Indian Ocean Territory"),
+  ("IQ", "Iraq"),
+  ("IR", "Iran, Islamic Republic of"),
+  ("IS", "Iceland"),
+  ("IT", "Italy"),
+  ("JE", "Jersey"),
+  ("JM", "Jamaica"),
+  ("JO", "Jordan"),
+  ("JP", "Japan"),
+  ("KE", "Kenya"),
+  ("KG", "Kyrgyzstan"),
+  ("KH", "Cambodia"),
+  ("KI", "Kiribati"),
+  ("KM", "Comoros"),
+  ("KN", "Saint Kitts and Nevis"),
+  ("KP", "Korea, Democratic People's Republic of"),
+  ("KR", "Korea, Republic of"),
+  ("KW", "Kuwait"),
+  ("KY", "Cayman Islands"),
+  ("KZ", "Kazakhstan"),
+  ("LA", "Lao People's Democratic Republic"),
+  ("LB", "Lebanon"),
+  ("LC", "Saint Lucia"),
+  ("LI", "Liechtenstein"),
+  ("LK", "Sri Lanka"),
+  ("LR", "Liberia"),
+  ("LS", "Lesotho"),
+  ("LT", "Lithuania"),
+  ("LU", "Luxembourg"),
+  ("LV", "Latvia"),
+  ("LY", "Libya"),
+  ("MA", "Morocco"),
+  ("MC", "Monaco"),
+  ("MD", "Moldova, Republic of"),
+  ("ME", "Montenegro"),
+  ("MF", "Saint Martin (French part)"),
+  ("MG", "Madagascar"),
+  ("MH", "Marshall Islands"),
+  ("MK", "Macedonia, the Former Yugoslav Republic of"),
+  ("ML", "Mali"),
+  ("MM", "Myanmar"),
+  ("MN", "Mongolia"),
+  ("MO", "Macao"),
+  ("MP", "Northern Mariana Islands"),
+  ("MQ", "Martinique"),
+  ("MR", "Mauritania"),
+  ("MS", "Montserrat"),
+  ("MT", "Malta"),
+  ("MU", "Mauritius"),
+  ("MV", "Maldives"),
+  ("MW", "Malawi"),
+  ("MX", "Mexico"),
+  ("MY", "Malaysia"),
+  ("MZ", "Mozambique"),
+  ("NA", "Namibia"),
+  ("NC", "New Caledonia"),
+  ("NE", "Niger"),
+  ("NF", "Norfolk Island"),
+  ("NG", "Nigeria"),
+  ("NI", "Nicaragua"),
+  ("NL", "Netherlands"),
+  ("NO", "Norway"),
+  ("NP", "Nepal"),
+  ("NR", "Nauru"),
+  ("NU", "Niue"),
+  ("NZ", "New Zealand"),
+  ("OM", "Oman"),
+  ("PA", "Panama"),
+  ("PE", "Peru"),
+  ("PF", "French Polynesia"),
+  ("PG", "Papua New Guinea"),
+  ("PH", "Philippines"),
+  ("PK", "Pakistan"),
+  ("PL", "Poland"),
+  ("PM", "Saint Pierre and Miquelon"),
+  ("PN", "Pitcairn"),
+  ("PR", "Puerto Rico"),
+  ("PS", "Palestine, State of"),
+  ("PT", "Portugal"),
+  ("PW", "Palau"),
+  ("PY", "Paraguay"),
+  ("QA", "Qatar"),
+  ("RE", "Réunion"),
+  ("RO", "Romania"),
+  ("RS", "Serbia"),
+  ("RU", "Russian Federation"),
+  ("RW", "Rwanda"),
+  ("SA", "Saudi Arabia"),
+  ("SB", "Solomon Islands"),
+  ("SC", "Seychelles"),
+  ("SD", "Sudan"),
+  ("SE", "Sweden"),
+  ("SG", "Singapore"),
+  ("SH", "Saint Helena, Ascension and Tristan da Cunha"),
+  ("SI", "Slovenia"),
+  ("SJ", "Svalbard and Jan Mayen"),
+  ("SK", "Slovakia"),
+  ("SL", "Sierra Leone"),
+  ("SM", "San Marino"),
+  ("SN", "Senegal"),
+  ("SO", "Somalia"),
+  ("SR", "Suriname"),
+  ("SS", "South Sudan"),
+  ("ST", "Sao Tome and Principe"),
+  ("SV", "El Salvador"),
+  ("SX", "Sint Maarten (Dutch part)"),
+  ("SY", "Syrian Arab Republic"),
+  ("SZ", "Swaziland"),
+  ("TC", "Turks and Caicos Islands"),
+  ("TD", "Chad"),
+  ("TF", "French Southern Territories"),
+  ("TG", "Togo"),
+  ("TH", "Thailand"),
+  ("TJ", "Tajikistan"),
+  ("TK", "Tokelau"),
+  ("TL", "Timor-Leste"),
+  ("TM", "Turkmenistan"),
+  ("TN", "Tunisia"),
+  ("TO", "Tonga"),
+  ("TR", "Turkey"),
+  ("TT", "Trinidad and Tobago"),
+  ("TV", "Tuvalu"),
+  ("TW", "Taiwan, Province of China"),
+  ("TZ", "Tanzania, United Republic of"),
+  ("UA", "Ukraine"),
+  ("UG", "Uganda"),
+  ("UM", "United States Minor Outlying Islands"),
+  ("US", "United States"),
+  ("UY", "Uruguay"),
+  ("UZ", "Uzbekistan"),
+  ("VA", "Holy See (Vatican City State)"),
+  ("VC", "Saint Vincent and the Grenadines"),
+  ("VE", "Venezuela, Bolivarian Republic of"),
+  ("VG", "Virgin Islands, British"),
+  ("VI", "Virgin Islands, U.S."),
+  ("VN", "Viet Nam"),
+  ("VU", "Vanuatu"),
+  ("WF", "Wallis and Futuna"),
+  ("WS", "Samoa"),
+  ("YE", "Yemen"),
+  ("YT", "Mayotte"),
+  ("ZA", "South Africa"),
+  ("ZM", "Zambia"),
+  ("ZW", "Zimbabwe"),
+];
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse() {
+    let uk = Country::parse("gb").unwrap();
+    assert_eq!(uk.code(), "GB");
+    assert_eq!(uk.as_str(), "United Kingdom");
+    assert_eq!(Country::parse(" united kingdom ").unwrap(), uk);
+    assert_eq!("Côte d'Ivoire".parse::<Country>().unwrap().code(), "CI");
+
+    for invalid in ["", "UK", "Untied Kingdom", "Englandd"] {
+      assert!(
+        matches!(Country::parse(invalid), Err(Error::InvalidParameter(_))),
+        "{}",
+        invalid
+      );
+    }
+  }
+
+  #[test]
+  fn test_table_is_sorted() {
+    assert!(COUNTRIES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    for (code, name) in COUNTRIES {
+      assert_eq!(Country::parse(code).unwrap().as_str(), *name);
+      assert_eq!(Country::parse(name).unwrap().code(), *code);
+    }
+  }
+
+  #[test]
+  fn test_serde_uses_the_name() {
+    let spain = Country::parse("ES").unwrap();
+    let json = serde_json::to_string(&spain).unwrap();
+    assert_eq!(json, "\"Spain\"");
+    assert_eq!(serde_json::from_str::<Country>(&json).unwrap(), spain);
+    assert!(serde_json::from_str::<Country>("\"Atlantis\"").is_err());
+  }
+}
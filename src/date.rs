@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+use crate::de;
+
+/// Date object from the API, as a unix timestamp and a display string
+///
+/// Accepts both shapes Last.fm uses: `{"uts": ..., "#text": "..."}` on
+/// scrobbles and loved tracks, and `{"unixtime": ..., "#text": ...}` on
+/// user registration dates.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[non_exhaustive]
+#[cfg_attr(feature = "strict-schema", serde(deny_unknown_fields))]
+pub struct LastfmDate {
+  #[serde(alias = "unixtime", deserialize_with = "de::number", default)]
+  pub uts: u64,
+  /// Human-readable form, e.g. `"14 Nov 2023, 22:13"` (UTC)
+  #[serde(rename = "#text", deserialize_with = "de::string", default)]
+  pub text: String,
+}
+
+impl LastfmDate {
+  /// Unix timestamp in seconds
+  pub fn timestamp(&self) -> u64 {
+    self.uts
+  }
+
+  /// The date as a UTC datetime
+  #[cfg(feature = "chrono")]
+  pub fn datetime(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+    i64::try_from(self.uts)
+      .ok()
+      .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_both_shapes() {
+    let scrobbled: LastfmDate =
+      serde_json::from_str(r##"{"uts":"1700000000","#text":"14 Nov 2023, 22:13"}"##).unwrap();
+    assert_eq!(scrobbled.timestamp(), 1_700_000_000);
+    assert_eq!(scrobbled.text, "14 Nov 2023, 22:13");
+
+    let registered: LastfmDate =
+      serde_json::from_str(r##"{"unixtime":"1037793040","#text":1037793040}"##).unwrap();
+    assert_eq!(registered.timestamp(), 1_037_793_040);
+    assert_eq!(registered.text, "1037793040");
+  }
+}
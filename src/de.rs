@@ -0,0 +1,462 @@
+//! Lenient deserializers for Last.fm's inconsistent JSON
+//!
+//! The API encodes numbers as strings (or not), omits fields, and collapses
+//! single-item arrays into bare objects. These helpers accept all of those
+//! shapes and fall back to defaults instead of failing the whole response.
+//!
+//! With the `strict-schema` feature, nested objects and lists that don't
+//! match their model, and fields no model knows, are errors instead, so
+//! schema changes show up in tests rather than as missing data.
+
+use serde::de::{DeserializeOwned, Error as _};
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+use crate::mbid::Mbid;
+
+/// Extract an unsigned integer from a number or numeric string
+fn value_as_u64(value: &Value) -> Option<u64> {
+  match value {
+    Value::Number(n) => n.as_u64().or_else(|| {
+      n.as_f64()
+        .filter(|f| f.is_finite() && *f >= 0.0 && *f <= u64::MAX as f64)
+        .map(|f| f as u64)
+    }),
+    Value::String(s) => {
+      let s = s.trim();
+      s.parse::<u64>().ok().or_else(|| {
+        s.parse::<f64>()
+          .ok()
+          .filter(|f| f.is_finite() && *f >= 0.0 && *f <= u64::MAX as f64)
+          .map(|f| f as u64)
+      })
+    }
+    _ => None,
+  }
+}
+
+/// Number or numeric string, defaulting when absent or unparseable
+pub(crate) fn number<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+  D: Deserializer<'de>,
+  T: TryFrom<u64> + Default,
+{
+  Ok(option_number(deserializer)?.unwrap_or_default())
+}
+
+/// Optional number or numeric string, `None` when absent or unparseable
+pub(crate) fn option_number<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+  D: Deserializer<'de>,
+  T: TryFrom<u64>,
+{
+  let value = Value::deserialize(deserializer)?;
+  Ok(value_as_u64(&value).and_then(|n| T::try_from(n).ok()))
+}
+
+/// String that may arrive as a number, boolean, or null
+pub(crate) fn string<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  let value = Value::deserialize(deserializer)?;
+  Ok(match value {
+    Value::String(s) => s,
+    Value::Number(n) => n.to_string(),
+    Value::Bool(b) => b.to_string(),
+    _ => String::new(),
+  })
+}
+
+/// MusicBrainz ID, `None` when empty or not a valid UUID
+pub(crate) fn mbid<'de, D>(deserializer: D) -> Result<Option<Mbid>, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  Ok(Mbid::parse(&string(deserializer)?).ok())
+}
+
+/// URL, `None` when empty or unparseable
+#[cfg(feature = "models")]
+pub(crate) fn url<'de, D>(deserializer: D) -> Result<Option<url::Url>, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  Ok(url::Url::parse(string(deserializer)?.trim()).ok())
+}
+
+/// Image list without the empty-URL entries Last.fm pads it with
+#[cfg(feature = "models")]
+pub(crate) fn images<'de, D>(deserializer: D) -> Result<Vec<crate::track::Image>, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  let mut images: Vec<crate::track::Image> = one_or_many(deserializer)?;
+  images.retain(|image| !image.url.trim().is_empty());
+  Ok(images)
+}
+
+/// Boolean that may arrive as `"1"`/`"0"`, a number, or a JSON boolean
+pub(crate) fn flag<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  let value = Value::deserialize(deserializer)?;
+  Ok(match value {
+    Value::Bool(b) => b,
+    value => value_as_u64(&value).is_some_and(|n| n != 0),
+  })
+}
+
+/// Whether Last.fm sent nothing in place of an object or list
+fn is_blank(value: &Value) -> bool {
+  match value {
+    Value::Null => true,
+    Value::String(s) => s.trim().is_empty(),
+    _ => false,
+  }
+}
+
+/// Nested object that is `None` whenever it doesn't match the expected shape
+///
+/// Under `strict-schema` only null and empty strings become `None`.
+#[cfg(any(feature = "models", feature = "spotify"))]
+pub(crate) fn option<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+  D: Deserializer<'de>,
+  T: DeserializeOwned,
+{
+  let value = Value::deserialize(deserializer)?;
+  if cfg!(feature = "strict-schema") && !is_blank(&value) {
+    return T::deserialize(value).map(Some).map_err(D::Error::custom);
+  }
+  Ok(T::deserialize(value).ok())
+}
+
+/// Array that may be collapsed into a single object, or be empty/absent
+///
+/// Entries that don't match the expected shape are skipped, except under
+/// `strict-schema`, where they fail the list.
+pub(crate) fn one_or_many<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+  D: Deserializer<'de>,
+  T: DeserializeOwned,
+{
+  let value = Value::deserialize(deserializer)?;
+  if cfg!(feature = "strict-schema") && !is_blank(&value) {
+    let items = match value {
+      Value::Array(items) => items,
+      value => vec![value],
+    };
+    return items
+      .into_iter()
+      .map(|item| T::deserialize(item).map_err(D::Error::custom))
+      .collect();
+  }
+  Ok(match value {
+    Value::Array(items) => items
+      .into_iter()
+      .filter_map(|item| T::deserialize(item).ok())
+      .collect(),
+    Value::Object(_) => T::deserialize(value).into_iter().collect(),
+    _ => Vec::new(),
+  })
+}
+
+/// Catch-all map of unmodelled fields, which must be empty under
+/// `strict-schema`
+#[cfg(all(feature = "models", feature = "strict-schema"))]
+pub(crate) fn no_extra<'de, D>(deserializer: D) -> Result<serde_json::Map<String, Value>, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  let extra = serde_json::Map::deserialize(deserializer)?;
+  if extra.is_empty() {
+    Ok(extra)
+  } else {
+    let names: Vec<&str> = extra.keys().map(String::as_str).collect();
+    Err(D::Error::custom(format!(
+      "unknown fields: {}",
+      names.join(", ")
+    )))
+  }
+}
+
+/// Duration encoded as milliseconds (track.getInfo)
+#[cfg(feature = "models")]
+pub(crate) mod duration_millis {
+  use std::time::Duration;
+
+  use serde::{Deserializer, Serializer};
+
+  pub fn serialize<S>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer,
+  {
+    match duration {
+      Some(duration) => serializer.serialize_some(&(duration.as_millis() as u64)),
+      None => serializer.serialize_none(),
+    }
+  }
+
+  pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    let millis: Option<u64> = super::option_number(deserializer)?;
+    Ok(millis.filter(|ms| *ms > 0).map(Duration::from_millis))
+  }
+}
+
+/// Duration encoded as whole seconds (scrobble and now playing submissions)
+pub(crate) mod duration_secs {
+  use std::time::Duration;
+
+  use serde::{Deserializer, Serializer};
+
+  pub fn serialize<S>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer,
+  {
+    match duration {
+      Some(duration) => serializer.serialize_some(&duration.as_secs()),
+      None => serializer.serialize_none(),
+    }
+  }
+
+  pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    let secs: Option<u64> = super::option_number(deserializer)?;
+    Ok(secs.map(Duration::from_secs))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  #[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+  use crate::scrobble::ScrobbleResponse;
+  #[cfg(feature = "metadata")]
+  use crate::track::TrackInfoResponse;
+  use proptest::prelude::*;
+
+  #[derive(Debug, Deserialize)]
+  #[cfg_attr(feature = "strict-schema", serde(deny_unknown_fields))]
+  struct Numbers {
+    #[serde(deserialize_with = "number", default)]
+    count: u64,
+    #[serde(deserialize_with = "option_number", default)]
+    maybe: Option<u32>,
+  }
+
+  #[derive(Debug, Deserialize)]
+  struct Items {
+    #[serde(deserialize_with = "one_or_many", default)]
+    item: Vec<Numbers>,
+  }
+
+  #[test]
+  fn test_number_shapes() {
+    let parsed: Numbers = serde_json::from_str(r#"{"count":"42","maybe":7}"#).unwrap();
+    assert_eq!(parsed.count, 42);
+    assert_eq!(parsed.maybe, Some(7));
+
+    let parsed: Numbers = serde_json::from_str(r#"{"count":42.0,"maybe":""}"#).unwrap();
+    assert_eq!(parsed.count, 42);
+    assert_eq!(parsed.maybe, None);
+
+    let parsed: Numbers = serde_json::from_str(r#"{"count":null,"maybe":"99999999999"}"#).unwrap();
+    assert_eq!(parsed.count, 0);
+    assert_eq!(parsed.maybe, None);
+
+    let parsed: Numbers = serde_json::from_str("{}").unwrap();
+    assert_eq!(parsed.count, 0);
+  }
+
+  #[test]
+  fn test_one_or_many_shapes() {
+    let parsed: Items = serde_json::from_str(r#"{"item":{"count":"1"}}"#).unwrap();
+    assert_eq!(parsed.item.len(), 1);
+
+    let parsed: Items = serde_json::from_str(r#"{"item":[{"count":1},{"count":2}]}"#).unwrap();
+    assert_eq!(parsed.item.len(), 2);
+
+    let parsed: Items = serde_json::from_str(r#"{"item":""}"#).unwrap();
+    assert!(parsed.item.is_empty());
+
+    #[cfg(not(feature = "strict-schema"))]
+    {
+      let parsed: Items = serde_json::from_str(r#"{"item":[1,{"count":3}]}"#).unwrap();
+      assert_eq!(parsed.item.len(), 1);
+    }
+  }
+
+  #[cfg(feature = "strict-schema")]
+  #[test]
+  fn test_strict_schema_reports_mismatches() {
+    assert!(serde_json::from_str::<Items>(r#"{"item":[1,{"count":3}]}"#).is_err());
+    assert!(serde_json::from_str::<Items>(r#"{"item":{"count":1,"other":2}}"#).is_err());
+
+    #[derive(Debug, Deserialize)]
+    struct Nested {
+      #[serde(deserialize_with = "option", default)]
+      inner: Option<Items>,
+    }
+    assert!(serde_json::from_str::<Nested>(r#"{"inner":""}"#)
+      .unwrap()
+      .inner
+      .is_none());
+    assert!(serde_json::from_str::<Nested>(r#"{"inner":{"item":[{"count":"x"},true]}}"#).is_err());
+  }
+
+  #[cfg(all(feature = "metadata", not(feature = "strict-schema")))]
+  #[test]
+  fn test_track_info_inconsistent_shapes() {
+    let json = r##"{"track":{
+      "name":"Wesley's Theory",
+      "url":"https://www.last.fm/music/Kendrick+Lamar/_/Wesley%27s+Theory",
+      "duration":287000,
+      "streamable":"0",
+      "listeners":"1000",
+      "playcount":2000,
+      "artist":{"name":"Kendrick Lamar"},
+      "album":{"artist":"Kendrick Lamar","title":"To Pimp a Butterfly",
+        "image":{"#text":"https://example.com/a.png","size":"small"},
+        "@attr":{"position":1}},
+      "toptags":{"tag":{"name":"hip-hop","url":"https://www.last.fm/tag/hip-hop"}},
+      "wiki":""
+    }}"##;
+
+    let track = serde_json::from_str::<TrackInfoResponse>(json)
+      .unwrap()
+      .track;
+    assert_eq!(track.duration, Some(std::time::Duration::from_secs(287)));
+    assert!(track.streamable.is_none());
+    assert_eq!(track.listeners, 1000);
+    assert_eq!(track.playcount, 2000);
+    let album = track.album.expect("album present");
+    assert_eq!(album.image.len(), 1);
+    assert_eq!(album.attr.expect("attr present").position, "1");
+    assert_eq!(track.toptags.expect("tags present").tag.len(), 1);
+    assert!(track.wiki.is_none());
+  }
+
+  #[test]
+  fn test_submissions_and_errors_accept_strings() {
+    let scrobble: crate::Scrobble = serde_json::from_str(
+      r#"{"artist":"Artist","track":"Track","timestamp":1700000000,"track_number":"3","duration":"200"}"#,
+    )
+    .unwrap();
+    assert_eq!(scrobble.track_number, Some(3));
+    assert_eq!(scrobble.duration, Some(std::time::Duration::from_secs(200)));
+
+    let now_playing: crate::NowPlaying =
+      serde_json::from_str(r#"{"artist":"Artist","track":"Track","track_number":""}"#).unwrap();
+    assert_eq!(now_playing.track_number, None);
+
+    #[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+    {
+      let result =
+        crate::response::parse::<Numbers>(r#"{"error":"29","message":"Rate Limit Exceded"}"#);
+      assert!(matches!(result, Err(crate::Error::RateLimited { .. })));
+    }
+  }
+
+  #[test]
+  fn test_duration_units() {
+    use std::time::Duration;
+
+    let scrobble = crate::Scrobble::new("Artist", "Track", 1_700_000_000)
+      .with_duration(Duration::from_millis(287_900));
+    let json = serde_json::to_value(&scrobble).unwrap();
+    assert_eq!(json["duration"], 287);
+
+    #[cfg(feature = "metadata")]
+    {
+      let track: crate::TrackInfo =
+        serde_json::from_str(r#"{"name":"Track","artist":{"name":"Artist"},"duration":"0"}"#)
+          .unwrap();
+      assert_eq!(track.duration, None);
+    }
+  }
+
+  #[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+  fn arb_json() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+      Just(Value::Null),
+      any::<bool>().prop_map(Value::Bool),
+      any::<i64>().prop_map(Value::from),
+      any::<f64>().prop_map(Value::from),
+      "[0-9]{0,24}".prop_map(Value::String),
+      ".{0,12}".prop_map(Value::String),
+    ];
+    let keys = prop::sample::select(vec![
+      "track",
+      "name",
+      "mbid",
+      "url",
+      "duration",
+      "streamable",
+      "listeners",
+      "playcount",
+      "artist",
+      "album",
+      "title",
+      "image",
+      "#text",
+      "size",
+      "@attr",
+      "position",
+      "toptags",
+      "tag",
+      "wiki",
+      "summary",
+      "userloved",
+      "userplaycount",
+      "scrobbles",
+      "accepted",
+      "ignored",
+      "error",
+      "message",
+    ]);
+    leaf.prop_recursive(6, 128, 8, move |inner| {
+      prop_oneof![
+        prop::collection::vec(inner.clone(), 0..6).prop_map(Value::Array),
+        prop::collection::btree_map(keys.clone(), inner, 0..10).prop_map(|map| Value::Object(
+          map.into_iter().map(|(k, v)| (k.to_string(), v)).collect()
+        )),
+      ]
+    })
+  }
+
+  proptest! {
+    #[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+    #[test]
+    fn prop_arbitrary_json_never_panics(value in arb_json()) {
+      let body = value.to_string();
+      #[cfg(feature = "metadata")]
+      let _ = crate::response::parse::<TrackInfoResponse>(&body);
+      let _ = crate::response::parse::<ScrobbleResponse>(&body);
+      #[cfg(feature = "auth-flow")]
+      let _ = crate::response::parse::<crate::auth::SessionResponse>(&body);
+    }
+
+    #[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+    #[test]
+    fn prop_arbitrary_text_never_panics(body in ".{0,256}") {
+      #[cfg(feature = "metadata")]
+      let _ = crate::response::parse::<TrackInfoResponse>(&body);
+      let _ = crate::response::parse::<ScrobbleResponse>(&body);
+    }
+
+    #[test]
+    fn prop_numbers_and_strings_agree(n in any::<u64>()) {
+      let from_number: Numbers = serde_json::from_value(serde_json::json!({ "count": n })).unwrap();
+      let from_string: Numbers = serde_json::from_value(serde_json::json!({ "count": n.to_string() })).unwrap();
+      prop_assert_eq!(from_number.count, n);
+      prop_assert_eq!(from_string.count, n);
+    }
+  }
+}
@@ -0,0 +1,357 @@
+//! Persistent cache for track lookups
+//!
+//! Enabled by the `disk-cache` feature.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::cache::CacheStats;
+use crate::error::Result;
+use crate::track::TrackInfo;
+
+/// How long entries stay fresh unless [`DiskCache::with_ttl`] says otherwise
+const DEFAULT_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Entries kept unless [`DiskCache::with_max_entries`] says otherwise
+const DEFAULT_MAX_ENTRIES: usize = 100_000;
+
+/// Stored time and sequence number in front of each entry's JSON
+const HEADER_LEN: usize = 16;
+
+/// Track info stored on disk, so it survives restarts and works offline
+///
+/// Attach one to a client with
+/// [`ClientBuilder::disk_cache`](crate::ClientBuilder::disk_cache).
+/// Entries older than the TTL are refetched, and once the cache holds more
+/// than its size bound the oldest entries are dropped. Storage errors while
+/// reading or writing entries are treated as misses, so a broken cache only
+/// costs requests.
+///
+/// # Example
+///
+/// ```no_run
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::time::Duration;
+/// use last_fm_rs::{Client, DiskCache};
+///
+/// let cache = DiskCache::open("lastfm-cache")?
+///   .with_ttl(Duration::from_secs(24 * 60 * 60))
+///   .with_max_entries(10_000);
+/// let client = Client::builder("api_key", "secret").disk_cache(cache).build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct DiskCache {
+  db: sled::Db,
+  /// Entry key to stored time (big-endian unix seconds), write sequence
+  /// number and track JSON
+  entries: sled::Tree,
+  /// Write sequence number followed by entry key, oldest first
+  by_age: sled::Tree,
+  len: AtomicUsize,
+  hits: AtomicU64,
+  misses: AtomicU64,
+  ttl: Duration,
+  max_entries: usize,
+}
+
+impl DiskCache {
+  /// Open or create a cache in the directory at `path`
+  ///
+  /// A cache directory can only be open in one process at a time.
+  pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+    Self::from_db(sled::open(path)?)
+  }
+
+  fn from_db(db: sled::Db) -> Result<Self> {
+    let entries = db.open_tree("track_info")?;
+    let by_age = db.open_tree("track_info_by_age")?;
+    Ok(Self {
+      db,
+      len: AtomicUsize::new(entries.len()),
+      hits: AtomicU64::new(0),
+      misses: AtomicU64::new(0),
+      entries,
+      by_age,
+      ttl: DEFAULT_TTL,
+      max_entries: DEFAULT_MAX_ENTRIES,
+    })
+  }
+
+  /// Refetch entries older than `ttl` (default 7 days)
+  pub fn with_ttl(mut self, ttl: Duration) -> Self {
+    self.ttl = ttl;
+    self
+  }
+
+  /// Keep at most `max_entries` tracks (default 100,000)
+  pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+    self.max_entries = max_entries;
+    self
+  }
+
+  /// Drop every entry
+  pub fn clear(&self) -> Result<()> {
+    self.entries.clear()?;
+    self.by_age.clear()?;
+    self.len.store(0, Ordering::Relaxed);
+    Ok(())
+  }
+
+  /// Drop the entries for a track, for every username and language it was
+  /// looked up with, so the next lookup asks Last.fm again
+  ///
+  /// Returns how many entries were dropped. Artist and track names must
+  /// match the ones looked up exactly.
+  pub fn invalidate(&self, artist: &str, track: &str) -> Result<usize> {
+    let mut removed = 0;
+    for entry in self.entries.scan_prefix(track_prefix(artist, track)) {
+      let (key, _) = entry?;
+      if self.remove(&key)? {
+        removed += 1;
+      }
+    }
+    Ok(removed)
+  }
+
+  /// Hits, misses and size; expired entries count as misses
+  pub fn stats(&self) -> CacheStats {
+    CacheStats {
+      hits: self.hits.load(Ordering::Relaxed),
+      misses: self.misses.load(Ordering::Relaxed),
+      len: self.len.load(Ordering::Relaxed),
+      capacity: self.max_entries,
+    }
+  }
+
+  /// Bytes the cache takes up on disk
+  pub fn size_on_disk(&self) -> Result<u64> {
+    Ok(self.db.size_on_disk()?)
+  }
+
+  pub(crate) fn get(&self, key: &[u8]) -> Option<TrackInfo> {
+    let info = self.lookup(key);
+    let counter = if info.is_some() {
+      &self.hits
+    } else {
+      &self.misses
+    };
+    counter.fetch_add(1, Ordering::Relaxed);
+    info
+  }
+
+  fn lookup(&self, key: &[u8]) -> Option<TrackInfo> {
+    let value = self.entries.get(key).ok()??;
+    let (stored, _) = header(&value)?;
+    if now().saturating_sub(stored) >= self.ttl.as_secs() {
+      let _ = self.remove(key);
+      return None;
+    }
+    serde_json::from_slice(&value[HEADER_LEN..]).ok()
+  }
+
+  pub(crate) fn insert(&self, key: &[u8], info: &TrackInfo) -> Result<()> {
+    let sequence = self.db.generate_id()?;
+    let mut value = now().to_be_bytes().to_vec();
+    value.extend_from_slice(&sequence.to_be_bytes());
+    serde_json::to_writer(&mut value, info)?;
+
+    match self.entries.insert(key, value)? {
+      Some(previous) => {
+        if let Some((_, previous)) = header(&previous) {
+          self.by_age.remove(age_key(previous, key))?;
+        }
+      }
+      None => {
+        self.len.fetch_add(1, Ordering::Relaxed);
+      }
+    }
+    self.by_age.insert(age_key(sequence, key), &[])?;
+
+    while self.len.load(Ordering::Relaxed) > self.max_entries {
+      let Some((oldest, _)) = self.by_age.pop_min()? else {
+        break;
+      };
+      if self.entries.remove(&oldest[8..])?.is_some() {
+        self.len.fetch_sub(1, Ordering::Relaxed);
+      }
+    }
+    Ok(())
+  }
+
+  /// Remove an entry; false if there was none
+  fn remove(&self, key: &[u8]) -> Result<bool> {
+    let Some(value) = self.entries.remove(key)? else {
+      return Ok(false);
+    };
+    self.len.fetch_sub(1, Ordering::Relaxed);
+    if let Some((_, sequence)) = header(&value) {
+      self.by_age.remove(age_key(sequence, key))?;
+    }
+    Ok(true)
+  }
+}
+
+/// Entry key for a lookup: length-prefixed parts, artist and track first so
+/// `invalidate` can find every lookup of a track by prefix
+pub(crate) fn entry_key(
+  artist: &str,
+  track: &str,
+  username: Option<&str>,
+  lang: Option<&str>,
+) -> Vec<u8> {
+  let mut key = track_prefix(artist, track);
+  for part in [username, lang] {
+    match part {
+      Some(part) => {
+        key.push(1);
+        push_part(&mut key, part);
+      }
+      None => key.push(0),
+    }
+  }
+  key
+}
+
+/// Start of the key of every lookup of one track
+fn track_prefix(artist: &str, track: &str) -> Vec<u8> {
+  let mut prefix = Vec::with_capacity(16 + artist.len() + track.len());
+  push_part(&mut prefix, artist);
+  push_part(&mut prefix, track);
+  prefix
+}
+
+fn push_part(key: &mut Vec<u8>, part: &str) {
+  key.extend_from_slice(&(part.len() as u64).to_be_bytes());
+  key.extend_from_slice(part.as_bytes());
+}
+
+fn now() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+fn header(value: &[u8]) -> Option<(u64, u64)> {
+  let stored = value.get(..8)?.try_into().ok()?;
+  let sequence = value.get(8..HEADER_LEN)?.try_into().ok()?;
+  Some((u64::from_be_bytes(stored), u64::from_be_bytes(sequence)))
+}
+
+fn age_key(sequence: u64, key: &[u8]) -> Vec<u8> {
+  let mut age_key = sequence.to_be_bytes().to_vec();
+  age_key.extend_from_slice(key);
+  age_key
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::client::Client;
+  use crate::test_server::{Reply, TestServer};
+
+  fn track(name: &str) -> TrackInfo {
+    serde_json::from_str(&format!(
+      r#"{{"name":"{}","artist":{{"name":"Cher"}}}}"#,
+      name
+    ))
+    .unwrap()
+  }
+
+  fn temp_cache() -> DiskCache {
+    DiskCache::from_db(sled::Config::new().temporary(true).open().unwrap()).unwrap()
+  }
+
+  #[test]
+  fn test_entries_round_trip_and_expire() {
+    let cache = temp_cache();
+    cache.insert(b"believe", &track("Believe")).unwrap();
+    assert_eq!(cache.get(b"believe"), Some(track("Believe")));
+    assert_eq!(cache.get(b"other"), None);
+
+    let cache = cache.with_ttl(Duration::ZERO);
+    assert_eq!(cache.get(b"believe"), None);
+    assert_eq!(cache.len.load(Ordering::Relaxed), 0);
+    assert!(cache.by_age.is_empty());
+  }
+
+  #[test]
+  fn test_invalidate_and_stats() {
+    let cache = temp_cache();
+    let believe = entry_key("Cher", "Believe", None, None);
+    let believe_de = entry_key("Cher", "Believe", Some("rj"), Some("de"));
+    let believe_remix = entry_key("Cher", "Believe (Remix)", None, None);
+    assert_ne!(
+      entry_key("Che", "rBelieve", None, None),
+      entry_key("Cher", "Believe", None, None)
+    );
+    assert_ne!(
+      entry_key("Cher", "Believe", Some("de"), None),
+      entry_key("Cher", "Believe", None, Some("de"))
+    );
+    for key in [&believe, &believe_de, &believe_remix] {
+      cache.insert(key, &track("Believe")).unwrap();
+    }
+
+    assert_eq!(cache.invalidate("Cher", "Believe").unwrap(), 2);
+    assert_eq!(cache.get(&believe), None);
+    assert!(cache.get(&believe_remix).is_some());
+    assert_eq!(
+      cache.stats(),
+      CacheStats {
+        hits: 1,
+        misses: 1,
+        len: 1,
+        capacity: DEFAULT_MAX_ENTRIES
+      }
+    );
+
+    cache.clear().unwrap();
+    assert_eq!(cache.get(&believe_remix), None);
+    assert_eq!(cache.stats().len, 0);
+    assert!(cache.by_age.is_empty());
+  }
+
+  #[test]
+  fn test_oldest_entries_are_evicted() {
+    let cache = temp_cache().with_max_entries(2);
+    cache.insert(b"a", &track("A")).unwrap();
+    cache.insert(b"b", &track("B")).unwrap();
+    cache.insert(b"a", &track("A2")).unwrap();
+    cache.insert(b"c", &track("C")).unwrap();
+
+    assert_eq!(cache.len.load(Ordering::Relaxed), 2);
+    assert_eq!(cache.entries.len(), 2);
+    assert_eq!(cache.by_age.len(), 2);
+    assert_eq!(cache.get(b"b"), None);
+    assert_eq!(cache.get(b"a"), Some(track("A2")));
+  }
+
+  #[tokio::test]
+  async fn test_client_reads_through_the_cache() {
+    // Answers a single request; a second one would fail to connect
+    let server = TestServer::replies(vec![Reply::ok(
+      r#"{"track":{"name":"Believe","artist":{"name":"Cher"}}}"#,
+    )]);
+
+    let client = Client::builder("key", "secret")
+      .api_base(server.url("/2.0/"))
+      .retry_policy(crate::NoRetry)
+      .disk_cache(temp_cache())
+      .build()
+      .unwrap();
+    let first = client
+      .track_get_info("Cher", "Believe", None)
+      .await
+      .unwrap();
+    assert_eq!(
+      client
+        .track_get_info("Cher", "Believe", None)
+        .await
+        .unwrap(),
+      first
+    );
+  }
+}
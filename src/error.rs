@@ -1,25 +1,352 @@
+use std::sync::Arc;
+
+#[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+use serde::Deserialize;
+
+#[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+use crate::de;
+#[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+use crate::params::Params;
+#[cfg(any(feature = "scrobble", feature = "auth-flow"))]
+use crate::signature;
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-#[derive(Error, Debug)]
+/// Errors returned by the client
+///
+/// Cheap to clone, so one failed request can be reported to every caller
+/// that was waiting on it.
+///
+/// Errors from an API call carry `method`, such as `track.getInfo`, and
+/// `params`, a `name=value` summary of the call's parameters with the API
+/// key, session key, auth token and signature left out. Both are empty when
+/// the error didn't come from a call.
+#[derive(Error, Debug, Clone)]
 #[non_exhaustive]
 pub enum Error {
-  #[error("HTTP request failed: {0}")]
-  Http(#[from] reqwest::Error),
+  #[cfg(feature = "client")]
+  #[error("HTTP request failed{}: {source}", context(method, params))]
+  Http {
+    method: String,
+    params: String,
+    source: Arc<reqwest::Error>,
+  },
+
+  #[error("Request timed out")]
+  Timeout,
+
+  /// The call, retries included, outlasted the client's deadline
+  #[error("Deadline exceeded")]
+  DeadlineExceeded,
+
+  #[error("JSON parsing failed{}: {source}", context(method, params))]
+  Json {
+    method: String,
+    params: String,
+    source: Arc<serde_json::Error>,
+  },
 
-  #[error("JSON parsing failed: {0}")]
-  Json(#[from] serde_json::Error),
+  /// Last.fm rejected a call
+  #[error("Last.fm API error {code}{}: {message}", context(method, params))]
+  Api {
+    method: String,
+    params: String,
+    code: u32,
+    message: String,
+  },
 
-  #[error("Last.fm API error: {0}")]
-  Api(String),
+  /// Last.fm error 13: the `api_sig` didn't match the request
+  ///
+  /// `signed` names the parameters that went into the signature, in signing
+  /// order, and `hints` lists likely causes worth checking.
+  #[error(
+    "Invalid signature{}: {message} (signed {}; {})",
+    context(method, params),
+    signed.join(", "),
+    hints.join("; ")
+  )]
+  InvalidSignature {
+    method: String,
+    params: String,
+    message: String,
+    signed: Vec<String>,
+    hints: Vec<&'static str>,
+  },
 
-  #[error("Authentication failed: {0}")]
-  Auth(String),
+  /// Last.fm error 29: too many requests from this API key
+  #[error("Rate limit exceeded{}", context(method, params))]
+  RateLimited { method: String, params: String },
+
+  /// Missing or rejected credentials; `code` is the Last.fm error code
+  /// when Last.fm rejected them: 10 (invalid API key) or 26 (suspended API
+  /// key) from any call, or whatever `auth.getSession` failed with
+  #[error("Authentication failed{}: {message}", context(method, params))]
+  Auth {
+    method: String,
+    params: String,
+    code: Option<u32>,
+    message: String,
+  },
+
+  /// Last.fm answered with valid JSON that lacks what the call needs
+  #[error("Unexpected response{}: {message}", context(method, params))]
+  UnexpectedResponse {
+    method: String,
+    params: String,
+    message: String,
+  },
 
   #[error("Invalid parameter: {0}")]
   InvalidParameter(String),
 
+  #[error("I/O failed: {0}")]
+  Io(#[source] Arc<std::io::Error>),
+
   #[error("URL parsing failed: {0}")]
   UrlParse(#[from] url::ParseError),
 }
+
+/// ` from method (params)` for errors from an API call, or nothing
+fn context(method: &str, params: &str) -> String {
+  match (method, params) {
+    ("", _) => String::new(),
+    (method, "") => format!(" from {}", method),
+    (method, params) => format!(" from {} ({})", method, params),
+  }
+}
+
+impl Error {
+  /// Credentials missing or unusable before any call was made
+  #[cfg(feature = "client")]
+  pub(crate) fn auth(message: impl Into<String>) -> Self {
+    Error::Auth {
+      method: String::new(),
+      params: String::new(),
+      code: None,
+      message: message.into(),
+    }
+  }
+
+  /// Rate limited, before the failing call is known
+  #[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+  pub(crate) fn rate_limited() -> Self {
+    Error::RateLimited {
+      method: String::new(),
+      params: String::new(),
+    }
+  }
+
+  /// Report an API error from an auth method as [`Error::Auth`], keeping
+  /// its code
+  #[cfg(feature = "auth-flow")]
+  pub(crate) fn into_auth(self) -> Self {
+    match self {
+      Error::Api {
+        method,
+        params,
+        code,
+        message,
+      } => Error::Auth {
+        method,
+        params,
+        code: Some(code),
+        message,
+      },
+      error => error,
+    }
+  }
+
+  /// Response that parsed but doesn't fit the call, before the call is known
+  #[cfg(feature = "scrobble")]
+  pub(crate) fn unexpected_response(message: impl Into<String>) -> Self {
+    Error::UnexpectedResponse {
+      method: String::new(),
+      params: String::new(),
+      message: message.into(),
+    }
+  }
+
+  /// Whether the failure is likely temporary, so retrying may succeed
+  ///
+  /// True for timeouts, rate limiting, connection failures, 5xx/429
+  /// responses and Last.fm's "service offline" style error codes.
+  pub fn is_transient(&self) -> bool {
+    match self {
+      Error::Timeout | Error::RateLimited { .. } => true,
+      Error::Api { code, .. } => matches!(
+        *code,
+        OPERATION_FAILED | SERVICE_OFFLINE | TEMPORARILY_UNAVAILABLE
+      ),
+      #[cfg(feature = "client")]
+      Error::Http { source: err, .. } => {
+        err.is_connect()
+          || err.status().is_some_and(|status| {
+            status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+          })
+      }
+      _ => false,
+    }
+  }
+
+  /// Name the API call an error came from, unless it's already named
+  #[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+  pub(crate) fn with_context(mut self, call: &Params<'_>) -> Self {
+    let (method, params) = match &mut self {
+      #[cfg(feature = "client")]
+      Error::Http { method, params, .. } => (method, params),
+      Error::Json { method, params, .. }
+      | Error::Api { method, params, .. }
+      | Error::InvalidSignature { method, params, .. }
+      | Error::RateLimited { method, params }
+      | Error::Auth { method, params, .. }
+      | Error::UnexpectedResponse { method, params, .. } => (method, params),
+      _ => return self,
+    };
+    if method.is_empty() {
+      *method = call.method().to_string();
+      *params = call.summary();
+    }
+    self
+  }
+
+  /// Explain an invalid signature error with the parameters that were signed
+  #[cfg(any(feature = "scrobble", feature = "auth-flow"))]
+  pub(crate) fn with_signed(self, params: &Params<'_>) -> Self {
+    match self {
+      Error::Api {
+        method,
+        params: summary,
+        code: INVALID_SIGNATURE,
+        message,
+      } => Error::InvalidSignature {
+        method,
+        params: summary,
+        message,
+        signed: params.signed_names(),
+        hints: signature::hints(params),
+      },
+      error => error,
+    }
+  }
+
+  #[cfg(all(
+    feature = "log",
+    any(feature = "scrobble", feature = "auth-flow", feature = "metadata")
+  ))]
+  /// Message safe to log: URLs lose their query, which carries the API key
+  /// on read-only GET requests
+  pub(crate) fn redacted(&self) -> String {
+    let message = self.to_string();
+    let Error::Http { source: err, .. } = self else {
+      return message;
+    };
+    match err.url() {
+      Some(url) if url.query().is_some() => {
+        let mut redacted = url.clone();
+        redacted.set_query(None);
+        message.replace(url.as_str(), redacted.as_str())
+      }
+      _ => message,
+    }
+  }
+}
+
+#[cfg(feature = "client")]
+impl From<reqwest::Error> for Error {
+  fn from(err: reqwest::Error) -> Self {
+    if err.is_timeout() {
+      Error::Timeout
+    } else {
+      Error::Http {
+        method: String::new(),
+        params: String::new(),
+        source: Arc::new(err),
+      }
+    }
+  }
+}
+
+impl From<serde_json::Error> for Error {
+  fn from(err: serde_json::Error) -> Self {
+    Error::Json {
+      method: String::new(),
+      params: String::new(),
+      source: Arc::new(err),
+    }
+  }
+}
+
+impl From<std::io::Error> for Error {
+  fn from(err: std::io::Error) -> Self {
+    Error::Io(Arc::new(err))
+  }
+}
+
+#[cfg(feature = "disk-cache")]
+impl From<sled::Error> for Error {
+  fn from(err: sled::Error) -> Self {
+    Error::Io(Arc::new(err.into()))
+  }
+}
+
+/// Last.fm error code for a backend failure
+pub(crate) const OPERATION_FAILED: u32 = 8;
+
+/// Last.fm error code for an unknown API key
+#[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+pub(crate) const INVALID_API_KEY: u32 = 10;
+
+/// Last.fm error code for the service being offline
+pub(crate) const SERVICE_OFFLINE: u32 = 11;
+
+/// Last.fm error code for a request whose signature didn't match
+#[cfg(any(feature = "scrobble", feature = "auth-flow"))]
+pub(crate) const INVALID_SIGNATURE: u32 = 13;
+
+/// Last.fm error code for a token the user hasn't authorized yet
+#[cfg(feature = "auth-flow")]
+pub(crate) const UNAUTHORIZED_TOKEN: u32 = 14;
+
+/// Last.fm error code for a temporary backend problem
+pub(crate) const TEMPORARILY_UNAVAILABLE: u32 = 16;
+
+/// Last.fm error code for an API key that has been suspended
+#[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+pub(crate) const SUSPENDED_API_KEY: u32 = 26;
+
+/// Last.fm error code for exceeding the API rate limit
+#[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+pub(crate) const RATE_LIMIT_EXCEEDED: u32 = 29;
+
+/// Error body returned by the Last.fm API
+#[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+#[derive(Debug, Deserialize)]
+pub(crate) struct ErrorResponse {
+  #[serde(deserialize_with = "de::number", default)]
+  pub error: u32,
+  #[serde(deserialize_with = "de::string", default)]
+  pub message: String,
+}
+
+#[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+impl ErrorResponse {
+  /// Map the Last.fm error code onto the crate's error variants
+  pub fn into_error(self) -> Error {
+    match self.error {
+      RATE_LIMIT_EXCEEDED => Error::rate_limited(),
+      code @ (INVALID_API_KEY | SUSPENDED_API_KEY) => Error::Auth {
+        method: String::new(),
+        params: String::new(),
+        code: Some(code),
+        message: self.message,
+      },
+      code => Error::Api {
+        method: String::new(),
+        params: String::new(),
+        code,
+        message: self.message,
+      },
+    }
+  }
+}
@@ -0,0 +1,573 @@
+//! Scrobble history backups in CSV or JSON-lines, and playlists
+//!
+//! Both backup formats share one stable schema, [`COLUMNS`], so exports can
+//! be diffed, appended to, and read back by other tools. [`playlist`] and
+//! [`PlaylistWriter`] turn loved tracks, charts or any other track list into
+//! M3U or XSPF files for media players.
+
+use std::io::Write;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::de;
+use crate::error::Result;
+use crate::loved::LovedTrack;
+use crate::mbid::Mbid;
+use crate::recent::RecentTrack;
+use crate::stats::Ranked;
+use crate::track::TrackInfo;
+
+/// Field order shared by the CSV header and every JSON-lines record
+pub const COLUMNS: [&str; 7] = [
+  "timestamp",
+  "artist",
+  "artist_mbid",
+  "album",
+  "album_mbid",
+  "track",
+  "track_mbid",
+];
+
+/// Output format for [`Exporter`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ExportFormat {
+  /// RFC 4180 CSV with a header row
+  #[default]
+  Csv,
+  /// One JSON object per line
+  JsonLines,
+}
+
+/// One exported scrobble
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ExportRecord {
+  /// Unix timestamp of the scrobble
+  #[serde(deserialize_with = "de::number", default)]
+  pub timestamp: u64,
+  pub artist: String,
+  #[serde(deserialize_with = "de::mbid", default)]
+  pub artist_mbid: Option<Mbid>,
+  pub album: String,
+  #[serde(deserialize_with = "de::mbid", default)]
+  pub album_mbid: Option<Mbid>,
+  pub track: String,
+  #[serde(deserialize_with = "de::mbid", default)]
+  pub track_mbid: Option<Mbid>,
+}
+
+impl From<&RecentTrack> for ExportRecord {
+  fn from(track: &RecentTrack) -> Self {
+    let (album, album_mbid) = track
+      .album
+      .as_ref()
+      .map(|album| (album.title.clone(), album.mbid.clone()))
+      .unwrap_or_default();
+
+    Self {
+      timestamp: track.timestamp().unwrap_or_default(),
+      artist: track.artist.name.clone(),
+      artist_mbid: track.artist.mbid.clone(),
+      album,
+      album_mbid,
+      track: track.name.clone(),
+      track_mbid: track.mbid.clone(),
+    }
+  }
+}
+
+/// Writes [`ExportRecord`]s to any [`Write`] in the chosen format
+///
+/// # Example
+///
+/// ```
+/// use last_fm_rs::{ExportFormat, ExportRecord, Exporter};
+///
+/// let mut exporter = Exporter::new(Vec::new(), ExportFormat::Csv)?;
+/// exporter.write(&ExportRecord {
+///   timestamp: 1_700_000_000,
+///   artist: "Cher".to_string(),
+///   artist_mbid: None,
+///   album: "Believe".to_string(),
+///   album_mbid: None,
+///   track: "Believe".to_string(),
+///   track_mbid: None,
+/// })?;
+///
+/// let csv = String::from_utf8(exporter.finish()?).unwrap();
+/// assert_eq!(csv.lines().count(), 2);
+/// # Ok::<(), last_fm_rs::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct Exporter<W: Write> {
+  writer: W,
+  format: ExportFormat,
+  written: u64,
+}
+
+impl<W: Write> Exporter<W> {
+  /// Start an export, writing the CSV header if needed
+  pub fn new(mut writer: W, format: ExportFormat) -> Result<Self> {
+    if format == ExportFormat::Csv {
+      writeln!(writer, "{}", COLUMNS.join(","))?;
+    }
+    Ok(Self {
+      writer,
+      format,
+      written: 0,
+    })
+  }
+
+  /// Append one record
+  pub fn write(&mut self, record: &ExportRecord) -> Result<()> {
+    match self.format {
+      ExportFormat::Csv => {
+        let fields = [
+          record.artist.as_str(),
+          mbid_str(&record.artist_mbid),
+          &record.album,
+          mbid_str(&record.album_mbid),
+          &record.track,
+          mbid_str(&record.track_mbid),
+        ];
+        write!(self.writer, "{}", record.timestamp)?;
+        for field in fields {
+          self.writer.write_all(b",")?;
+          write_csv_field(&mut self.writer, field)?;
+        }
+        self.writer.write_all(b"\n")?;
+      }
+      ExportFormat::JsonLines => {
+        serde_json::to_writer(&mut self.writer, record)?;
+        self.writer.write_all(b"\n")?;
+      }
+    }
+    self.written += 1;
+    Ok(())
+  }
+
+  /// Number of records written so far
+  pub fn written(&self) -> u64 {
+    self.written
+  }
+
+  /// Flush and return the underlying writer
+  pub fn finish(mut self) -> Result<W> {
+    self.writer.flush()?;
+    Ok(self.writer)
+  }
+}
+
+/// Playlist file format for [`PlaylistWriter`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PlaylistFormat {
+  /// Extended M3U (`#EXTM3U`), one file path per entry
+  #[default]
+  M3u,
+  /// XML Shareable Playlist Format, version 1
+  Xspf,
+}
+
+/// One playlist track
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PlaylistEntry {
+  pub artist: String,
+  pub title: String,
+  pub album: Option<String>,
+  pub duration: Option<Duration>,
+  /// File path (M3U) or URI such as `file:///music/believe.mp3` (XSPF)
+  pub location: Option<String>,
+}
+
+impl PlaylistEntry {
+  pub fn new(artist: impl Into<String>, title: impl Into<String>) -> Self {
+    Self {
+      artist: artist.into(),
+      title: title.into(),
+      album: None,
+      duration: None,
+      location: None,
+    }
+  }
+}
+
+impl From<&LovedTrack> for PlaylistEntry {
+  fn from(track: &LovedTrack) -> Self {
+    Self::new(&track.artist.name, &track.name)
+  }
+}
+
+impl From<&RecentTrack> for PlaylistEntry {
+  fn from(track: &RecentTrack) -> Self {
+    let mut entry = Self::new(&track.artist.name, &track.name);
+    entry.album = track
+      .album
+      .as_ref()
+      .map(|album| album.title.clone())
+      .filter(|title| !title.is_empty());
+    entry
+  }
+}
+
+impl From<&TrackInfo> for PlaylistEntry {
+  fn from(track: &TrackInfo) -> Self {
+    let mut entry = Self::new(&track.artist.name, &track.name);
+    entry.album = track
+      .album
+      .as_ref()
+      .map(|album| album.title.clone())
+      .filter(|title| !title.is_empty());
+    entry.duration = track.duration;
+    entry
+  }
+}
+
+/// Entry for a [`Stats::top_tracks`](crate::stats::Stats::top_tracks) row;
+/// artist rankings have no title, so they make poor playlist entries
+impl From<&Ranked> for PlaylistEntry {
+  fn from(ranked: &Ranked) -> Self {
+    Self::new(&ranked.artist, ranked.title.clone().unwrap_or_default())
+  }
+}
+
+type Resolver<'a> = Box<dyn FnMut(&PlaylistEntry) -> Option<String> + 'a>;
+
+/// Writes [`PlaylistEntry`]s to any [`Write`] as an M3U or XSPF playlist
+///
+/// M3U lists files, so entries without a location are skipped there (see
+/// [`skipped`](Self::skipped)); XSPF keeps them for players that match
+/// tracks by artist and title.
+///
+/// # Example
+///
+/// ```
+/// use last_fm_rs::export::{PlaylistEntry, PlaylistFormat, PlaylistWriter};
+///
+/// let mut playlist = PlaylistWriter::new(Vec::new(), PlaylistFormat::M3u)?
+///   .with_resolver(|entry| Some(format!("/music/{}/{}.mp3", entry.artist, entry.title)));
+/// playlist.write(&PlaylistEntry::new("Cher", "Believe"))?;
+///
+/// let m3u = String::from_utf8(playlist.finish()?).unwrap();
+/// assert_eq!(m3u, "#EXTM3U\n#EXTINF:-1,Cher - Believe\n/music/Cher/Believe.mp3\n");
+/// # Ok::<(), last_fm_rs::Error>(())
+/// ```
+pub struct PlaylistWriter<'a, W: Write> {
+  writer: W,
+  format: PlaylistFormat,
+  resolver: Option<Resolver<'a>>,
+  written: u64,
+  skipped: u64,
+}
+
+impl<'a, W: Write> PlaylistWriter<'a, W> {
+  /// Start a playlist, writing the format's header
+  pub fn new(writer: W, format: PlaylistFormat) -> Result<Self> {
+    Self::with_title(writer, format, None)
+  }
+
+  /// Start a playlist named `title`
+  pub fn titled(writer: W, format: PlaylistFormat, title: &str) -> Result<Self> {
+    Self::with_title(writer, format, Some(title))
+  }
+
+  fn with_title(mut writer: W, format: PlaylistFormat, title: Option<&str>) -> Result<Self> {
+    match format {
+      PlaylistFormat::M3u => {
+        writer.write_all(b"#EXTM3U\n")?;
+        if let Some(title) = title {
+          writeln!(writer, "#PLAYLIST:{}", single_line(title))?;
+        }
+      }
+      PlaylistFormat::Xspf => {
+        writer.write_all(b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")?;
+        writer.write_all(b"<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n")?;
+        if let Some(title) = title {
+          write_element(&mut writer, 1, "title", title)?;
+        }
+        writer.write_all(b"  <trackList>\n")?;
+      }
+    }
+    Ok(Self {
+      writer,
+      format,
+      resolver: None,
+      written: 0,
+      skipped: 0,
+    })
+  }
+
+  /// Look up the location of entries that don't have one, e.g. by searching
+  /// a local music library
+  pub fn with_resolver(
+    mut self,
+    resolver: impl FnMut(&PlaylistEntry) -> Option<String> + 'a,
+  ) -> Self {
+    self.resolver = Some(Box::new(resolver));
+    self
+  }
+
+  /// Append one entry
+  pub fn write(&mut self, entry: &PlaylistEntry) -> Result<()> {
+    let resolved = match (&entry.location, &mut self.resolver) {
+      (None, Some(resolver)) => resolver(entry),
+      _ => None,
+    };
+    let location = entry.location.as_deref().or(resolved.as_deref());
+
+    match self.format {
+      PlaylistFormat::M3u => {
+        let Some(location) = location else {
+          self.skipped += 1;
+          return Ok(());
+        };
+        let seconds = entry
+          .duration
+          .map_or(-1, |duration| duration.as_secs() as i64);
+        writeln!(
+          self.writer,
+          "#EXTINF:{},{} - {}",
+          seconds,
+          single_line(&entry.artist),
+          single_line(&entry.title)
+        )?;
+        writeln!(self.writer, "{}", single_line(location))?;
+      }
+      PlaylistFormat::Xspf => {
+        self.writer.write_all(b"    <track>\n")?;
+        if let Some(location) = location {
+          write_element(&mut self.writer, 3, "location", location)?;
+        }
+        write_element(&mut self.writer, 3, "creator", &entry.artist)?;
+        write_element(&mut self.writer, 3, "title", &entry.title)?;
+        if let Some(album) = &entry.album {
+          write_element(&mut self.writer, 3, "album", album)?;
+        }
+        if let Some(duration) = entry.duration {
+          write_element(
+            &mut self.writer,
+            3,
+            "duration",
+            &duration.as_millis().to_string(),
+          )?;
+        }
+        self.writer.write_all(b"    </track>\n")?;
+      }
+    }
+    self.written += 1;
+    Ok(())
+  }
+
+  /// Number of entries written so far
+  pub fn written(&self) -> u64 {
+    self.written
+  }
+
+  /// Number of entries left out of an M3U playlist for lack of a location
+  pub fn skipped(&self) -> u64 {
+    self.skipped
+  }
+
+  /// Close the playlist, flush, and return the underlying writer
+  pub fn finish(mut self) -> Result<W> {
+    if self.format == PlaylistFormat::Xspf {
+      self.writer.write_all(b"  </trackList>\n</playlist>\n")?;
+    }
+    self.writer.flush()?;
+    Ok(self.writer)
+  }
+}
+
+impl<W: Write> std::fmt::Debug for PlaylistWriter<'_, W> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("PlaylistWriter")
+      .field("format", &self.format)
+      .field("written", &self.written)
+      .field("skipped", &self.skipped)
+      .finish_non_exhaustive()
+  }
+}
+
+/// Write `entries` as a complete playlist and return the writer
+///
+/// For location lookups or a playlist title, use [`PlaylistWriter`].
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::fs::File;
+/// use last_fm_rs::export::{self, PlaylistEntry, PlaylistFormat};
+/// use last_fm_rs::Client;
+///
+/// let client = Client::new("api_key", "secret");
+/// let loved = client.loved_tracks("rj", 1, 50).await?;
+///
+/// let file = File::create("loved.xspf")?;
+/// export::playlist(file, PlaylistFormat::Xspf, loved.items.iter().map(PlaylistEntry::from))?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn playlist<W, I>(writer: W, format: PlaylistFormat, entries: I) -> Result<W>
+where
+  W: Write,
+  I: IntoIterator<Item = PlaylistEntry>,
+{
+  let mut playlist = PlaylistWriter::new(writer, format)?;
+  for entry in entries {
+    playlist.write(&entry)?;
+  }
+  playlist.finish()
+}
+
+/// M3U is line-based, so line breaks in names would start a bogus entry
+fn single_line(value: &str) -> String {
+  value.replace(['\r', '\n'], " ")
+}
+
+/// Write `<name>value</name>` on its own line, indented `depth` levels
+fn write_element<W: Write>(
+  writer: &mut W,
+  depth: usize,
+  name: &str,
+  value: &str,
+) -> std::io::Result<()> {
+  write!(writer, "{:indent$}<{}>", "", name, indent = depth * 2)?;
+  for c in value.chars() {
+    match c {
+      '&' => writer.write_all(b"&amp;")?,
+      '<' => writer.write_all(b"&lt;")?,
+      '>' => writer.write_all(b"&gt;")?,
+      c => write!(writer, "{}", c)?,
+    }
+  }
+  writeln!(writer, "</{}>", name)
+}
+
+/// CSV column value for an optional MBID
+fn mbid_str(mbid: &Option<Mbid>) -> &str {
+  mbid.as_ref().map_or("", Mbid::as_str)
+}
+
+/// Write a field, quoting it only when it contains a delimiter, quote or newline
+fn write_csv_field<W: Write>(writer: &mut W, field: &str) -> std::io::Result<()> {
+  if !field.contains([',', '"', '\n', '\r']) {
+    return writer.write_all(field.as_bytes());
+  }
+
+  writer.write_all(b"\"")?;
+  for (i, part) in field.split('"').enumerate() {
+    if i > 0 {
+      writer.write_all(b"\"\"")?;
+    }
+    writer.write_all(part.as_bytes())?;
+  }
+  writer.write_all(b"\"")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn record() -> ExportRecord {
+    ExportRecord {
+      timestamp: 1_700_000_000,
+      artist: "Crosby, Stills & Nash".to_string(),
+      artist_mbid: None,
+      album: "The \"Best\" Of".to_string(),
+      album_mbid: None,
+      track: "Suite: Judy Blue Eyes".to_string(),
+      track_mbid: Some(Mbid::parse("b1a9c0e9-d987-4042-ae91-78d6a3267d69").unwrap()),
+    }
+  }
+
+  #[test]
+  fn test_csv_escaping() {
+    let mut exporter = Exporter::new(Vec::new(), ExportFormat::Csv).unwrap();
+    exporter.write(&record()).unwrap();
+    assert_eq!(exporter.written(), 1);
+
+    let csv = String::from_utf8(exporter.finish().unwrap()).unwrap();
+    assert_eq!(
+      csv,
+      "timestamp,artist,artist_mbid,album,album_mbid,track,track_mbid\n\
+       1700000000,\"Crosby, Stills & Nash\",,\"The \"\"Best\"\" Of\",,Suite: Judy Blue Eyes,b1a9c0e9-d987-4042-ae91-78d6a3267d69\n"
+    );
+  }
+
+  #[test]
+  fn test_json_lines_round_trip() {
+    let mut exporter = Exporter::new(Vec::new(), ExportFormat::JsonLines).unwrap();
+    exporter.write(&record()).unwrap();
+    exporter.write(&record()).unwrap();
+
+    let out = String::from_utf8(exporter.finish().unwrap()).unwrap();
+    let records: Vec<ExportRecord> = out
+      .lines()
+      .map(|line| serde_json::from_str(line).unwrap())
+      .collect();
+    assert_eq!(records, vec![record(), record()]);
+
+    assert!(out.starts_with(r#"{"timestamp":1700000000,"artist":"Crosby, Stills & Nash","#));
+  }
+
+  fn entries() -> Vec<PlaylistEntry> {
+    let mut believe = PlaylistEntry::new("Cher", "Believe");
+    believe.album = Some("Believe".to_string());
+    believe.duration = Some(Duration::from_millis(239_500));
+    believe.location = Some("/music/believe.mp3".to_string());
+    vec![
+      believe,
+      PlaylistEntry::new("Simon & Garfunkel", "Mrs.\nRobinson"),
+    ]
+  }
+
+  #[test]
+  fn test_m3u_playlist() {
+    let mut resolved = Vec::new();
+    let mut playlist = PlaylistWriter::titled(Vec::new(), PlaylistFormat::M3u, "Loved")
+      .unwrap()
+      .with_resolver(|entry| {
+        resolved.push(entry.title.clone());
+        None
+      });
+    for entry in entries() {
+      playlist.write(&entry).unwrap();
+    }
+    assert_eq!((playlist.written(), playlist.skipped()), (1, 1));
+
+    let m3u = String::from_utf8(playlist.finish().unwrap()).unwrap();
+    assert_eq!(
+      m3u,
+      "#EXTM3U\n#PLAYLIST:Loved\n#EXTINF:239,Cher - Believe\n/music/believe.mp3\n"
+    );
+    // Only entries without a location are resolved
+    assert_eq!(resolved, ["Mrs.\nRobinson"]);
+  }
+
+  #[test]
+  fn test_xspf_playlist() {
+    let xspf = playlist(Vec::new(), PlaylistFormat::Xspf, entries()).unwrap();
+    let xspf = String::from_utf8(xspf).unwrap();
+    assert_eq!(
+      xspf,
+      r#"<?xml version="1.0" encoding="UTF-8"?>
+<playlist version="1" xmlns="http://xspf.org/ns/0/">
+  <trackList>
+    <track>
+      <location>/music/believe.mp3</location>
+      <creator>Cher</creator>
+      <title>Believe</title>
+      <album>Believe</album>
+      <duration>239500</duration>
+    </track>
+    <track>
+      <creator>Simon &amp; Garfunkel</creator>
+      <title>Mrs.
+Robinson</title>
+    </track>
+  </trackList>
+</playlist>
+"#
+    );
+  }
+}
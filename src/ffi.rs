@@ -0,0 +1,329 @@
+//! C bindings for scrobbling from players written in C or C++
+//!
+//! Enabled by the `ffi` feature. Build a library to link against with
+//! `cargo rustc --release --features ffi --crate-type staticlib` (or
+//! `cdylib`) and include `include/last_fm_rs.h`.
+//!
+//! Each client owns a small runtime, so every call blocks the calling thread
+//! until the request finishes, retries included. Strings are NUL-terminated
+//! UTF-8. Functions report failures through [`LastfmStatus`];
+//! [`lastfm_last_error`] describes the most recent failure on the calling
+//! thread.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::future::Future;
+use std::ptr;
+use std::time::Duration;
+
+use crate::client::Client;
+use crate::error::{Error, Result};
+use crate::scrobble::{NowPlaying, Scrobble};
+
+/// Outcome of an FFI call
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LastfmStatus {
+  Ok = 0,
+  /// A required argument was NULL or not valid UTF-8
+  InvalidArgument = -1,
+  /// Missing or rejected credentials
+  Auth = -2,
+  RateLimited = -3,
+  /// Last.fm rejected the request
+  Api = -4,
+  /// Connection failure or timeout
+  Network = -5,
+  Other = -6,
+}
+
+impl From<&Error> for LastfmStatus {
+  fn from(error: &Error) -> Self {
+    match error {
+      Error::InvalidParameter(_) | Error::UrlParse(_) => Self::InvalidArgument,
+      Error::Auth { .. } => Self::Auth,
+      Error::RateLimited { .. } => Self::RateLimited,
+      Error::Api { .. } | Error::InvalidSignature { .. } => Self::Api,
+      Error::Http { .. } | Error::Timeout | Error::DeadlineExceeded => Self::Network,
+      _ => Self::Other,
+    }
+  }
+}
+
+/// Client handle owned by C code; free it with [`lastfm_client_free`]
+pub struct LastfmClient {
+  client: Client,
+  runtime: tokio::runtime::Runtime,
+}
+
+impl LastfmClient {
+  fn new(client: Client) -> Result<Self> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()?;
+    Ok(Self { client, runtime })
+  }
+
+  fn block_on<T>(&self, call: impl Future<Output = Result<T>>) -> LastfmStatus {
+    status(self.runtime.block_on(call).map(|_| ()))
+  }
+}
+
+thread_local! {
+  static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Record the outcome of a call for [`lastfm_last_error`]
+fn status(result: Result<()>) -> LastfmStatus {
+  let (status, message) = match &result {
+    Ok(()) => (LastfmStatus::Ok, None),
+    Err(error) => {
+      let message = error.to_string().replace('\0', " ");
+      (LastfmStatus::from(error), CString::new(message).ok())
+    }
+  };
+  LAST_ERROR.with(|last| *last.borrow_mut() = message);
+  status
+}
+
+/// Borrow an optional C string argument
+///
+/// # Safety
+///
+/// `value` must be NULL or point to a NUL-terminated string that outlives
+/// the returned reference.
+unsafe fn optional<'a>(name: &str, value: *const c_char) -> Result<Option<&'a str>> {
+  if value.is_null() {
+    return Ok(None);
+  }
+  CStr::from_ptr(value)
+    .to_str()
+    .map(Some)
+    .map_err(|_| Error::InvalidParameter(format!("{} is not valid UTF-8", name)))
+}
+
+/// Borrow a required C string argument
+///
+/// # Safety
+///
+/// As for [`optional`].
+unsafe fn required<'a>(name: &str, value: *const c_char) -> Result<&'a str> {
+  optional(name, value)?.ok_or_else(|| Error::InvalidParameter(format!("{} is NULL", name)))
+}
+
+/// Box a client for C, or record the error and return NULL
+fn into_handle(client: Result<Client>) -> *mut LastfmClient {
+  match client.and_then(LastfmClient::new) {
+    Ok(handle) => {
+      status(Ok(()));
+      Box::into_raw(Box::new(handle))
+    }
+    Err(error) => {
+      status(Err(error));
+      ptr::null_mut()
+    }
+  }
+}
+
+/// Create a Last.fm client, or NULL on failure
+///
+/// `session_key` may be NULL for a client that can't scrobble yet.
+///
+/// # Safety
+///
+/// Every non-NULL argument must point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn lastfm_client_new(
+  api_key: *const c_char,
+  api_secret: *const c_char,
+  session_key: *const c_char,
+) -> *mut LastfmClient {
+  let client = (|| {
+    let client = Client::builder(
+      required("api_key", api_key)?,
+      required("api_secret", api_secret)?,
+    )
+    .build()?;
+    Ok(match optional("session_key", session_key)? {
+      Some(session_key) => client.with_session_key(session_key),
+      None => client,
+    })
+  })();
+  into_handle(client)
+}
+
+/// Create a client for a token-authenticated scrobble server, or NULL on
+/// failure
+///
+/// # Safety
+///
+/// Both arguments must point to NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn lastfm_client_new_token(
+  base_url: *const c_char,
+  token: *const c_char,
+) -> *mut LastfmClient {
+  let client =
+    (|| Client::with_token(required("base_url", base_url)?, required("token", token)?))();
+  into_handle(client)
+}
+
+/// Free a client created by one of the `lastfm_client_new*` functions
+///
+/// # Safety
+///
+/// `client` must be NULL or a handle that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn lastfm_client_free(client: *mut LastfmClient) {
+  if !client.is_null() {
+    drop(Box::from_raw(client));
+  }
+}
+
+/// Announce the track that just started playing
+///
+/// `album` may be NULL; `duration_secs` is the track length, 0 if unknown.
+///
+/// # Safety
+///
+/// `client` must be a live handle; every non-NULL string argument must point
+/// to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn lastfm_now_playing(
+  client: *const LastfmClient,
+  artist: *const c_char,
+  track: *const c_char,
+  album: *const c_char,
+  duration_secs: u32,
+) -> LastfmStatus {
+  let Some(client) = client.as_ref() else {
+    return status(Err(Error::InvalidParameter("client is NULL".to_string())));
+  };
+  let now_playing = (|| {
+    let mut now_playing =
+      NowPlaying::try_new(required("artist", artist)?, required("track", track)?)?;
+    now_playing.album = optional("album", album)?.map(str::to_string);
+    if duration_secs > 0 {
+      now_playing.duration = Some(Duration::from_secs(duration_secs.into()));
+    }
+    Ok(now_playing)
+  })();
+  match now_playing {
+    Ok(now_playing) => client.block_on(client.client.update_now_playing(&now_playing)),
+    Err(error) => status(Err(error)),
+  }
+}
+
+/// Scrobble a track that started playing at `timestamp` (unix seconds)
+///
+/// `album` may be NULL; `duration_secs` is the track length, 0 if unknown.
+/// Returns [`LastfmStatus::Ok`] once Last.fm has answered, even if it
+/// ignored the scrobble.
+///
+/// # Safety
+///
+/// As for [`lastfm_now_playing`].
+#[no_mangle]
+pub unsafe extern "C" fn lastfm_scrobble(
+  client: *const LastfmClient,
+  artist: *const c_char,
+  track: *const c_char,
+  album: *const c_char,
+  timestamp: u64,
+  duration_secs: u32,
+) -> LastfmStatus {
+  let Some(client) = client.as_ref() else {
+    return status(Err(Error::InvalidParameter("client is NULL".to_string())));
+  };
+  let scrobble = (|| {
+    let mut scrobble = Scrobble::try_new(
+      required("artist", artist)?,
+      required("track", track)?,
+      timestamp,
+    )?;
+    scrobble.album = optional("album", album)?.map(str::to_string);
+    if duration_secs > 0 {
+      scrobble.duration = Some(Duration::from_secs(duration_secs.into()));
+    }
+    Ok(scrobble)
+  })();
+  match scrobble {
+    Ok(scrobble) => client.block_on(client.client.scrobble([scrobble])),
+    Err(error) => status(Err(error)),
+  }
+}
+
+/// Message for the most recent failure on this thread, or NULL after a
+/// successful call
+///
+/// The string stays valid until the next `lastfm_*` call on this thread.
+#[no_mangle]
+pub extern "C" fn lastfm_last_error() -> *const c_char {
+  LAST_ERROR.with(|last| {
+    last
+      .borrow()
+      .as_ref()
+      .map_or(ptr::null(), |message| message.as_ptr())
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_server::{Reply, TestServer};
+
+  fn last_error() -> String {
+    let message = lastfm_last_error();
+    assert!(!message.is_null());
+    unsafe { CStr::from_ptr(message) }
+      .to_string_lossy()
+      .into_owned()
+  }
+
+  #[test]
+  fn test_invalid_arguments() {
+    let key = CString::new("key").unwrap();
+    unsafe {
+      assert!(lastfm_client_new(key.as_ptr(), ptr::null(), ptr::null()).is_null());
+      assert_eq!(last_error(), "Invalid parameter: api_secret is NULL");
+
+      let client = lastfm_client_new(key.as_ptr(), key.as_ptr(), ptr::null());
+      assert!(!client.is_null());
+      assert!(lastfm_last_error().is_null());
+
+      let status = lastfm_scrobble(
+        client,
+        key.as_ptr(),
+        ptr::null(),
+        ptr::null(),
+        1_700_000_000,
+        0,
+      );
+      assert_eq!(status, LastfmStatus::InvalidArgument);
+      lastfm_client_free(client);
+    }
+  }
+
+  #[test]
+  fn test_token_scrobble() {
+    let server = TestServer::replies(vec![Reply::ok("")]);
+    let base_url = CString::new(server.url("/")).unwrap();
+    let token = CString::new("token").unwrap();
+    let artist = CString::new("Cher").unwrap();
+    let track = CString::new("Believe").unwrap();
+    unsafe {
+      let client = lastfm_client_new_token(base_url.as_ptr(), token.as_ptr());
+      let status = lastfm_scrobble(
+        client,
+        artist.as_ptr(),
+        track.as_ptr(),
+        ptr::null(),
+        1_700_000_000,
+        240,
+      );
+      assert_eq!(status, LastfmStatus::Ok);
+      lastfm_client_free(client);
+    }
+    assert!(server.requests()[0].starts_with("POST /scrob "));
+  }
+}
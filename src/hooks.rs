@@ -0,0 +1,130 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::Result;
+#[cfg(any(feature = "scrobble", feature = "auth-flow"))]
+use crate::params::Params;
+
+type RequestHook = dyn Fn(&mut reqwest::Request) -> Result<()> + Send + Sync;
+type ResponseHook = dyn Fn(&ResponseInfo<'_>) + Send + Sync;
+type SignatureHook = dyn Fn(&SignatureInfo<'_>) + Send + Sync;
+
+/// Summary of a finished HTTP exchange, passed to
+/// [`Client::on_response`](crate::Client::on_response)
+///
+/// `url` is the full request URL; for read-only GET requests its query
+/// carries the API key, so strip it before logging.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct ResponseInfo<'a> {
+  pub method: &'a reqwest::Method,
+  pub url: &'a url::Url,
+  /// `None` when no response arrived (connection failure or timeout)
+  pub status: Option<reqwest::StatusCode>,
+  /// Time from sending the request to receiving the response headers
+  pub elapsed: Duration,
+}
+
+/// How a request was signed, passed to
+/// [`Client::on_signature`](crate::Client::on_signature)
+///
+/// `message` is the exact canonical message given to the
+/// [`Signer`](crate::Signer), session key included, so only log `masked`.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct SignatureInfo<'a> {
+  /// API method, such as `track.scrobble`
+  pub method: &'a str,
+  /// Sorted name+value pairs that were signed
+  pub message: &'a str,
+  /// The string Last.fm's MD5 scheme hashes (`message` followed by the
+  /// secret), with the session key shown as `****` and the secret as
+  /// `<secret>`
+  pub masked: &'a str,
+  /// Resulting `api_sig`
+  pub signature: &'a str,
+}
+
+/// Hooks run around every HTTP request a client sends
+#[derive(Clone, Default)]
+pub(crate) struct Hooks {
+  request: Vec<Arc<RequestHook>>,
+  response: Vec<Arc<ResponseHook>>,
+  signature: Vec<Arc<SignatureHook>>,
+}
+
+impl Hooks {
+  pub fn add_request(&mut self, hook: Arc<RequestHook>) {
+    self.request.push(hook);
+  }
+
+  pub fn add_response(&mut self, hook: Arc<ResponseHook>) {
+    self.response.push(hook);
+  }
+
+  pub fn add_signature(&mut self, hook: Arc<SignatureHook>) {
+    self.signature.push(hook);
+  }
+
+  /// Report that `params` were signed as `message`, giving `signature`;
+  /// does nothing, and costs nothing, without signature hooks
+  #[cfg(any(feature = "scrobble", feature = "auth-flow"))]
+  pub fn signed(&self, params: &mut Params<'_>, message: &str, signature: &str) {
+    if self.signature.is_empty() {
+      return;
+    }
+    let masked = format!("{}<secret>", params.masked_signing_message());
+    let info = SignatureInfo {
+      method: params.method(),
+      message,
+      masked: &masked,
+      signature,
+    };
+    for hook in &self.signature {
+      hook(&info);
+    }
+  }
+
+  /// Execute `request` with `http_client`, running the hooks in the order
+  /// they were added
+  #[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+  pub async fn execute(
+    &self,
+    http_client: &reqwest::Client,
+    mut request: reqwest::Request,
+  ) -> Result<reqwest::Response> {
+    for hook in &self.request {
+      hook(&mut request)?;
+    }
+    if self.response.is_empty() {
+      return Ok(http_client.execute(request).await?);
+    }
+
+    let method = request.method().clone();
+    let url = request.url().clone();
+    let start = tokio::time::Instant::now();
+    let result = http_client.execute(request).await;
+
+    let info = ResponseInfo {
+      method: &method,
+      url: &url,
+      status: result.as_ref().ok().map(reqwest::Response::status),
+      elapsed: start.elapsed(),
+    };
+    for hook in &self.response {
+      hook(&info);
+    }
+    Ok(result?)
+  }
+}
+
+impl fmt::Debug for Hooks {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Hooks")
+      .field("request", &self.request.len())
+      .field("response", &self.response.len())
+      .field("signature", &self.signature.len())
+      .finish()
+  }
+}
@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use futures::{stream, StreamExt};
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::client::Client;
+use crate::error::Result;
+use crate::state::ClientState;
+use crate::track::TrackInfo;
+
+/// Last.fm asks for no more than five requests per second per API key
+const MIN_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Spaces out the start of requests made by concurrent lookups
+pub(crate) struct Pacer {
+  interval: Duration,
+  last_request: Mutex<Option<Instant>>,
+}
+
+impl Pacer {
+  fn new(interval: Duration) -> Self {
+    Self {
+      interval,
+      last_request: Mutex::new(None),
+    }
+  }
+
+  /// Sleep until a request is allowed, then claim the slot
+  pub(crate) async fn wait_turn(&self) {
+    let mut last_request = self.last_request.lock().await;
+    if let Some(last) = *last_request {
+      let elapsed = last.elapsed();
+      if elapsed < self.interval {
+        tokio::time::sleep(self.interval - elapsed).await;
+      }
+    }
+    *last_request = Some(Instant::now());
+  }
+}
+
+/// Look up each (artist, track) pair, at most `concurrency` at a time
+pub(crate) async fn tracks<S, A, T>(
+  client: &Client<S>,
+  items: Vec<(A, T)>,
+  concurrency: usize,
+) -> Vec<Result<TrackInfo>>
+where
+  S: ClientState,
+  A: AsRef<str>,
+  T: AsRef<str>,
+{
+  let pacer = Pacer::new(MIN_INTERVAL);
+  let pacer = &pacer;
+  stream::iter(&items)
+    .map(|(artist, track)| {
+      client.paced_track_info(artist.as_ref(), track.as_ref(), None, Some(pacer))
+    })
+    .buffered(concurrency.max(1))
+    .collect()
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::error::Error;
+  use crate::test_server::{Reply, TestServer};
+
+  #[tokio::test]
+  async fn test_failures_are_reported_per_track() {
+    let server = TestServer::replies(vec![
+      Reply::ok(r#"{"track":{"name":"Believe","artist":{"name":"Cher"}}}"#),
+      Reply::ok(r#"{"error":6,"message":"Track not found"}"#),
+      Reply::ok(r#"{"track":{"name":"Strong Enough","artist":{"name":"Cher"}}}"#),
+    ]);
+    let client = Client::builder("key", "secret")
+      .api_base(server.url("/2.0/"))
+      .retry_policy(crate::NoRetry)
+      .build()
+      .unwrap();
+
+    let items = vec![
+      ("Cher", "Believe"),
+      ("Cher", "Missing"),
+      ("Cher", "Strong Enough"),
+    ];
+    let results = tracks(&client, items, 1).await;
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].as_ref().unwrap().name, "Believe");
+    assert!(matches!(results[1], Err(Error::Api { code: 6, .. })));
+    assert_eq!(results[2].as_ref().unwrap().name, "Strong Enough");
+  }
+
+  #[tokio::test]
+  async fn test_cache_hits_are_not_paced() {
+    let server = TestServer::replies(vec![Reply::ok(
+      r#"{"track":{"name":"Believe","artist":{"name":"Cher"}}}"#,
+    )]);
+    let client = Client::builder("key", "secret")
+      .api_base(server.url("/2.0/"))
+      .retry_policy(crate::NoRetry)
+      .track_info_cache(10)
+      .build()
+      .unwrap();
+
+    let start = Instant::now();
+    let results = tracks(&client, vec![("Cher", "Believe"); 5], 1).await;
+    assert!(results.iter().all(Result::is_ok));
+    assert_eq!(server.requests().len(), 1);
+    // Five paced lookups would take at least four intervals
+    assert!(
+      start.elapsed() < MIN_INTERVAL * 2,
+      "took {:?}",
+      start.elapsed()
+    );
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn test_pacer_spaces_requests() {
+    let pacer = Pacer::new(MIN_INTERVAL);
+    let start = Instant::now();
+    for _ in 0..3 {
+      pacer.wait_turn().await;
+    }
+    assert_eq!(start.elapsed(), MIN_INTERVAL * 2);
+  }
+}
@@ -0,0 +1,160 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::error::{Error, Result};
+
+/// How a pooled client picks an API key for each read-only request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum KeySelection {
+  /// Spread requests evenly across every key
+  #[default]
+  RoundRobin,
+  /// Stick with one key until it is rate limited or rejected, then move on
+  Failover,
+}
+
+/// API keys shared by the read-only (unsigned) methods of a client
+///
+/// Signed calls stay on the client's primary key, because session keys are
+/// bound to the API key that created them.
+#[derive(Debug)]
+pub(crate) struct KeyPool {
+  keys: Vec<String>,
+  selection: KeySelection,
+  next: AtomicUsize,
+}
+
+impl KeyPool {
+  /// Pool starting with the primary key, skipping duplicates
+  pub fn new(
+    primary: &str,
+    extra: impl IntoIterator<Item = String>,
+    selection: KeySelection,
+  ) -> Self {
+    let mut keys = vec![primary.to_string()];
+    for key in extra {
+      if !keys.contains(&key) {
+        keys.push(key);
+      }
+    }
+    Self {
+      keys,
+      selection,
+      next: AtomicUsize::new(0),
+    }
+  }
+
+  /// Index of the key to try first
+  fn start(&self) -> usize {
+    match self.selection {
+      KeySelection::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed) % self.keys.len(),
+      KeySelection::Failover => self.next.load(Ordering::Relaxed) % self.keys.len(),
+    }
+  }
+
+  /// Move a failover pool past a key that stopped working
+  fn failed(&self, index: usize) {
+    if self.selection == KeySelection::Failover {
+      let _ = self.next.compare_exchange(
+        index,
+        (index + 1) % self.keys.len(),
+        Ordering::Relaxed,
+        Ordering::Relaxed,
+      );
+    }
+  }
+
+  /// Run `call` with a key, trying the others while keys are rate limited
+  /// or rejected
+  pub async fn run<'a, T, F, Fut>(&'a self, call: F) -> Result<T>
+  where
+    F: Fn(&'a str) -> Fut,
+    Fut: Future<Output = Result<T>>,
+  {
+    let start = self.start();
+    let mut attempt = 0;
+    loop {
+      let index = (start + attempt) % self.keys.len();
+      match call(&self.keys[index]).await {
+        Err(Error::RateLimited { .. } | Error::Auth { .. }) if attempt + 1 < self.keys.len() => {
+          self.failed(index);
+          attempt += 1;
+        }
+        result => return result,
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Mutex;
+
+  use super::*;
+
+  fn pool(selection: KeySelection) -> KeyPool {
+    KeyPool::new(
+      "a",
+      ["b".to_string(), "a".to_string(), "c".to_string()],
+      selection,
+    )
+  }
+
+  #[tokio::test]
+  async fn test_round_robin_spreads_requests() {
+    let pool = pool(KeySelection::RoundRobin);
+    let mut used = Vec::new();
+    for _ in 0..4 {
+      used.push(pool.run(|key| async move { Ok(key) }).await.unwrap());
+    }
+    assert_eq!(used, ["a", "b", "c", "a"]);
+  }
+
+  #[tokio::test]
+  async fn test_failover_skips_rate_limited_key() {
+    let pool = pool(KeySelection::Failover);
+    let tried = Mutex::new(Vec::new());
+
+    let key = pool
+      .run(|key| {
+        tried.lock().unwrap().push(key);
+        async move {
+          match key {
+            "a" => Err(Error::rate_limited()),
+            "b" => Err(Error::auth("Suspended API key")),
+            _ => Ok(key),
+          }
+        }
+      })
+      .await
+      .unwrap();
+    assert_eq!(key, "c");
+    assert_eq!(*tried.lock().unwrap(), ["a", "b", "c"]);
+
+    // Later requests start from the key that worked
+    assert_eq!(pool.run(|key| async move { Ok(key) }).await.unwrap(), "c");
+  }
+
+  #[tokio::test]
+  async fn test_other_errors_are_returned() {
+    let pool = pool(KeySelection::Failover);
+    let result: Result<()> = pool
+      .run(|_| async {
+        Err(Error::Api {
+          method: "track.getInfo".to_string(),
+          params: String::new(),
+          code: 6,
+          message: "Track not found".to_string(),
+        })
+      })
+      .await;
+    assert!(matches!(result, Err(Error::Api { code: 6, .. })));
+  }
+
+  #[tokio::test]
+  async fn test_last_error_when_every_key_fails() {
+    let pool = pool(KeySelection::RoundRobin);
+    let result: Result<()> = pool.run(|_| async { Err(Error::rate_limited()) }).await;
+    assert!(matches!(result, Err(Error::RateLimited { .. })));
+  }
+}
@@ -2,15 +2,118 @@
 ///
 /// Supports authentication and scrobbling for desktop applications.
 mod auth;
+#[cfg(feature = "client")]
 mod auth_mode;
+#[cfg(feature = "client")]
+mod builder;
+#[cfg(feature = "metadata")]
+mod cache;
+#[cfg(feature = "client")]
 mod client;
+#[cfg(feature = "metadata")]
+mod coalesce;
+#[cfg(feature = "metadata")]
+mod country;
+#[cfg(feature = "metadata")]
+mod date;
+mod de;
+#[cfg(feature = "disk-cache")]
+mod disk_cache;
 mod error;
+#[cfg(feature = "metadata")]
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "client")]
+mod hooks;
+#[cfg(feature = "metadata")]
+mod hydrate;
+#[cfg(feature = "metadata")]
+mod key_pool;
+#[cfg(feature = "metadata")]
+mod loved;
+mod mbid;
+#[cfg(feature = "musicbrainz")]
+pub mod musicbrainz;
+#[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+mod params;
+#[cfg(feature = "metadata")]
+mod period;
+#[cfg(feature = "metadata")]
+mod recent;
+#[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+mod response;
+#[cfg(feature = "client")]
+mod retry;
 mod scrobble;
+#[cfg(feature = "metadata")]
+mod search;
 mod signature;
+#[cfg(feature = "spotify")]
+pub mod spotify;
+#[cfg(feature = "client")]
+mod state;
+#[cfg(feature = "metadata")]
+pub mod stats;
+#[cfg(test)]
+#[allow(dead_code)] // Only the scrobble tests count allocations
+pub(crate) mod test_alloc;
+#[cfg(all(test, feature = "client"))]
+#[allow(dead_code)] // Which helpers the tests use depends on the features
+pub(crate) mod test_server;
+#[cfg(feature = "models")]
 mod track;
+#[cfg(feature = "metadata")]
+mod watch;
 
 pub use auth::{AuthToken, SessionKey};
+#[cfg(feature = "client")]
+pub use auth_mode::AuthKind;
+#[cfg(feature = "client")]
+pub use builder::{ClientBuilder, Endpoint};
+#[cfg(feature = "metadata")]
+pub use cache::{CacheStats, TrackInfoCache};
+#[cfg(feature = "client")]
 pub use client::Client;
+#[cfg(feature = "metadata")]
+pub use country::Country;
+#[cfg(feature = "metadata")]
+pub use date::LastfmDate;
+#[cfg(feature = "disk-cache")]
+pub use disk_cache::DiskCache;
 pub use error::{Error, Result};
-pub use scrobble::{NowPlaying, Scrobble, ScrobbleResponse};
-pub use track::{TrackInfo, TrackInfoResponse};
+#[cfg(feature = "metadata")]
+pub use export::{
+  ExportFormat, ExportRecord, Exporter, PlaylistEntry, PlaylistFormat, PlaylistWriter,
+};
+#[cfg(feature = "client")]
+pub use hooks::{ResponseInfo, SignatureInfo};
+#[cfg(feature = "metadata")]
+pub use key_pool::KeySelection;
+#[cfg(feature = "metadata")]
+pub use loved::LovedTrack;
+#[cfg(all(feature = "metadata", feature = "scrobble"))]
+pub use loved::{SyncOptions, SyncReport};
+pub use mbid::Mbid;
+#[cfg(feature = "metadata")]
+pub use period::Period;
+#[cfg(feature = "metadata")]
+pub use recent::{RecentTrack, RecentTrackState, RecentTracksOptions};
+#[cfg(feature = "metadata")]
+pub use response::ResponseParts;
+#[cfg(feature = "client")]
+pub use retry::{is_idempotent, ExponentialBackoff, NoRetry, RetryPolicy};
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub use scrobble::encode_batch;
+pub use scrobble::{
+  Corrected, Corrections, IgnoredMessage, NowPlaying, Scrobble, ScrobbleBuilder, ScrobbleOutcome,
+  ScrobbleResponse, ScrobbleResult, Timestamp,
+};
+#[cfg(feature = "metadata")]
+pub use search::{PageAttr, SearchPage, TrackMatch};
+pub use signature::{Md5Signer, Signer};
+#[cfg(feature = "client")]
+pub use state::{Authenticated, Authorized, ClientState, Dynamic, Unauthenticated};
+#[cfg(feature = "models")]
+pub use track::{Album, Artist, Cover, Image, ImageSize, TrackInfo, TrackInfoResponse};
@@ -0,0 +1,306 @@
+#[cfg(feature = "scrobble")]
+use std::collections::HashSet;
+use std::fmt;
+#[cfg(feature = "scrobble")]
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+#[cfg(feature = "scrobble")]
+use crate::client::Client;
+use crate::date::LastfmDate;
+use crate::de;
+#[cfg(feature = "scrobble")]
+use crate::error::{Error, Result};
+use crate::mbid::Mbid;
+use crate::scrobble::write_track;
+use crate::search::{PageAttr, SearchPage};
+#[cfg(feature = "scrobble")]
+use crate::state::{Authorized, ClientState};
+use crate::track::{Artist, Image};
+
+/// Largest page size accepted by user.getLovedTracks
+#[cfg(feature = "scrobble")]
+pub(crate) const MAX_LIMIT: u32 = 1000;
+
+/// Track a user has loved, from user.getLovedTracks
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct LovedTrack {
+  #[serde(deserialize_with = "de::string", default)]
+  pub name: String,
+  #[serde(deserialize_with = "de::mbid", default)]
+  pub mbid: Option<Mbid>,
+  /// Last.fm page, `None` when missing or malformed
+  #[serde(deserialize_with = "de::url", default)]
+  pub url: Option<Url>,
+  pub artist: Artist,
+  /// Available sizes; entries Last.fm sends without a URL are dropped
+  #[serde(deserialize_with = "de::images", default)]
+  pub image: Vec<Image>,
+  /// When the track was loved
+  #[serde(deserialize_with = "de::option", default)]
+  pub date: Option<LastfmDate>,
+  /// Fields this version doesn't model yet
+  #[serde(flatten)]
+  #[cfg_attr(feature = "strict-schema", serde(deserialize_with = "de::no_extra"))]
+  pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[cfg(feature = "scrobble")]
+impl LovedTrack {
+  /// Case-insensitive identity used to match tracks across accounts
+  fn key(&self) -> (String, String) {
+    (self.artist.name.to_lowercase(), self.name.to_lowercase())
+  }
+}
+
+/// `Artist – Track`
+impl fmt::Display for LovedTrack {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write_track(f, &self.artist.name, &self.name, None)
+  }
+}
+
+/// Response wrapper for user.getLovedTracks
+#[derive(Debug, Deserialize)]
+pub(crate) struct LovedTracksResponse {
+  lovedtracks: LovedTracks,
+}
+
+#[derive(Debug, Deserialize)]
+struct LovedTracks {
+  #[serde(deserialize_with = "de::one_or_many", default)]
+  track: Vec<LovedTrack>,
+  #[serde(rename = "@attr", default)]
+  attr: PageAttr,
+}
+
+impl From<LovedTracksResponse> for SearchPage<LovedTrack> {
+  fn from(response: LovedTracksResponse) -> Self {
+    let LovedTracks { track, attr } = response.lovedtracks;
+    attr.into_page(track)
+  }
+}
+
+/// Settings for [`Client::sync_loved_tracks`]
+#[cfg(feature = "scrobble")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SyncOptions {
+  /// Report what would be loved without changing the target
+  pub dry_run: bool,
+  /// Pause between track.love calls on the target
+  pub delay: Duration,
+}
+
+#[cfg(feature = "scrobble")]
+impl SyncOptions {
+  /// Love missing tracks, pausing 250ms between requests
+  pub fn new() -> Self {
+    Self {
+      dry_run: false,
+      delay: Duration::from_millis(250),
+    }
+  }
+
+  pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+    self.dry_run = dry_run;
+    self
+  }
+
+  pub fn with_delay(mut self, delay: Duration) -> Self {
+    self.delay = delay;
+    self
+  }
+}
+
+#[cfg(feature = "scrobble")]
+impl Default for SyncOptions {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// Outcome of [`Client::sync_loved_tracks`]
+#[cfg(feature = "scrobble")]
+#[derive(Debug, Clone, Default)]
+pub struct SyncReport {
+  /// Source tracks that weren't loved on the target, oldest first
+  pub missing: Vec<LovedTrack>,
+  /// How many of them were loved (always 0 on a dry run)
+  pub loved: usize,
+  /// Tracks that couldn't be loved, each with the error it failed with
+  pub failed: Vec<(LovedTrack, Error)>,
+}
+
+/// Fetch every loved track for `user`, newest first
+#[cfg(feature = "scrobble")]
+pub(crate) async fn all(client: &Client<impl ClientState>, user: &str) -> Result<Vec<LovedTrack>> {
+  let mut tracks = Vec::new();
+  let mut page = 1;
+  loop {
+    let result = client.loved_tracks(user, page, MAX_LIMIT).await?;
+    let more = result.has_next_page();
+    tracks.extend(result.items);
+    if !more {
+      return Ok(tracks);
+    }
+    page += 1;
+  }
+}
+
+/// Tracks loved in `source` but not in `target`, oldest first
+#[cfg(feature = "scrobble")]
+pub(crate) fn missing(source: Vec<LovedTrack>, target: &[LovedTrack]) -> Vec<LovedTrack> {
+  let loved: HashSet<(String, String)> = target.iter().map(LovedTrack::key).collect();
+  let mut missing: Vec<LovedTrack> = source
+    .into_iter()
+    .filter(|track| !loved.contains(&track.key()))
+    .collect();
+  missing.reverse();
+  missing
+}
+
+/// Love each track on `target`, pausing `delay` between requests
+///
+/// A failed track is recorded in the report and the rest are still tried;
+/// rate limiting is left to the target's retry policy.
+#[cfg(feature = "scrobble")]
+pub(crate) async fn love_all(
+  target: &Client<impl Authorized>,
+  tracks: &[LovedTrack],
+  delay: Duration,
+  report: &mut SyncReport,
+) {
+  for (i, track) in tracks.iter().enumerate() {
+    if i > 0 {
+      tokio::time::sleep(delay).await;
+    }
+    match target.love_track(&track.artist.name, &track.name).await {
+      Ok(()) => report.loved += 1,
+      Err(error) => report.failed.push((track.clone(), error)),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[cfg(feature = "scrobble")]
+  fn loved(artist: &str, name: &str) -> LovedTrack {
+    serde_json::from_value(serde_json::json!({
+      "name": name,
+      "artist": { "name": artist },
+    }))
+    .unwrap()
+  }
+
+  #[test]
+  fn test_loved_tracks_page() {
+    let json = r##"{"lovedtracks":{
+      "track":{"name":"Believe","mbid":"","url":"https://www.last.fm/music/Cher/_/Believe",
+        "artist":{"name":"Cher","mbid":"","url":"https://www.last.fm/music/Cher"},
+        "date":{"uts":"1700000000","#text":"14 Nov 2023, 22:13"}},
+      "@attr":{"user":"rj","totalPages":"1","page":"1","perPage":"50","total":"1"}
+    }}"##;
+
+    let response: LovedTracksResponse = serde_json::from_str(json).unwrap();
+    let page = SearchPage::from(response);
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(page.items[0].artist.name, "Cher");
+    assert_eq!(
+      page.items[0].date.as_ref().map(LastfmDate::timestamp),
+      Some(1_700_000_000)
+    );
+    assert!(!page.has_next_page());
+  }
+
+  #[cfg(feature = "scrobble")]
+  #[test]
+  fn test_missing_is_case_insensitive_and_oldest_first() {
+    let source = vec![
+      loved("Cher", "Believe"),
+      loved("ABBA", "Waterloo"),
+      loved("Björk", "Jóga"),
+    ];
+    let target = vec![loved("cher", "BELIEVE")];
+
+    let missing = missing(source, &target);
+    let names: Vec<&str> = missing.iter().map(|track| track.name.as_str()).collect();
+    assert_eq!(names, ["Jóga", "Waterloo"]);
+  }
+
+  #[cfg(all(feature = "metadata", feature = "scrobble"))]
+  #[tokio::test(start_paused = true)]
+  async fn test_sync_keeps_going_past_failures() {
+    use crate::test_server::{Reply, TestServer};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let limited = AtomicBool::new(false);
+    let server = TestServer::start(move |request| {
+      Reply::ok(if request.contains("user=old") {
+        r#"{"lovedtracks":{"track":[
+          {"name":"Jóga","artist":{"name":"Björk"}},
+          {"name":"Believe","artist":{"name":"Cher"}},
+          {"name":"Waterloo","artist":{"name":"ABBA"}}
+        ]}}"#
+      } else if request.contains("user=new") {
+        r#"{"lovedtracks":{"track":[{"name":"Believe","artist":{"name":"Cher"}}]}}"#
+      } else if request.contains("track=Waterloo") && !limited.swap(true, Ordering::Relaxed) {
+        r#"{"error":29,"message":"Rate limit exceeded"}"#
+      } else if request.contains("artist=Bj") {
+        r#"{"error":6,"message":"Track not found"}"#
+      } else {
+        "{}"
+      })
+    });
+    let client = Client::builder("key", "secret")
+      .api_base(server.url("/2.0/"))
+      .build()
+      .unwrap();
+    let target = client.clone().with_session_key("session");
+
+    let options = SyncOptions::new().with_dry_run(true);
+    let report = client
+      .sync_loved_tracks("old", &target, "new", &options)
+      .await
+      .unwrap();
+    let names: Vec<&str> = report
+      .missing
+      .iter()
+      .map(|track| track.name.as_str())
+      .collect();
+    assert_eq!(names, ["Waterloo", "Jóga"]);
+    assert_eq!(report.loved, 0);
+    assert!(report.failed.is_empty());
+    assert!(!server
+      .requests()
+      .iter()
+      .any(|request| request.contains("track.love")));
+
+    let report = client
+      .sync_loved_tracks("old", &target, "new", &SyncOptions::new())
+      .await
+      .unwrap();
+    assert_eq!(report.missing.len(), 2);
+    assert_eq!(report.loved, 1);
+    let [(track, error)] = &report.failed[..] else {
+      panic!("unexpected failures: {:?}", report.failed);
+    };
+    assert_eq!(track.name, "Jóga");
+    assert!(
+      matches!(error, Error::Api { code: 6, .. }),
+      "got {:?}",
+      error
+    );
+    // The client's default retry policy repeats the rate-limited love once
+    let loves = server
+      .requests()
+      .iter()
+      .filter(|request| request.contains("track.love"))
+      .count();
+    assert_eq!(loves, 3);
+  }
+}
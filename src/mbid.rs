@@ -0,0 +1,121 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error::{Error, Result};
+
+/// MusicBrainz identifier, a hyphenated UUID such as
+/// `b1a9c0e9-d987-4042-ae91-78d6a3267d69`
+///
+/// Stored in lowercase. Last.fm sends `""` for entities without one, which
+/// the response models turn into `None` rather than an `Mbid`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Mbid(String);
+
+impl Mbid {
+  /// Validate `value` as a UUID in the 8-4-4-4-12 hex format
+  pub fn parse(value: &str) -> Result<Self> {
+    let value = value.trim();
+    let valid = value.len() == 36
+      && value.char_indices().all(|(i, c)| match i {
+        8 | 13 | 18 | 23 => c == '-',
+        _ => c.is_ascii_hexdigit(),
+      });
+    if !valid {
+      return Err(Error::InvalidParameter(format!(
+        "Invalid MBID: {:?}",
+        value
+      )));
+    }
+    Ok(Self(value.to_ascii_lowercase()))
+  }
+
+  pub fn as_str(&self) -> &str {
+    &self.0
+  }
+}
+
+impl FromStr for Mbid {
+  type Err = Error;
+
+  fn from_str(value: &str) -> Result<Self> {
+    Self::parse(value)
+  }
+}
+
+impl TryFrom<&str> for Mbid {
+  type Error = Error;
+
+  fn try_from(value: &str) -> Result<Self> {
+    Self::parse(value)
+  }
+}
+
+impl TryFrom<String> for Mbid {
+  type Error = Error;
+
+  fn try_from(value: String) -> Result<Self> {
+    Self::parse(&value)
+  }
+}
+
+impl AsRef<str> for Mbid {
+  fn as_ref(&self) -> &str {
+    &self.0
+  }
+}
+
+impl fmt::Display for Mbid {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.0)
+  }
+}
+
+impl Serialize for Mbid {
+  fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(&self.0)
+  }
+}
+
+impl<'de> Deserialize<'de> for Mbid {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    Self::parse(&value).map_err(serde::de::Error::custom)
+  }
+}
+
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for Mbid {
+  fn schema_name() -> std::borrow::Cow<'static, str> {
+    "Mbid".into()
+  }
+
+  fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+    schemars::json_schema!({ "type": "string", "format": "uuid" })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse() {
+    let mbid = Mbid::parse("B1A9C0E9-D987-4042-AE91-78D6A3267D69").unwrap();
+    assert_eq!(mbid.as_str(), "b1a9c0e9-d987-4042-ae91-78d6a3267d69");
+
+    for invalid in [
+      "",
+      "other",
+      "b1a9c0e9d9874042ae9178d6a3267d69",
+      "b1a9c0e9-d987-4042-ae91-78d6a3267d6z",
+    ] {
+      assert!(
+        matches!(Mbid::parse(invalid), Err(Error::InvalidParameter(_))),
+        "{}",
+        invalid
+      );
+    }
+  }
+}
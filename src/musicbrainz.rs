@@ -0,0 +1,205 @@
+//! MusicBrainz lookups for filling in missing recording IDs
+//!
+//! Last.fm matches submissions with an `mbid` far more reliably than by
+//! artist and title alone, which matters for obscure releases. [`MusicBrainz`]
+//! searches the MusicBrainz web service for recordings and keeps to its
+//! one-request-per-second limit on its own, independent of the Last.fm client.
+//!
+//! Enabled by the `musicbrainz` feature.
+
+use std::time::Duration;
+
+use serde::Deserialize;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::de;
+use crate::error::Result;
+use crate::mbid::Mbid;
+use crate::scrobble::Scrobble;
+
+const API_BASE: &str = "https://musicbrainz.org/ws/2/recording";
+
+/// MusicBrainz allows one request per second per client
+const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Default lowest search score accepted as a match (0-100)
+const DEFAULT_MIN_SCORE: u8 = 90;
+
+/// Rate-limited MusicBrainz recording search
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn example() -> last_fm_rs::Result<()> {
+/// use last_fm_rs::musicbrainz::MusicBrainz;
+/// use last_fm_rs::Scrobble;
+///
+/// let musicbrainz = MusicBrainz::new("my-scrobbler/1.0 (me@example.com)")?;
+/// let mut scrobbles = vec![Scrobble::new("Cher", "Believe", 1_700_000_000)];
+///
+/// let filled = musicbrainz.fill_mbids(&mut scrobbles).await?;
+/// println!("Resolved {} recording IDs", filled);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct MusicBrainz {
+  http_client: reqwest::Client,
+  min_score: u8,
+  last_request: Mutex<Option<Instant>>,
+}
+
+impl MusicBrainz {
+  /// Create a lookup client
+  ///
+  /// MusicBrainz rejects anonymous clients, so `user_agent` should name the
+  /// application and a contact, e.g. `"my-app/1.0 (me@example.com)"`.
+  pub fn new(user_agent: &str) -> Result<Self> {
+    let http_client = reqwest::Client::builder().user_agent(user_agent).build()?;
+    Ok(Self {
+      http_client,
+      min_score: DEFAULT_MIN_SCORE,
+      last_request: Mutex::new(None),
+    })
+  }
+
+  /// Set the lowest search score (0-100) accepted as a match
+  pub fn with_min_score(mut self, min_score: u8) -> Self {
+    self.min_score = min_score.min(100);
+    self
+  }
+
+  /// Find the recording ID for a track, if a confident match exists
+  pub async fn lookup_recording(&self, artist: &str, track: &str) -> Result<Option<Mbid>> {
+    self.wait_turn().await;
+
+    let query = format!("recording:{} AND artist:{}", quote(track), quote(artist));
+    let resp = self
+      .http_client
+      .get(API_BASE)
+      .query(&[("query", query.as_str()), ("fmt", "json"), ("limit", "1")])
+      .send()
+      .await?
+      .error_for_status()?;
+
+    let search: RecordingSearch = serde_json::from_str(&resp.text().await?)?;
+    Ok(search.best_match(self.min_score))
+  }
+
+  /// Look up recording IDs for scrobbles that don't have one
+  ///
+  /// Returns how many were filled in. Lookups run one per second, so large
+  /// batches take a while; scrobbles with an ID already are skipped.
+  pub async fn fill_mbids(&self, scrobbles: &mut [Scrobble]) -> Result<usize> {
+    let mut filled = 0;
+    for scrobble in scrobbles
+      .iter_mut()
+      .filter(|scrobble| scrobble.mbid.is_none())
+    {
+      if let Some(mbid) = self
+        .lookup_recording(&scrobble.artist, &scrobble.track)
+        .await?
+      {
+        scrobble.mbid = Some(mbid);
+        filled += 1;
+      }
+    }
+    Ok(filled)
+  }
+
+  /// Sleep until a request is allowed, then claim the slot
+  async fn wait_turn(&self) {
+    let mut last_request = self.last_request.lock().await;
+    if let Some(last) = *last_request {
+      let elapsed = last.elapsed();
+      if elapsed < MIN_INTERVAL {
+        tokio::time::sleep(MIN_INTERVAL - elapsed).await;
+      }
+    }
+    *last_request = Some(Instant::now());
+  }
+}
+
+/// Quote a value for a Lucene phrase query
+fn quote(value: &str) -> String {
+  let mut quoted = String::with_capacity(value.len() + 2);
+  quoted.push('"');
+  for c in value.chars() {
+    if c == '"' || c == '\\' {
+      quoted.push('\\');
+    }
+    quoted.push(c);
+  }
+  quoted.push('"');
+  quoted
+}
+
+/// Response from the recording search endpoint
+#[derive(Debug, Deserialize)]
+struct RecordingSearch {
+  #[serde(deserialize_with = "de::one_or_many", default)]
+  recordings: Vec<Recording>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Recording {
+  #[serde(deserialize_with = "de::mbid", default)]
+  id: Option<Mbid>,
+  #[serde(deserialize_with = "de::number", default)]
+  score: u8,
+}
+
+impl RecordingSearch {
+  fn best_match(self, min_score: u8) -> Option<Mbid> {
+    self
+      .recordings
+      .into_iter()
+      .filter(|recording| recording.score >= min_score && recording.id.is_some())
+      .max_by_key(|recording| recording.score)
+      .and_then(|recording| recording.id)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_quote_escapes_phrase() {
+    assert_eq!(quote("Believe"), r#""Believe""#);
+    assert_eq!(quote(r#"12" \ Mix"#), r#""12\" \\ Mix""#);
+  }
+
+  #[test]
+  fn test_best_match_respects_score() {
+    let json = r#"{"recordings":[
+      {"id":"b1a9c0e9-d987-4042-ae91-78d6a3267d69","score":100,"title":"Believe"},
+      {"id":"other","score":"80"}
+    ]}"#;
+
+    let search: RecordingSearch = serde_json::from_str(json).unwrap();
+    assert_eq!(
+      search.best_match(90).as_ref().map(Mbid::as_str),
+      Some("b1a9c0e9-d987-4042-ae91-78d6a3267d69")
+    );
+
+    let search: RecordingSearch =
+      serde_json::from_str(r#"{"recordings":[{"id":"x","score":50}]}"#).unwrap();
+    assert_eq!(search.best_match(90), None);
+
+    let search: RecordingSearch = serde_json::from_str(r#"{"count":0}"#).unwrap();
+    assert_eq!(search.best_match(0), None);
+  }
+
+  #[tokio::test]
+  async fn test_requests_are_spaced() {
+    tokio::time::pause();
+    let musicbrainz = MusicBrainz::new("last-fm-rs tests").unwrap();
+
+    let start = Instant::now();
+    musicbrainz.wait_turn().await;
+    musicbrainz.wait_turn().await;
+    assert!(start.elapsed() >= MIN_INTERVAL);
+  }
+}
@@ -0,0 +1,428 @@
+use std::fmt::Write;
+
+use url::form_urlencoded;
+
+#[cfg(any(feature = "scrobble", feature = "auth-flow"))]
+use crate::signature;
+#[cfg(all(feature = "scrobble", any(test, feature = "bench")))]
+use crate::signature::Signer;
+
+/// Parameter name, optionally indexed (`artist[3]`) for batch submissions
+#[derive(Debug, Clone, Copy)]
+struct Key<'a> {
+  name: &'a str,
+  index: Option<usize>,
+}
+
+impl Key<'_> {
+  fn write_to(&self, out: &mut String) {
+    out.push_str(self.name);
+    if let Some(index) = self.index {
+      // Writing into a String cannot fail
+      let _ = write!(out, "[{}]", index);
+    }
+  }
+
+  /// Bytes of the rendered key, without allocating
+  #[cfg(any(feature = "scrobble", feature = "auth-flow"))]
+  fn bytes(&self) -> impl Iterator<Item = u8> + '_ {
+    let mut suffix = [0u8; 22];
+    let mut len = 0;
+    if let Some(mut index) = self.index {
+      let mut digits = [0u8; 20];
+      let mut count = 0;
+      loop {
+        digits[count] = b'0' + (index % 10) as u8;
+        count += 1;
+        index /= 10;
+        if index == 0 {
+          break;
+        }
+      }
+      suffix[0] = b'[';
+      for (i, digit) in digits[..count].iter().rev().enumerate() {
+        suffix[i + 1] = *digit;
+      }
+      suffix[count + 1] = b']';
+      len = count + 2;
+    }
+    self.name.bytes().chain(suffix.into_iter().take(len))
+  }
+
+  #[cfg(any(feature = "scrobble", feature = "auth-flow"))]
+  fn len(&self) -> usize {
+    self.name.len()
+      + self.index.map_or(0, |index| {
+        index.checked_ilog10().map_or(1, |d| d as usize + 1) + 2
+      })
+  }
+}
+
+/// Render an integer into a stack buffer
+fn format_u64(mut n: u64, buf: &mut [u8; 20]) -> &str {
+  let mut start = buf.len();
+  loop {
+    start -= 1;
+    buf[start] = b'0' + (n % 10) as u8;
+    n /= 10;
+    if n == 0 {
+      break;
+    }
+  }
+  // Only ASCII digits were written
+  std::str::from_utf8(&buf[start..]).unwrap_or_default()
+}
+
+/// Parameter value, borrowed where possible
+#[derive(Debug, Clone)]
+pub(crate) enum Value<'a> {
+  Str(&'a str),
+  Owned(String),
+  Num(u64),
+}
+
+impl Value<'_> {
+  fn write_to(&self, out: &mut String) {
+    match self {
+      Self::Str(s) => out.push_str(s),
+      Self::Owned(s) => out.push_str(s),
+      Self::Num(n) => {
+        let _ = write!(out, "{}", n);
+      }
+    }
+  }
+}
+
+impl<'a> From<&'a str> for Value<'a> {
+  fn from(value: &'a str) -> Self {
+    Self::Str(value)
+  }
+}
+
+impl<'a> From<&'a String> for Value<'a> {
+  fn from(value: &'a String) -> Self {
+    Self::Str(value)
+  }
+}
+
+impl From<String> for Value<'_> {
+  fn from(value: String) -> Self {
+    Self::Owned(value)
+  }
+}
+
+impl From<u64> for Value<'_> {
+  fn from(value: u64) -> Self {
+    Self::Num(value)
+  }
+}
+
+impl From<u32> for Value<'_> {
+  fn from(value: u32) -> Self {
+    Self::Num(value.into())
+  }
+}
+
+/// Parameters left out of error summaries: credentials and the signature
+const SECRET: [&str; 4] = ["api_key", "sk", "token", "api_sig"];
+
+/// Parameters hidden in debug output of the signing message
+#[cfg(any(feature = "scrobble", feature = "auth-flow"))]
+const MASKED: [&str; 1] = ["sk"];
+
+/// Request parameters for a Last.fm API call
+///
+/// Collects borrowed names and values, sorts them byte-wise for signing and
+/// renders indexed batch keys (`artist[0]`) only when writing them out.
+/// `format=json` is appended to every encoded request and never signed.
+#[derive(Debug, Clone)]
+pub(crate) struct Params<'a> {
+  entries: Vec<(Key<'a>, Value<'a>)>,
+}
+
+impl<'a> Params<'a> {
+  /// Start a parameter list for an API method
+  pub fn new(method: &'a str) -> Self {
+    Self::with_capacity(method, 8)
+  }
+
+  /// Start a parameter list with room for `capacity` entries
+  pub fn with_capacity(method: &'a str, capacity: usize) -> Self {
+    let mut params = Self {
+      entries: Vec::with_capacity(capacity),
+    };
+    params.push("method", method);
+    params
+  }
+
+  /// Add a parameter
+  pub fn push(&mut self, name: &'a str, value: impl Into<Value<'a>>) -> &mut Self {
+    self.entries.push((Key { name, index: None }, value.into()));
+    self
+  }
+
+  /// Add a parameter if a value is present
+  #[cfg(any(feature = "scrobble", feature = "metadata"))]
+  pub fn push_opt<V: Into<Value<'a>>>(&mut self, name: &'a str, value: Option<V>) -> &mut Self {
+    if let Some(value) = value {
+      self.push(name, value);
+    }
+    self
+  }
+
+  /// Add an indexed batch parameter, e.g. `artist[3]`
+  #[cfg(feature = "scrobble")]
+  pub fn push_indexed(
+    &mut self,
+    name: &'a str,
+    index: usize,
+    value: impl Into<Value<'a>>,
+  ) -> &mut Self {
+    self.entries.push((
+      Key {
+        name,
+        index: Some(index),
+      },
+      value.into(),
+    ));
+    self
+  }
+
+  /// Add an indexed batch parameter if a value is present
+  #[cfg(feature = "scrobble")]
+  pub fn push_indexed_opt<V: Into<Value<'a>>>(
+    &mut self,
+    name: &'a str,
+    index: usize,
+    value: Option<V>,
+  ) -> &mut Self {
+    if let Some(value) = value {
+      self.push_indexed(name, index, value);
+    }
+    self
+  }
+
+  /// Sort entries by rendered key, byte-wise, as the signature requires
+  #[cfg(any(feature = "scrobble", feature = "auth-flow"))]
+  fn sort(&mut self) {
+    // Keys are unique, so an unstable (allocation-free) sort is enough
+    self
+      .entries
+      .sort_unstable_by(|(a, _), (b, _)| a.bytes().cmp(b.bytes()));
+  }
+
+  /// Canonical signing message: sorted name+value pairs
+  #[cfg(any(feature = "scrobble", feature = "auth-flow"))]
+  pub fn signing_message(&mut self) -> String {
+    self.sort();
+
+    let len = self
+      .entries
+      .iter()
+      .filter(|(key, _)| !signature::is_unsigned(key.name))
+      .map(|(key, value)| {
+        key.len()
+          + match value {
+            Value::Str(s) => s.len(),
+            Value::Owned(s) => s.len(),
+            Value::Num(_) => 20,
+          }
+      })
+      .sum();
+
+    let mut message = String::with_capacity(len);
+    for (key, value) in &self.entries {
+      if !signature::is_unsigned(key.name) {
+        key.write_to(&mut message);
+        value.write_to(&mut message);
+      }
+    }
+    message
+  }
+
+  /// [`signing_message`](Self::signing_message) with session keys replaced
+  /// by `****`, for debug output
+  #[cfg(any(feature = "scrobble", feature = "auth-flow"))]
+  pub fn masked_signing_message(&mut self) -> String {
+    self.sort();
+
+    let mut message = String::new();
+    for (key, value) in &self.entries {
+      if !signature::is_unsigned(key.name) {
+        key.write_to(&mut message);
+        if MASKED.contains(&key.name) {
+          message.push_str("****");
+        } else {
+          value.write_to(&mut message);
+        }
+      }
+    }
+    message
+  }
+
+  /// API method these parameters call
+  pub fn method(&self) -> &str {
+    self
+      .entries
+      .iter()
+      .find_map(|(key, value)| match (key.name, value) {
+        ("method", Value::Str(method)) => Some(*method),
+        _ => None,
+      })
+      .unwrap_or_default()
+  }
+
+  /// `name=value` pairs for error messages, without the method, credentials
+  /// or signature
+  pub fn summary(&self) -> String {
+    let mut summary = String::new();
+    for (key, value) in &self.entries {
+      if key.name == "method" || SECRET.contains(&key.name) {
+        continue;
+      }
+      if !summary.is_empty() {
+        summary.push_str(", ");
+      }
+      key.write_to(&mut summary);
+      summary.push('=');
+      value.write_to(&mut summary);
+    }
+    summary
+  }
+
+  /// Names of the parameters that go into the signature, in signing order
+  /// once signed
+  #[cfg(any(feature = "scrobble", feature = "auth-flow"))]
+  pub fn signed_names(&self) -> Vec<String> {
+    self
+      .entries
+      .iter()
+      .filter(|(key, _)| !signature::is_unsigned(key.name) && key.name != "api_sig")
+      .map(|(key, _)| {
+        let mut name = String::with_capacity(key.len());
+        key.write_to(&mut name);
+        name
+      })
+      .collect()
+  }
+
+  /// Whether any value has characters outside ASCII
+  #[cfg(any(feature = "scrobble", feature = "auth-flow"))]
+  pub fn has_non_ascii(&self) -> bool {
+    self.entries.iter().any(|(_, value)| match value {
+      Value::Str(s) => !s.is_ascii(),
+      Value::Owned(s) => !s.is_ascii(),
+      Value::Num(_) => false,
+    })
+  }
+
+  /// Sign the parameters and append `api_sig`
+  #[cfg(all(feature = "scrobble", any(test, feature = "bench")))]
+  pub fn sign(&mut self, secret: &str, signer: &dyn Signer) -> &mut Self {
+    let sig = signature::generate(self, secret, signer);
+    self.push("api_sig", sig)
+  }
+
+  /// Encode as `application/x-www-form-urlencoded`, with `format=json`
+  ///
+  /// The encoded length is measured first so the body is written into a
+  /// single allocation regardless of how many batch entries there are.
+  pub fn encode(&self) -> String {
+    let mut len = 0;
+    self.write_encoded(|piece| len += piece.len());
+
+    let mut body = String::with_capacity(len);
+    self.write_encoded(|piece| body.push_str(piece));
+    body
+  }
+
+  /// Emit the form-encoded body piece by piece, without allocating
+  fn write_encoded(&self, mut emit: impl FnMut(&str)) {
+    let mut digits = [0u8; 20];
+
+    for (key, value) in &self.entries {
+      form_urlencoded::byte_serialize(key.name.as_bytes()).for_each(&mut emit);
+      if let Some(index) = key.index {
+        emit("%5B");
+        emit(format_u64(index as u64, &mut digits));
+        emit("%5D");
+      }
+      emit("=");
+      match value {
+        Value::Str(s) => form_urlencoded::byte_serialize(s.as_bytes()).for_each(&mut emit),
+        Value::Owned(s) => form_urlencoded::byte_serialize(s.as_bytes()).for_each(&mut emit),
+        Value::Num(n) => emit(format_u64(*n, &mut digits)),
+      }
+      emit("&");
+    }
+
+    emit("format=json");
+  }
+
+  /// Full GET URL for these parameters
+  #[cfg(any(feature = "auth-flow", feature = "metadata"))]
+  pub fn url(&self, base: &str) -> String {
+    format!("{}?{}", base, self.encode())
+  }
+}
+
+#[cfg(all(test, feature = "scrobble"))]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_indexed_keys_sort_bytewise() {
+    let mut params = Params::new("track.scrobble");
+    params
+      .push_indexed("artist", 1, "B")
+      .push_indexed("artist", 10, "K")
+      .push_indexed("albumArtist", 0, "X")
+      .push_indexed("album", 0, "Y")
+      .push("api_key", "key");
+
+    // '0' (0x30) sorts before ']' (0x5D) and 'A' (0x41) before '[' (0x5B)
+    assert_eq!(
+      params.signing_message(),
+      "albumArtist[0]Xalbum[0]Yapi_keykeyartist[10]Kartist[1]Bmethodtrack.scrobble"
+    );
+  }
+
+  #[test]
+  fn test_masked_signing_message() {
+    let mut params = Params::new("track.love");
+    params
+      .push("track", "Believe")
+      .push("sk", "session")
+      .push("api_key", "key");
+    assert_eq!(params.method(), "track.love");
+    assert_eq!(
+      params.masked_signing_message(),
+      "api_keykeymethodtrack.lovesk****trackBelieve"
+    );
+  }
+
+  #[test]
+  fn test_encode_appends_format() {
+    let mut params = Params::new("track.getInfo");
+    params
+      .push("artist", "Sigur Rós")
+      .push("limit", 30u32)
+      .push_opt::<&str>("username", None);
+
+    assert_eq!(
+      params.encode(),
+      "method=track.getInfo&artist=Sigur+R%C3%B3s&limit=30&format=json"
+    );
+  }
+
+  #[test]
+  fn test_signing_ignores_format_and_appends_sig() {
+    let mut params = Params::new("auth.getSession");
+    params.push("api_key", "testkey").push("token", "testtoken");
+    params.sign("testsecret", &signature::Md5Signer);
+
+    assert!(params
+      .encode()
+      .ends_with("api_sig=7e846232cc93d093646ff399db5c3d75&format=json"));
+  }
+}
@@ -0,0 +1,62 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Time range accepted by Last.fm's chart-style user and tag methods
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Period {
+  #[default]
+  #[serde(rename = "overall")]
+  Overall,
+  #[serde(rename = "7day")]
+  SevenDay,
+  #[serde(rename = "1month")]
+  OneMonth,
+  #[serde(rename = "3month")]
+  ThreeMonth,
+  #[serde(rename = "6month")]
+  SixMonth,
+  #[serde(rename = "12month")]
+  TwelveMonth,
+}
+
+impl Period {
+  /// Value sent as the `period` request parameter
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Overall => "overall",
+      Self::SevenDay => "7day",
+      Self::OneMonth => "1month",
+      Self::ThreeMonth => "3month",
+      Self::SixMonth => "6month",
+      Self::TwelveMonth => "12month",
+    }
+  }
+}
+
+impl fmt::Display for Period {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_wire_encoding_matches_serde() {
+    for period in [
+      Period::Overall,
+      Period::SevenDay,
+      Period::OneMonth,
+      Period::ThreeMonth,
+      Period::SixMonth,
+      Period::TwelveMonth,
+    ] {
+      let json = serde_json::to_string(&period).unwrap();
+      assert_eq!(json, format!("\"{}\"", period.as_str()));
+      assert_eq!(serde_json::from_str::<Period>(&json).unwrap(), period);
+    }
+  }
+}
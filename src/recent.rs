@@ -0,0 +1,379 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use futures::Stream;
+use serde::{Deserialize, Deserializer, Serialize};
+use url::Url;
+
+use crate::client::Client;
+use crate::date::LastfmDate;
+use crate::de;
+use crate::error::Result;
+use crate::mbid::Mbid;
+use crate::scrobble::write_track;
+use crate::search::{PageAttr, SearchPage};
+use crate::track::{Album, Artist, Image};
+
+/// Largest page size accepted by user.getRecentTracks
+pub(crate) const MAX_LIMIT: u32 = 200;
+
+/// Filters for a user's listening history
+///
+/// # Example
+///
+/// ```
+/// use last_fm_rs::RecentTracksOptions;
+///
+/// let options = RecentTracksOptions::new()
+///   .with_from(1_700_000_000)
+///   .with_limit(50);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RecentTracksOptions {
+  pub limit: u32,
+  pub from: Option<u64>,
+  pub to: Option<u64>,
+}
+
+impl RecentTracksOptions {
+  /// Fetch the whole history, 200 scrobbles per request
+  pub fn new() -> Self {
+    Self {
+      limit: MAX_LIMIT,
+      from: None,
+      to: None,
+    }
+  }
+
+  /// Set the page size (clamped to 1..=200)
+  pub fn with_limit(mut self, limit: u32) -> Self {
+    self.limit = limit.clamp(1, MAX_LIMIT);
+    self
+  }
+
+  /// Only include scrobbles at or after this unix timestamp
+  pub fn with_from(mut self, from: u64) -> Self {
+    self.from = Some(from);
+    self
+  }
+
+  /// Only include scrobbles at or before this unix timestamp
+  pub fn with_to(mut self, to: u64) -> Self {
+    self.to = Some(to);
+    self
+  }
+}
+
+impl Default for RecentTracksOptions {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// Entry in a user's listening history from user.getRecentTracks
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct RecentTrack {
+  #[serde(deserialize_with = "de::string", default)]
+  pub name: String,
+  #[serde(deserialize_with = "de::mbid", default)]
+  pub mbid: Option<Mbid>,
+  /// Last.fm page, `None` when missing or malformed
+  #[serde(deserialize_with = "de::url", default)]
+  pub url: Option<Url>,
+  pub artist: Artist,
+  #[serde(deserialize_with = "de::option", default)]
+  pub album: Option<Album>,
+  /// Available sizes; entries Last.fm sends without a URL are dropped
+  #[serde(deserialize_with = "de::images", default)]
+  pub image: Vec<Image>,
+  /// Still playing, or scrobbled and when
+  #[serde(flatten)]
+  pub state: RecentTrackState,
+  /// Fields this version doesn't model yet
+  #[serde(flatten)]
+  #[cfg_attr(feature = "strict-schema", serde(deserialize_with = "de::no_extra"))]
+  pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl RecentTrack {
+  /// Unix timestamp of the scrobble, `None` while the track is still playing
+  pub fn timestamp(&self) -> Option<u64> {
+    match &self.state {
+      RecentTrackState::NowPlaying => None,
+      RecentTrackState::Scrobbled { date } => Some(date.timestamp()),
+    }
+  }
+
+  /// Whether this entry is the track the user is listening to right now
+  pub fn is_now_playing(&self) -> bool {
+    self.state == RecentTrackState::NowPlaying
+  }
+}
+
+/// `Artist – Track (Album)`
+impl fmt::Display for RecentTrack {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let album = self.album.as_ref().map(|album| album.title.as_str());
+    write_track(f, &self.artist.name, &self.name, album)
+  }
+}
+
+#[cfg(feature = "chrono")]
+impl RecentTrack {
+  /// Scrobble time as a UTC datetime
+  pub fn datetime(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+    self
+      .timestamp()
+      .and_then(|uts| i64::try_from(uts).ok())
+      .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+  }
+}
+
+/// Whether a [`RecentTrack`] is still playing or already scrobbled
+///
+/// Last.fm lists the track being played on top of the history, flagged
+/// with `@attr.nowplaying` and without a date.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "RawState", into = "RawState")]
+pub enum RecentTrackState {
+  NowPlaying,
+  Scrobbled { date: LastfmDate },
+}
+
+/// Wire form of [`RecentTrackState`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RawState {
+  #[serde(
+    rename = "@attr",
+    deserialize_with = "de::option",
+    default,
+    skip_serializing_if = "Option::is_none"
+  )]
+  attr: Option<NowPlayingAttr>,
+  #[serde(
+    deserialize_with = "de::option",
+    default,
+    skip_serializing_if = "Option::is_none"
+  )]
+  date: Option<LastfmDate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-schema", serde(deny_unknown_fields))]
+struct NowPlayingAttr {
+  #[serde(deserialize_with = "de::string", default)]
+  nowplaying: String,
+}
+
+impl TryFrom<RawState> for RecentTrackState {
+  type Error = String;
+
+  fn try_from(raw: RawState) -> std::result::Result<Self, String> {
+    let now_playing = raw.attr.is_some_and(|attr| attr.nowplaying == "true");
+    match (now_playing, raw.date) {
+      (true, _) => Ok(Self::NowPlaying),
+      (false, Some(date)) => Ok(Self::Scrobbled { date }),
+      (false, None) => Err("recent track is neither playing nor dated".to_string()),
+    }
+  }
+}
+
+impl From<RecentTrackState> for RawState {
+  fn from(state: RecentTrackState) -> Self {
+    match state {
+      RecentTrackState::NowPlaying => Self {
+        attr: Some(NowPlayingAttr {
+          nowplaying: "true".to_string(),
+        }),
+        date: None,
+      },
+      RecentTrackState::Scrobbled { date } => Self {
+        attr: None,
+        date: Some(date),
+      },
+    }
+  }
+}
+
+/// Response wrapper for user.getRecentTracks
+#[derive(Debug, Deserialize)]
+pub(crate) struct RecentTracksResponse {
+  recenttracks: RecentTracks,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecentTracks {
+  #[serde(deserialize_with = "dated_or_playing", default)]
+  track: Vec<RecentTrack>,
+  #[serde(rename = "@attr", default)]
+  attr: PageAttr,
+}
+
+/// Parse a page's entries, dropping any that are neither playing nor dated
+/// instead of failing the whole page over one of them
+fn dated_or_playing<'de, D>(deserializer: D) -> std::result::Result<Vec<RecentTrack>, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  let entries: Vec<serde_json::Value> = de::one_or_many(deserializer)?;
+  entries
+    .iter()
+    .filter(|entry| {
+      let state = RawState::deserialize(*entry).map(RecentTrackState::try_from);
+      !matches!(state, Ok(Err(_)))
+    })
+    .map(|entry| RecentTrack::deserialize(entry).map_err(serde::de::Error::custom))
+    .collect()
+}
+
+impl From<RecentTracksResponse> for SearchPage<RecentTrack> {
+  fn from(response: RecentTracksResponse) -> Self {
+    let RecentTracks { track, attr } = response.recenttracks;
+    // The now-playing entry is reported on top of the requested page size
+    let items: Vec<RecentTrack> = track
+      .into_iter()
+      .filter(|track| !track.is_now_playing())
+      .collect();
+    attr.into_page(items)
+  }
+}
+
+impl RecentTracksResponse {
+  /// The entry flagged as playing right now, if any
+  pub(crate) fn now_playing(self) -> Option<RecentTrack> {
+    self
+      .recenttracks
+      .track
+      .into_iter()
+      .find(RecentTrack::is_now_playing)
+  }
+}
+
+/// Pagination state behind [`Client::recent_tracks_stream`]
+struct Pager {
+  client: Client,
+  user: String,
+  options: RecentTracksOptions,
+  page: u32,
+  buffer: VecDeque<RecentTrack>,
+  done: bool,
+}
+
+/// Stream every scrobble matching `options`, newest first, one page at a time
+///
+/// Ends after the last page or after yielding the first error.
+pub(crate) fn stream(
+  client: Client,
+  user: String,
+  options: RecentTracksOptions,
+) -> impl Stream<Item = Result<RecentTrack>> + Send + 'static {
+  let pager = Pager {
+    client,
+    user,
+    options,
+    page: 1,
+    buffer: VecDeque::new(),
+    done: false,
+  };
+
+  futures::stream::unfold(pager, |mut pager| async move {
+    loop {
+      if let Some(track) = pager.buffer.pop_front() {
+        return Some((Ok(track), pager));
+      }
+      if pager.done {
+        return None;
+      }
+      let page = pager
+        .client
+        .recent_tracks(&pager.user, &pager.options, pager.page)
+        .await;
+      match page {
+        Ok(page) => {
+          pager.done = !page.has_next_page();
+          pager.page += 1;
+          pager.buffer.extend(page.items);
+        }
+        Err(err) => {
+          pager.done = true;
+          return Some((Err(err), pager));
+        }
+      }
+    }
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_recent_tracks_page() {
+    let json = r##"{"recenttracks":{
+      "track":[
+        {"artist":{"mbid":"","#text":"Cher"},"name":"Believe","@attr":{"nowplaying":"true"},
+         "album":{"mbid":"","#text":"Believe"},"image":[]},
+        {"artist":{"mbid":"","#text":"Cher"},"name":"Strong Enough",
+         "album":{"mbid":"","#text":"Believe"},
+         "date":{"uts":"1700000000","#text":"14 Nov 2023, 22:13"}}
+      ],
+      "@attr":{"user":"rj","totalPages":"3","page":"2","perPage":"1","total":"3"}
+    }}"##;
+
+    let response: RecentTracksResponse = serde_json::from_str(json).unwrap();
+    let page = SearchPage::from(response);
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(page.page(), 2);
+    assert_eq!(page.total_pages(), 3);
+    assert!(page.has_next_page());
+
+    let track = &page.items[0];
+    assert_eq!(track.artist.name, "Cher");
+    assert_eq!(
+      track.album.as_ref().map(|album| album.title.as_str()),
+      Some("Believe")
+    );
+    assert_eq!(track.timestamp(), Some(1_700_000_000));
+    assert!(!track.is_now_playing());
+  }
+
+  #[test]
+  fn test_state() {
+    let playing: RecentTrack = serde_json::from_str(
+      r##"{"artist":{"#text":"Cher"},"name":"Believe","@attr":{"nowplaying":"true"}}"##,
+    )
+    .unwrap();
+    assert_eq!(playing.state, RecentTrackState::NowPlaying);
+    assert!(playing.extra.is_empty());
+
+    let json = serde_json::to_string(&playing).unwrap();
+    assert_eq!(serde_json::from_str::<RecentTrack>(&json).unwrap(), playing);
+
+    let undated =
+      serde_json::from_str::<RecentTrack>(r##"{"artist":{"#text":"Cher"},"name":"Believe"}"##);
+    assert!(undated.is_err());
+  }
+
+  #[test]
+  fn test_undated_entries_are_skipped() {
+    let json = r##"{"recenttracks":{"track":[
+      {"artist":{"#text":"Cher"},"name":"Believe"},
+      {"artist":{"#text":"Cher"},"name":"Strong Enough","date":{"uts":"1700000000"}}
+    ]}}"##;
+
+    let page = SearchPage::from(serde_json::from_str::<RecentTracksResponse>(json).unwrap());
+    let names: Vec<&str> = page.items.iter().map(|track| track.name.as_str()).collect();
+    assert_eq!(names, ["Strong Enough"]);
+
+    let no_artist =
+      r##"{"recenttracks":{"track":[{"name":"Believe","date":{"uts":"1700000000"}}]}}"##;
+    assert!(serde_json::from_str::<RecentTracksResponse>(no_artist).is_err());
+  }
+
+  #[test]
+  fn test_options_clamp_limit() {
+    assert_eq!(RecentTracksOptions::new().with_limit(1000).limit, MAX_LIMIT);
+    assert_eq!(RecentTracksOptions::new().with_limit(0).limit, 1);
+  }
+}
@@ -0,0 +1,182 @@
+use serde::de::{DeserializeOwned, Error as _, IgnoredAny};
+use serde::{Deserialize, Deserializer};
+
+use crate::error::{ErrorResponse, Result};
+
+/// Response envelope shared by every Last.fm endpoint
+///
+/// Last.fm answers either with the requested payload or with an
+/// `{"error": code, "message": "..."}` body, often with a 200 status.
+#[derive(Debug)]
+pub(crate) enum ApiResponse<T> {
+  Ok(T),
+  Error(ErrorResponse),
+}
+
+impl<T> ApiResponse<T> {
+  /// Convert the envelope into a crate result
+  pub fn into_result(self) -> Result<T> {
+    match self {
+      Self::Ok(payload) => Ok(payload),
+      Self::Error(error) => Err(error.into_error()),
+    }
+  }
+}
+
+impl<'de, T: DeserializeOwned> Deserialize<'de> for ApiResponse<T> {
+  fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    let value = serde_json::Value::deserialize(deserializer)?;
+
+    if value.get("error").is_some() {
+      ErrorResponse::deserialize(value)
+        .map(Self::Error)
+        .map_err(D::Error::custom)
+    } else {
+      T::deserialize(value)
+        .map(Self::Ok)
+        .map_err(D::Error::custom)
+    }
+  }
+}
+
+/// Read a response body, turning non-2xx statuses into errors
+///
+/// Last.fm answers some failures with a 4xx status and a JSON error body;
+/// when the body parses as one, its typed error is returned rather than the
+/// bare status.
+pub(crate) async fn text(resp: reqwest::Response) -> Result<String> {
+  let status_error = resp.error_for_status_ref().err();
+  let body = resp.text().await?;
+  match status_error {
+    None => Ok(body),
+    Some(status_error) => match serde_json::from_str::<ApiResponse<IgnoredAny>>(&body) {
+      Ok(ApiResponse::Error(error)) => Err(error.into_error()),
+      _ => Err(status_error.into()),
+    },
+  }
+}
+
+/// Parse a response body into the expected payload type
+pub(crate) fn parse<T: DeserializeOwned>(body: &str) -> Result<T> {
+  serde_json::from_str::<ApiResponse<T>>(body)?.into_result()
+}
+
+/// Parse a response body, keeping the JSON alongside the typed payload
+#[cfg(feature = "metadata")]
+pub(crate) fn parse_parts<T: DeserializeOwned>(body: &str) -> Result<ResponseParts<T>> {
+  let raw_json: serde_json::Value = serde_json::from_str(body)?;
+  let typed = ApiResponse::<T>::deserialize(&raw_json)?.into_result()?;
+  Ok(ResponseParts { typed, raw_json })
+}
+
+/// A typed result together with the JSON it was parsed from
+///
+/// Returned by the `*_raw` client methods, for reading fields the typed
+/// models don't cover.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// use last_fm_rs::Client;
+///
+/// let client = Client::new("api_key", "secret");
+///
+/// let parts = client.track_get_info_raw("Cher", "Believe", None).await?;
+/// println!("{} listeners", parts.typed.listeners);
+/// // Fields Last.fm adds later are still reachable
+/// if let Some(value) = parts.raw_json["track"].get("new_field") {
+///   println!("new_field: {}", value);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "metadata")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseParts<T> {
+  pub typed: T,
+  /// The response body as Last.fm sent it
+  pub raw_json: serde_json::Value,
+}
+
+#[cfg(feature = "metadata")]
+impl<T> ResponseParts<T> {
+  /// Convert the typed part, keeping the JSON
+  pub fn map<U>(self, f: impl FnOnce(T) -> U) -> ResponseParts<U> {
+    ResponseParts {
+      typed: f(self.typed),
+      raw_json: self.raw_json,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::error::Error;
+  use crate::params::Params;
+
+  #[derive(Debug, Deserialize)]
+  struct Payload {
+    token: String,
+  }
+
+  #[test]
+  fn test_parse_payload() {
+    let payload: Payload = parse(r#"{"token":"abc"}"#).expect("valid payload");
+    assert_eq!(payload.token, "abc");
+  }
+
+  #[cfg(feature = "metadata")]
+  #[test]
+  fn test_parse_parts_keeps_json() {
+    let parts: ResponseParts<Payload> =
+      parse_parts(r#"{"token":"abc","expires":3600}"#).expect("valid payload");
+    assert_eq!(parts.typed.token, "abc");
+    assert_eq!(parts.raw_json["expires"], 3600);
+
+    let result = parse_parts::<Payload>(r#"{"error":29,"message":"Rate Limit Exceded"}"#);
+    assert!(matches!(result, Err(Error::RateLimited { .. })));
+  }
+
+  #[test]
+  fn test_parse_error_body() {
+    let result = parse::<Payload>(r#"{"error":9,"message":"Invalid session key"}"#)
+      .map_err(|error| error.with_context(&Params::new("auth.getSession")));
+    assert_eq!(
+      result.as_ref().unwrap_err().to_string(),
+      "Last.fm API error 9 from auth.getSession: Invalid session key"
+    );
+    match result {
+      Err(Error::Api { code, message, .. }) => {
+        assert_eq!(code, 9);
+        assert_eq!(message, "Invalid session key");
+      }
+      other => panic!("unexpected result: {:?}", other),
+    }
+  }
+
+  #[test]
+  fn test_parse_rate_limit() {
+    let result = parse::<Payload>(r#"{"error":29,"message":"Rate Limit Exceded"}"#);
+    assert!(matches!(result, Err(Error::RateLimited { .. })));
+
+    let result = parse::<Payload>(r#"{"error":26,"message":"Suspended API key"}"#);
+    assert!(matches!(result, Err(Error::Auth { code: Some(26), .. })));
+  }
+
+  #[test]
+  fn test_parse_malformed_body() {
+    assert!(matches!(
+      parse::<Payload>(r#"{"token":1}"#),
+      Err(Error::Json { .. })
+    ));
+    assert!(matches!(
+      parse::<Payload>("<html>"),
+      Err(Error::Json { .. })
+    ));
+  }
+}
@@ -0,0 +1,252 @@
+#[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+use std::future::Future;
+use std::time::Duration;
+
+use crate::error::Error;
+#[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+use crate::error::Result;
+
+/// Decides whether a failed request is tried again, and after how long
+///
+/// Consulted after every failure with the API method (e.g. `track.scrobble`),
+/// the error and the number of attempts made so far, starting at 1.
+/// [`is_idempotent`] tells calls that are safe to repeat apart from
+/// scrobble submissions, which may be recorded twice.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use last_fm_rs::{Error, RetryPolicy};
+///
+/// /// Retry scrobbles forever, but never anything else
+/// struct PersistentScrobbles;
+///
+/// impl RetryPolicy for PersistentScrobbles {
+///   fn retry_after(&self, method: &str, error: &Error, _attempt: u32) -> Option<Duration> {
+///     (method == "track.scrobble" && error.is_transient()).then(|| Duration::from_secs(30))
+///   }
+/// }
+/// ```
+pub trait RetryPolicy: Send + Sync {
+  /// Delay before the next attempt, or `None` to give up and return `error`
+  fn retry_after(&self, method: &str, error: &Error, attempt: u32) -> Option<Duration>;
+}
+
+/// Whether repeating a call to `method` is harmless
+///
+/// False only for scrobble submissions (`track.scrobble`, and `scrob` in
+/// token mode): if a request times out after the server recorded it,
+/// sending it again records the plays twice. Everything else either reads
+/// data or sets state that a repeat leaves unchanged.
+pub fn is_idempotent(method: &str) -> bool {
+  !matches!(method, "track.scrobble" | "scrob")
+}
+
+/// Whether `error` proves the server didn't act on the request
+///
+/// Connection failures happen before anything is sent, and API errors and
+/// rate limiting are the server declining the call.
+fn was_rejected(error: &Error) -> bool {
+  match error {
+    Error::RateLimited { .. } | Error::Api { .. } => true,
+    Error::Http { source: err, .. } => err.is_connect(),
+    _ => false,
+  }
+}
+
+/// Never retry
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoRetry;
+
+impl RetryPolicy for NoRetry {
+  fn retry_after(&self, _method: &str, _error: &Error, _attempt: u32) -> Option<Duration> {
+    None
+  }
+}
+
+/// Retry transient failures with exponentially growing delays
+///
+/// The default policy: up to 3 retries, waiting 500ms, 1s, then 2s.
+/// See [`Error::is_transient`] for what counts as transient.
+///
+/// Calls that aren't [idempotent](is_idempotent), i.e. scrobble
+/// submissions, are only retried when the failure shows the server didn't
+/// record them, such as a refused connection or rate limiting. A timeout
+/// might have landed after the plays were recorded, so it isn't retried
+/// unless [`with_retry_non_idempotent`](Self::with_retry_non_idempotent)
+/// opts in, accepting the risk of duplicate scrobbles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExponentialBackoff {
+  pub max_retries: u32,
+  pub initial_delay: Duration,
+  pub max_delay: Duration,
+  pub retry_non_idempotent: bool,
+}
+
+impl ExponentialBackoff {
+  pub fn new() -> Self {
+    Self {
+      max_retries: 3,
+      initial_delay: Duration::from_millis(500),
+      max_delay: Duration::from_secs(30),
+      retry_non_idempotent: false,
+    }
+  }
+
+  pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+    self.max_retries = max_retries;
+    self
+  }
+
+  pub fn with_initial_delay(mut self, initial_delay: Duration) -> Self {
+    self.initial_delay = initial_delay;
+    self
+  }
+
+  /// Cap on any single delay
+  pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+    self.max_delay = max_delay;
+    self
+  }
+
+  /// Retry every transient failure of scrobble submissions too, even when
+  /// the server may already have recorded them
+  pub fn with_retry_non_idempotent(mut self, enable: bool) -> Self {
+    self.retry_non_idempotent = enable;
+    self
+  }
+}
+
+impl Default for ExponentialBackoff {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl RetryPolicy for ExponentialBackoff {
+  fn retry_after(&self, method: &str, error: &Error, attempt: u32) -> Option<Duration> {
+    if attempt > self.max_retries || !error.is_transient() {
+      return None;
+    }
+    if !self.retry_non_idempotent && !is_idempotent(method) && !was_rejected(error) {
+      return None;
+    }
+    let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+    Some(
+      self
+        .initial_delay
+        .saturating_mul(factor)
+        .min(self.max_delay),
+    )
+  }
+}
+
+/// Run `call` until it succeeds or `policy` gives up
+#[cfg(any(feature = "scrobble", feature = "auth-flow", feature = "metadata"))]
+pub(crate) async fn run<T, F, Fut>(policy: &dyn RetryPolicy, method: &str, mut call: F) -> Result<T>
+where
+  F: FnMut() -> Fut,
+  Fut: Future<Output = Result<T>>,
+{
+  let mut attempt = 0;
+  loop {
+    attempt += 1;
+    match call().await {
+      Err(error) => match policy.retry_after(method, &error, attempt) {
+        Some(delay) => {
+          #[cfg(feature = "log")]
+          log::debug!(
+            "{} attempt {} failed, retrying in {:?}: {}",
+            method,
+            attempt,
+            delay,
+            error.redacted()
+          );
+          tokio::time::sleep(delay).await
+        }
+        None => return Err(error),
+      },
+      result => return result,
+    }
+  }
+}
+
+#[cfg(all(
+  test,
+  any(feature = "scrobble", feature = "auth-flow", feature = "metadata")
+))]
+mod tests {
+  use std::sync::atomic::{AtomicU32, Ordering};
+
+  use super::*;
+
+  #[test]
+  fn test_exponential_delays() {
+    let policy = ExponentialBackoff::new().with_max_delay(Duration::from_secs(1));
+    let delays: Vec<Option<Duration>> = (1..=4)
+      .map(|attempt| policy.retry_after("track.getInfo", &Error::Timeout, attempt))
+      .collect();
+    assert_eq!(
+      delays,
+      [
+        Some(Duration::from_millis(500)),
+        Some(Duration::from_secs(1)),
+        Some(Duration::from_secs(1)),
+        None,
+      ]
+    );
+
+    let error = Error::Api {
+      method: "track.getInfo".to_string(),
+      params: String::new(),
+      code: 6,
+      message: "Track not found".to_string(),
+    };
+    assert_eq!(policy.retry_after("track.getInfo", &error, 1), None);
+  }
+
+  #[test]
+  fn test_non_idempotent_calls_need_opt_in() {
+    let policy = ExponentialBackoff::new();
+    assert!(policy
+      .retry_after("track.getInfo", &Error::Timeout, 1)
+      .is_some());
+    assert!(policy
+      .retry_after("track.scrobble", &Error::Timeout, 1)
+      .is_none());
+    assert!(policy.retry_after("scrob", &Error::Timeout, 1).is_none());
+    assert!(policy
+      .retry_after("track.scrobble", &Error::rate_limited(), 1)
+      .is_some());
+
+    let policy = policy.with_retry_non_idempotent(true);
+    assert!(policy
+      .retry_after("track.scrobble", &Error::Timeout, 1)
+      .is_some());
+  }
+
+  #[tokio::test]
+  async fn test_run_stops_when_policy_gives_up() {
+    tokio::time::pause();
+    let calls = AtomicU32::new(0);
+    let policy = ExponentialBackoff::new().with_max_retries(2);
+
+    let result: Result<()> = run(&policy, "track.scrobble", || async {
+      calls.fetch_add(1, Ordering::Relaxed);
+      Err(Error::rate_limited())
+    })
+    .await;
+    assert!(matches!(result, Err(Error::RateLimited { .. })));
+    assert_eq!(calls.load(Ordering::Relaxed), 3);
+
+    calls.store(0, Ordering::Relaxed);
+    let result: Result<()> = run(&NoRetry, "track.scrobble", || async {
+      calls.fetch_add(1, Ordering::Relaxed);
+      Err(Error::Timeout)
+    })
+    .await;
+    assert!(result.is_err());
+    assert_eq!(calls.load(Ordering::Relaxed), 1);
+  }
+}
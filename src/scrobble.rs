@@ -1,14 +1,51 @@
+#[cfg(feature = "scrobble")]
+use std::borrow::Borrow;
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 use serde::{Deserialize, Serialize};
 
+use crate::de;
+use crate::error::{Error, Result};
+use crate::mbid::Mbid;
+#[cfg(feature = "scrobble")]
+use crate::params::Params;
+#[cfg(all(feature = "scrobble", any(test, feature = "bench")))]
+use crate::signature::Md5Signer;
+
 /// "Now Playing" notification
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct NowPlaying {
   pub artist: String,
   pub track: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub album: Option<String>,
+  #[serde(
+    deserialize_with = "de::option_number",
+    default,
+    skip_serializing_if = "Option::is_none"
+  )]
   pub track_number: Option<u32>,
-  pub duration: Option<u64>,
+  #[serde(
+    with = "de::duration_secs",
+    default,
+    skip_serializing_if = "Option::is_none"
+  )]
+  #[cfg_attr(feature = "schemars", schemars(with = "Option<u64>"))]
+  pub duration: Option<Duration>,
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub album_artist: Option<String>,
+  /// MusicBrainz recording ID
+  #[serde(
+    deserialize_with = "de::mbid",
+    default,
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub mbid: Option<Mbid>,
+  /// Name of the application playing the track, sent to Last.fm as
+  /// `context`
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub player: Option<String>,
 }
 
@@ -21,10 +58,24 @@ impl NowPlaying {
       track_number: None,
       duration: None,
       album_artist: None,
+      mbid: None,
       player: None,
     }
   }
 
+  /// Like [`NowPlaying::new`], but rejecting a blank artist or track
+  pub fn try_new(artist: impl Into<String>, track: impl Into<String>) -> Result<Self> {
+    let now_playing = Self::new(artist, track);
+    now_playing.validate()?;
+    Ok(now_playing)
+  }
+
+  /// Check the fields Last.fm requires, which it would otherwise ignore silently
+  pub fn validate(&self) -> Result<()> {
+    require("artist", &self.artist)?;
+    require("track", &self.track)
+  }
+
   pub fn with_album(mut self, album: impl Into<String>) -> Self {
     self.album = Some(album.into());
     self
@@ -35,7 +86,8 @@ impl NowPlaying {
     self
   }
 
-  pub fn with_duration(mut self, duration: u64) -> Self {
+  /// Set the track length (submitted to the API in whole seconds)
+  pub fn with_duration(mut self, duration: Duration) -> Self {
     self.duration = Some(duration);
     self
   }
@@ -45,31 +97,75 @@ impl NowPlaying {
     self
   }
 
+  /// Set the MusicBrainz recording ID
+  pub fn with_mbid(mut self, mbid: Mbid) -> Self {
+    self.mbid = Some(mbid);
+    self
+  }
+
   pub fn with_player(mut self, player: impl Into<String>) -> Self {
     self.player = Some(player.into());
     self
   }
+
+  /// Turn the notification into the scrobble for the same play, once the
+  /// track has played long enough
+  ///
+  /// `timestamp` is when the track started playing.
+  pub fn into_scrobble(self, timestamp: impl Into<Timestamp>) -> Scrobble {
+    Scrobble {
+      artist: self.artist,
+      track: self.track,
+      timestamp: timestamp.into().as_secs(),
+      album: self.album,
+      track_number: self.track_number,
+      duration: self.duration,
+      album_artist: self.album_artist,
+      mbid: self.mbid,
+      player: self.player,
+    }
+  }
 }
 
 /// Scrobble submission
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Scrobble {
   pub artist: String,
   pub track: String,
   pub timestamp: u64,
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub album: Option<String>,
+  #[serde(
+    deserialize_with = "de::option_number",
+    default,
+    skip_serializing_if = "Option::is_none"
+  )]
   pub track_number: Option<u32>,
-  pub duration: Option<u64>,
+  #[serde(
+    with = "de::duration_secs",
+    default,
+    skip_serializing_if = "Option::is_none"
+  )]
+  #[cfg_attr(feature = "schemars", schemars(with = "Option<u64>"))]
+  pub duration: Option<Duration>,
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub album_artist: Option<String>,
+  /// MusicBrainz recording ID
+  #[serde(
+    deserialize_with = "de::mbid",
+    default,
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub mbid: Option<Mbid>,
+  /// Name of the application playing the track, sent to Last.fm as
+  /// `context`
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub player: Option<String>,
 }
 
 impl Scrobble {
-  pub fn new(
-    artist: impl Into<String>,
-    track: impl Into<String>,
-    timestamp: u64,
-  ) -> Self {
+  pub fn new(artist: impl Into<String>, track: impl Into<String>, timestamp: u64) -> Self {
     Self {
       artist: artist.into(),
       track: track.into(),
@@ -78,10 +174,74 @@ impl Scrobble {
       track_number: None,
       duration: None,
       album_artist: None,
+      mbid: None,
       player: None,
     }
   }
 
+  /// Like [`Scrobble::new`], but rejecting a blank artist or track and a
+  /// zero timestamp
+  pub fn try_new(
+    artist: impl Into<String>,
+    track: impl Into<String>,
+    timestamp: u64,
+  ) -> Result<Self> {
+    let scrobble = Self::new(artist, track, timestamp);
+    scrobble.validate()?;
+    Ok(scrobble)
+  }
+
+  /// Check the fields Last.fm requires, which it would otherwise ignore silently
+  pub fn validate(&self) -> Result<()> {
+    require("artist", &self.artist)?;
+    require("track", &self.track)?;
+    Timestamp::new(self.timestamp).validate()?;
+    Ok(())
+  }
+
+  /// Scrobble a track that started playing just now
+  pub fn now(artist: impl Into<String>, track: impl Into<String>) -> Self {
+    Self::started_at(artist, track, Timestamp::now())
+  }
+
+  /// Scrobble a track that started playing at `started`
+  ///
+  /// ```
+  /// use std::time::{Duration, SystemTime};
+  /// use last_fm_rs::Scrobble;
+  ///
+  /// let started = SystemTime::now() - Duration::from_secs(200);
+  /// let scrobble = Scrobble::started_at("Cher", "Believe", started);
+  /// ```
+  pub fn started_at(
+    artist: impl Into<String>,
+    track: impl Into<String>,
+    started: impl Into<Timestamp>,
+  ) -> Self {
+    Self::new(artist, track, started.into().as_secs())
+  }
+
+  /// Start building a scrobble, with the timestamp given as a [`Timestamp`]
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use std::time::{Duration, SystemTime};
+  /// use last_fm_rs::Scrobble;
+  ///
+  /// let scrobble = Scrobble::builder("Kendrick Lamar", "Wesley's Theory")
+  ///   .timestamp(SystemTime::now() - Duration::from_secs(287))
+  ///   .album("To Pimp a Butterfly")
+  ///   .duration(Duration::from_secs(287))
+  ///   .build()?;
+  /// # Ok::<(), last_fm_rs::Error>(())
+  /// ```
+  pub fn builder(artist: impl Into<String>, track: impl Into<String>) -> ScrobbleBuilder {
+    ScrobbleBuilder {
+      scrobble: Self::new(artist, track, 0),
+    }
+  }
+
   pub fn with_album(mut self, album: impl Into<String>) -> Self {
     self.album = Some(album.into());
     self
@@ -92,7 +252,8 @@ impl Scrobble {
     self
   }
 
-  pub fn with_duration(mut self, duration: u64) -> Self {
+  /// Set the track length (submitted to the API in whole seconds)
+  pub fn with_duration(mut self, duration: Duration) -> Self {
     self.duration = Some(duration);
     self
   }
@@ -102,26 +263,642 @@ impl Scrobble {
     self
   }
 
+  /// Set the MusicBrainz recording ID
+  pub fn with_mbid(mut self, mbid: Mbid) -> Self {
+    self.mbid = Some(mbid);
+    self
+  }
+
   pub fn with_player(mut self, player: impl Into<String>) -> Self {
     self.player = Some(player.into());
     self
   }
 }
 
+/// How far ahead of the local clock a timestamp may be before Last.fm
+/// ignores it
+const MAX_CLOCK_SKEW: u64 = 86_400;
+
+/// Unix timestamp in seconds, convertible from the usual time types
+///
+/// Times before the unix epoch become `0`, which [`Scrobble::validate`]
+/// rejects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Timestamp(u64);
+
+impl Timestamp {
+  /// `secs` seconds after the unix epoch
+  pub const fn new(secs: u64) -> Self {
+    Self(secs)
+  }
+
+  /// Seconds since the unix epoch
+  pub const fn as_secs(self) -> u64 {
+    self.0
+  }
+
+  /// The current time
+  pub fn now() -> Self {
+    SystemTime::now().into()
+  }
+
+  /// Check that Last.fm would accept this time: after the unix epoch and no
+  /// more than a day ahead of the local clock
+  pub fn validate(self) -> Result<Self> {
+    if self.0 == 0 {
+      return Err(Error::InvalidParameter(
+        "Scrobble timestamp is zero".to_string(),
+      ));
+    }
+    if self.0 > Self::now().0.saturating_add(MAX_CLOCK_SKEW) {
+      return Err(Error::InvalidParameter(
+        "Scrobble timestamp is more than a day in the future".to_string(),
+      ));
+    }
+    Ok(self)
+  }
+}
+
+impl From<u64> for Timestamp {
+  fn from(secs: u64) -> Self {
+    Self(secs)
+  }
+}
+
+/// Time since the unix epoch
+impl From<Duration> for Timestamp {
+  fn from(since_epoch: Duration) -> Self {
+    Self(since_epoch.as_secs())
+  }
+}
+
+impl From<SystemTime> for Timestamp {
+  fn from(time: SystemTime) -> Self {
+    time
+      .duration_since(UNIX_EPOCH)
+      .map(Self::from)
+      .unwrap_or_default()
+  }
+}
+
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> From<chrono::DateTime<Tz>> for Timestamp {
+  fn from(time: chrono::DateTime<Tz>) -> Self {
+    Self(time.timestamp().max(0) as u64)
+  }
+}
+
+/// Builder for [`Scrobble`], created by [`Scrobble::builder`]
+#[derive(Debug, Clone)]
+pub struct ScrobbleBuilder {
+  scrobble: Scrobble,
+}
+
+impl ScrobbleBuilder {
+  /// When the track started playing
+  pub fn timestamp(mut self, timestamp: impl Into<Timestamp>) -> Self {
+    self.scrobble.timestamp = timestamp.into().as_secs();
+    self
+  }
+
+  pub fn album(mut self, album: impl Into<String>) -> Self {
+    self.scrobble.album = Some(album.into());
+    self
+  }
+
+  pub fn track_number(mut self, track_number: u32) -> Self {
+    self.scrobble.track_number = Some(track_number);
+    self
+  }
+
+  /// Set the track length (submitted to the API in whole seconds)
+  pub fn duration(mut self, duration: Duration) -> Self {
+    self.scrobble.duration = Some(duration);
+    self
+  }
+
+  pub fn album_artist(mut self, album_artist: impl Into<String>) -> Self {
+    self.scrobble.album_artist = Some(album_artist.into());
+    self
+  }
+
+  /// Set the MusicBrainz recording ID
+  pub fn mbid(mut self, mbid: Mbid) -> Self {
+    self.scrobble.mbid = Some(mbid);
+    self
+  }
+
+  pub fn player(mut self, player: impl Into<String>) -> Self {
+    self.scrobble.player = Some(player.into());
+    self
+  }
+
+  /// Finish the scrobble, failing like [`Scrobble::validate`] if the
+  /// timestamp was never set or a required field is blank
+  pub fn build(self) -> Result<Scrobble> {
+    self.scrobble.validate()?;
+    Ok(self.scrobble)
+  }
+}
+
+#[cfg(feature = "chrono")]
+impl Scrobble {
+  /// Create a scrobble from a datetime instead of a unix timestamp
+  pub fn at<Tz: chrono::TimeZone>(
+    artist: impl Into<String>,
+    track: impl Into<String>,
+    started: chrono::DateTime<Tz>,
+  ) -> Self {
+    Self::new(artist, track, 0).with_datetime(started)
+  }
+
+  /// Set the timestamp from a datetime (clamped to the unix epoch)
+  pub fn with_datetime<Tz: chrono::TimeZone>(mut self, started: chrono::DateTime<Tz>) -> Self {
+    self.timestamp = started.timestamp().max(0) as u64;
+    self
+  }
+
+  /// Timestamp as a UTC datetime
+  pub fn datetime(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+    i64::try_from(self.timestamp)
+      .ok()
+      .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+  }
+}
+
+fn require(field: &str, value: &str) -> Result<()> {
+  if value.trim().is_empty() {
+    return Err(Error::InvalidParameter(format!(
+      "{} must not be empty",
+      field
+    )));
+  }
+  Ok(())
+}
+
+/// Unsigned `track.scrobble` parameters for a batch of up to 50 scrobbles
+#[cfg(feature = "scrobble")]
+pub(crate) fn batch_params<'a, S: Borrow<Scrobble>>(
+  scrobbles: &'a [S],
+  api_key: &'a str,
+  sk: &'a str,
+) -> Params<'a> {
+  // Up to nine fields per scrobble, plus method, api_key, sk and api_sig
+  let mut params = Params::with_capacity("track.scrobble", scrobbles.len() * 9 + 4);
+  params.push("api_key", api_key).push("sk", sk);
+
+  for (i, scrobble) in scrobbles.iter().enumerate() {
+    let scrobble = scrobble.borrow();
+    params
+      .push_indexed("artist", i, &scrobble.artist)
+      .push_indexed("track", i, &scrobble.track)
+      .push_indexed("timestamp", i, scrobble.timestamp)
+      .push_indexed_opt("album", i, scrobble.album.as_ref())
+      .push_indexed_opt("trackNumber", i, scrobble.track_number)
+      .push_indexed_opt("duration", i, scrobble.duration.map(|d| d.as_secs()))
+      .push_indexed_opt("albumArtist", i, scrobble.album_artist.as_ref())
+      .push_indexed_opt("mbid", i, scrobble.mbid.as_ref().map(Mbid::as_str))
+      .push_indexed_opt("context", i, scrobble.player.as_ref());
+  }
+  params
+}
+
+/// Signed form body for a `track.scrobble` batch, as the client sends it
+///
+/// Exported only with the `bench` feature, for the benchmarks.
+#[cfg(all(feature = "scrobble", any(test, feature = "bench")))]
+pub fn encode_batch(scrobbles: &[Scrobble], api_key: &str, sk: &str, secret: &str) -> String {
+  let mut params = batch_params(scrobbles, api_key, sk);
+  params.sign(secret, &Md5Signer);
+  params.encode()
+}
+
+/// Write `Artist – Track (Album)`, leaving out a missing or empty album
+pub(crate) fn write_track(
+  f: &mut fmt::Formatter<'_>,
+  artist: &str,
+  track: &str,
+  album: Option<&str>,
+) -> fmt::Result {
+  write!(f, "{} – {}", artist, track)?;
+  match album.filter(|album| !album.is_empty()) {
+    Some(album) => write!(f, " ({})", album),
+    None => Ok(()),
+  }
+}
+
+/// `Artist – Track (Album)`
+impl fmt::Display for NowPlaying {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write_track(f, &self.artist, &self.track, self.album.as_deref())
+  }
+}
+
+/// `Artist – Track (Album)`
+impl fmt::Display for Scrobble {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write_track(f, &self.artist, &self.track, self.album.as_deref())
+  }
+}
+
 /// Scrobble response
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[non_exhaustive]
+#[cfg_attr(feature = "strict-schema", serde(deny_unknown_fields))]
 pub struct ScrobbleResponse {
   pub scrobbles: ScrobbleData,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[non_exhaustive]
+#[cfg_attr(feature = "strict-schema", serde(deny_unknown_fields))]
 pub struct ScrobbleData {
+  /// Per-track results, in submission order (empty in token mode)
+  #[serde(rename = "scrobble", deserialize_with = "de::one_or_many", default)]
+  pub results: Vec<ScrobbleResult>,
   #[serde(rename = "@attr")]
   pub attr: ScrobbleAttr,
 }
 
-#[derive(Debug, Deserialize)]
+/// How Last.fm recorded one submitted scrobble
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[non_exhaustive]
+pub struct ScrobbleResult {
+  #[serde(default)]
+  pub artist: Corrected,
+  #[serde(default)]
+  pub track: Corrected,
+  #[serde(default)]
+  pub album: Corrected,
+  #[serde(rename = "albumArtist", default)]
+  pub album_artist: Corrected,
+  #[serde(deserialize_with = "de::number", default)]
+  pub timestamp: u64,
+  #[serde(rename = "ignoredMessage", default)]
+  pub ignored_message: IgnoredMessage,
+  /// Fields this version doesn't model yet
+  #[serde(flatten)]
+  #[cfg_attr(feature = "strict-schema", serde(deserialize_with = "de::no_extra"))]
+  pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Submitted value, possibly corrected by Last.fm (e.g. a misspelt artist)
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[non_exhaustive]
+pub struct Corrected {
+  #[serde(rename = "#text", deserialize_with = "de::string", default)]
+  pub text: String,
+  #[serde(deserialize_with = "de::flag", default)]
+  pub corrected: bool,
+  /// Fields this version doesn't model yet
+  #[serde(flatten)]
+  #[cfg_attr(feature = "strict-schema", serde(deserialize_with = "de::no_extra"))]
+  pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Corrected {
+  /// The corrected value, if Last.fm changed what was submitted
+  pub fn correction(&self) -> Option<&str> {
+    self.corrected.then_some(self.text.as_str())
+  }
+}
+
+/// Why Last.fm ignored a scrobble; code `0` means it wasn't ignored
+///
+/// Known codes: 1 artist ignored, 2 track ignored, 3 timestamp too old,
+/// 4 timestamp too new, 5 daily scrobble limit exceeded.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[non_exhaustive]
+pub struct IgnoredMessage {
+  #[serde(deserialize_with = "de::number", default)]
+  pub code: u32,
+  #[serde(rename = "#text", deserialize_with = "de::string", default)]
+  pub text: String,
+  /// Fields this version doesn't model yet
+  #[serde(flatten)]
+  #[cfg_attr(feature = "strict-schema", serde(deserialize_with = "de::no_extra"))]
+  pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Result of [`Client::scrobble_one`](crate::Client::scrobble_one)
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ScrobbleOutcome {
+  pub accepted: bool,
+  /// Why the scrobble was ignored, `None` when accepted
+  pub ignored: Option<IgnoredMessage>,
+  /// Values Last.fm corrected; all `None` when it kept the submission as is
+  pub corrections: Corrections,
+}
+
+/// Corrections Last.fm applied to a scrobble
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Corrections {
+  pub artist: Option<String>,
+  pub track: Option<String>,
+  pub album: Option<String>,
+  pub album_artist: Option<String>,
+}
+
+impl Corrections {
+  pub fn is_empty(&self) -> bool {
+    self == &Self::default()
+  }
+}
+
+impl From<&ScrobbleResult> for ScrobbleOutcome {
+  fn from(result: &ScrobbleResult) -> Self {
+    let correction = |value: &Corrected| value.correction().map(str::to_string);
+    let ignored = result.ignored_message.code != 0;
+    Self {
+      accepted: !ignored,
+      ignored: ignored.then(|| result.ignored_message.clone()),
+      corrections: Corrections {
+        artist: correction(&result.artist),
+        track: correction(&result.track),
+        album: correction(&result.album),
+        album_artist: correction(&result.album_artist),
+      },
+    }
+  }
+}
+
+impl ScrobbleResponse {
+  /// Outcome of each submitted scrobble, in submission order
+  ///
+  /// Token-mode servers don't report per-track results, so every scrobble
+  /// counts as accepted.
+  pub fn outcomes(&self) -> Vec<ScrobbleOutcome> {
+    let data = &self.scrobbles;
+    if data.results.is_empty() {
+      let accepted = ScrobbleOutcome {
+        accepted: true,
+        ..ScrobbleOutcome::default()
+      };
+      return vec![accepted; data.attr.accepted as usize];
+    }
+    data.results.iter().map(ScrobbleOutcome::from).collect()
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[non_exhaustive]
+#[cfg_attr(feature = "strict-schema", serde(deny_unknown_fields))]
 pub struct ScrobbleAttr {
+  #[serde(deserialize_with = "de::number", default)]
   pub accepted: u32,
+  #[serde(deserialize_with = "de::number", default)]
   pub ignored: u32,
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_scrobble_response_round_trip() {
+    let json = r#"{"scrobbles":{"@attr":{"accepted":1,"ignored":0}}}"#;
+    let response: ScrobbleResponse = serde_json::from_str(json).unwrap();
+    let reparsed: ScrobbleResponse =
+      serde_json::from_str(&serde_json::to_string(&response).unwrap()).unwrap();
+    assert_eq!(response, reparsed);
+  }
+
+  #[test]
+  fn test_scrobble_outcomes() {
+    let json = r##"{"scrobbles":{"scrobble":{
+      "artist":{"corrected":"1","#text":"Cher"},
+      "album":{"corrected":"0"},
+      "track":{"corrected":"0","#text":"Believe"},
+      "ignoredMessage":{"code":"0","#text":""},
+      "albumArtist":{"corrected":"0","#text":""},
+      "timestamp":"1700000000"
+    },"@attr":{"accepted":1,"ignored":0}}}"##;
+    let response: ScrobbleResponse = serde_json::from_str(json).unwrap();
+    let outcomes = response.outcomes();
+    assert_eq!(outcomes.len(), 1);
+    assert!(outcomes[0].accepted);
+    assert_eq!(outcomes[0].corrections.artist.as_deref(), Some("Cher"));
+    assert_eq!(outcomes[0].corrections.track, None);
+
+    let json = r##"{"scrobbles":{"scrobble":[
+      {"ignoredMessage":{"code":"0","#text":""}},
+      {"ignoredMessage":{"code":"3","#text":"Timestamp too old"}}
+    ],"@attr":{"accepted":1,"ignored":1}}}"##;
+    let response: ScrobbleResponse = serde_json::from_str(json).unwrap();
+    let outcomes = response.outcomes();
+    assert!(outcomes[0].corrections.is_empty());
+    assert!(!outcomes[1].accepted);
+    assert_eq!(
+      outcomes[1].ignored.as_ref().map(|ignored| ignored.code),
+      Some(3)
+    );
+  }
+
+  #[test]
+  fn test_try_new_rejects_missing_fields() {
+    assert!(Scrobble::try_new("Artist", "Track", 1_700_000_000).is_ok());
+    assert!(matches!(
+      Scrobble::try_new(" ", "Track", 1_700_000_000),
+      Err(Error::InvalidParameter(message)) if message == "artist must not be empty"
+    ));
+    assert!(Scrobble::try_new("Artist", "", 1_700_000_000).is_err());
+    assert!(Scrobble::try_new("Artist", "Track", 0).is_err());
+
+    assert!(NowPlaying::try_new("Artist", "Track").is_ok());
+    assert!(NowPlaying::try_new("Artist", "\t").is_err());
+  }
+
+  #[test]
+  fn test_builder_timestamps() {
+    let at = |timestamp: Timestamp| {
+      Scrobble::builder("Artist", "Track")
+        .timestamp(timestamp)
+        .build()
+        .map(|scrobble| scrobble.timestamp)
+    };
+    let since_epoch = Duration::from_secs(1_700_000_000);
+
+    assert_eq!(at(since_epoch.into()).unwrap(), 1_700_000_000);
+    assert_eq!(
+      at((UNIX_EPOCH + since_epoch).into()).unwrap(),
+      1_700_000_000
+    );
+    assert!(at((UNIX_EPOCH - Duration::from_secs(1)).into()).is_err());
+    assert!(Scrobble::builder("Artist", "Track").build().is_err());
+
+    let mbid: Mbid = "b1a9c0e9-d987-4042-ae91-78d6a3267d69".parse().unwrap();
+    let scrobble = Scrobble::builder("Artist", "Track")
+      .timestamp(1_700_000_000)
+      .album("Album")
+      .mbid(mbid.clone())
+      .build()
+      .unwrap();
+    assert_eq!(
+      scrobble,
+      Scrobble::new("Artist", "Track", 1_700_000_000)
+        .with_album("Album")
+        .with_mbid(mbid)
+    );
+  }
+
+  #[cfg(feature = "chrono")]
+  #[test]
+  fn test_chrono_conversions() {
+    use chrono::{TimeZone, Utc};
+
+    let started = Utc.with_ymd_and_hms(2024, 3, 1, 12, 30, 0).unwrap();
+    let scrobble = Scrobble::at("Artist", "Track", started);
+    assert_eq!(scrobble.timestamp, 1_709_296_200);
+    assert_eq!(scrobble.datetime(), Some(started));
+  }
+
+  #[test]
+  fn test_timestamp_validation() {
+    let now = Timestamp::now();
+    assert_eq!(now.validate().unwrap(), now);
+    assert!(Timestamp::new(0).validate().is_err());
+    assert!(Timestamp::new(now.as_secs() + 2 * MAX_CLOCK_SKEW)
+      .validate()
+      .is_err());
+
+    let scrobble = Scrobble::now("Artist", "Track");
+    assert!(scrobble.timestamp >= now.as_secs());
+    assert!(scrobble.validate().is_ok());
+
+    let started = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    assert_eq!(
+      Scrobble::started_at("Artist", "Track", started).timestamp,
+      1_700_000_000
+    );
+  }
+
+  #[test]
+  fn test_scrobble_round_trip() {
+    let scrobble = Scrobble::new("Artist", "Track", 1_700_000_000)
+      .with_album("Album")
+      .with_duration(Duration::from_secs(200));
+    let reparsed: Scrobble =
+      serde_json::from_str(&serde_json::to_string(&scrobble).unwrap()).unwrap();
+    assert_eq!(scrobble, reparsed);
+  }
+
+  #[test]
+  fn test_unset_fields_are_left_out() {
+    let json = serde_json::to_value(Scrobble::new("Cher", "Believe", 1_700_000_000)).unwrap();
+    assert_eq!(
+      json,
+      serde_json::json!({"artist": "Cher", "track": "Believe", "timestamp": 1_700_000_000})
+    );
+    let json = serde_json::to_value(NowPlaying::new("Cher", "Believe").with_album("Believe"));
+    assert_eq!(
+      json.unwrap(),
+      serde_json::json!({"artist": "Cher", "track": "Believe", "album": "Believe"})
+    );
+  }
+
+  #[test]
+  fn test_display() {
+    let scrobble = Scrobble::new("Cher", "Believe", 1_700_000_000);
+    assert_eq!(scrobble.to_string(), "Cher – Believe");
+    assert_eq!(
+      scrobble.with_album("Believe").to_string(),
+      "Cher – Believe (Believe)"
+    );
+    assert_eq!(
+      NowPlaying::new("Cher", "Believe")
+        .with_album("")
+        .to_string(),
+      "Cher – Believe"
+    );
+  }
+
+  #[test]
+  fn test_now_playing_into_scrobble() {
+    let now_playing = NowPlaying::new("Cher", "Believe")
+      .with_album("Believe")
+      .with_track_number(1)
+      .with_player("player");
+    let scrobble = now_playing.clone().into_scrobble(1_700_000_000);
+    let expected = Scrobble::new("Cher", "Believe", 1_700_000_000)
+      .with_album("Believe")
+      .with_track_number(1)
+      .with_player("player");
+    assert_eq!(scrobble, expected);
+  }
+
+  #[cfg(feature = "schemars")]
+  #[test]
+  fn test_json_schema_matches_serialized_form() {
+    let schema = serde_json::to_value(schemars::schema_for!(Scrobble)).unwrap();
+    let properties = &schema["properties"];
+    assert_eq!(properties["timestamp"]["type"], "integer");
+    assert_eq!(
+      properties["duration"]["type"],
+      serde_json::json!(["integer", "null"])
+    );
+    assert_eq!(schema["$defs"]["Mbid"]["format"], "uuid");
+
+    let schema = serde_json::to_value(schemars::schema_for!(ScrobbleResponse)).unwrap();
+    let data = &schema["$defs"]["ScrobbleData"]["properties"];
+    assert!(data.get("scrobble").is_some() && data.get("@attr").is_some());
+  }
+
+  #[cfg(feature = "scrobble")]
+  #[test]
+  fn test_encode_batch_sends_every_field() {
+    let scrobbles: Vec<Scrobble> = (0..11u64)
+      .map(|i| {
+        Scrobble::new("Sigur Rós", format!("Track {}", i), 1_700_000_000 + i)
+          .with_album("Ágætis byrjun")
+          .with_track_number(i as u32 + 1)
+          .with_duration(Duration::from_secs(240))
+          .with_album_artist("Sigur Rós")
+          .with_player("my-player")
+      })
+      .collect();
+
+    let body = encode_batch(&scrobbles, "key", "session", "secret");
+    assert!(body.starts_with("albumArtist%5B0%5D=Sigur+R%C3%B3s&albumArtist%5B10%5D="));
+    assert!(body.contains("&context%5B10%5D=my-player&"));
+    assert!(body.contains("&trackNumber%5B10%5D=11&"));
+    assert!(body.contains("&method=track.scrobble&sk=session&"));
+    assert!(body.ends_with("&format=json"));
+    assert_eq!(body.capacity(), body.len());
+  }
+
+  #[cfg(feature = "scrobble")]
+  #[test]
+  fn test_encode_batch_allocations_independent_of_size() {
+    use crate::test_alloc::allocations;
+
+    let batch = |len: u64| -> Vec<Scrobble> {
+      (0..len)
+        .map(|i| {
+          Scrobble::new("Sigur Rós", format!("Track {}", i), 1_700_000_000 + i)
+            .with_album("Ágætis byrjun")
+            .with_track_number(i as u32 + 1)
+            .with_duration(Duration::from_secs(240))
+            .with_album_artist("Sigur Rós")
+            .with_player("my-player")
+        })
+        .collect()
+    };
+    let single = batch(1);
+    let full = batch(50);
+
+    let (single_allocs, body) = allocations(|| encode_batch(&single, "key", "session", "secret"));
+    assert!(body.contains("&context%5B0%5D=my-player&"));
+    let (full_allocs, body) = allocations(|| encode_batch(&full, "key", "session", "secret"));
+    assert!(body.contains("&trackNumber%5B49%5D=50&"));
+
+    // Entry list, signing message, signature and body: nothing per scrobble
+    assert_eq!(single_allocs, full_allocs);
+    assert!(full_allocs <= 6, "{} allocations", full_allocs);
+  }
+}
@@ -0,0 +1,218 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::de;
+use crate::mbid::Mbid;
+use crate::scrobble::write_track;
+use crate::track::Image;
+
+/// One page of search results with OpenSearch pagination metadata
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SearchPage<T> {
+  /// Total number of matches across all pages
+  pub total_results: u64,
+  /// Zero-based index of the first item on this page
+  pub start_index: u64,
+  /// Page size requested from the API
+  pub items_per_page: u64,
+  pub items: Vec<T>,
+  /// The `@attr` block of list responses, `None` for searches
+  #[serde(default)]
+  pub attr: Option<PageAttr>,
+}
+
+impl<T> SearchPage<T> {
+  /// One-based number of this page
+  pub fn page(&self) -> u64 {
+    self
+      .start_index
+      .checked_div(self.items_per_page)
+      .map_or(1, |index| index + 1)
+  }
+
+  /// Number of pages needed to cover all results
+  pub fn total_pages(&self) -> u64 {
+    if self.items_per_page == 0 {
+      return 0;
+    }
+    self.total_results.div_ceil(self.items_per_page)
+  }
+
+  /// Whether another page can be fetched after this one
+  pub fn has_next_page(&self) -> bool {
+    self.start_index + (self.items.len() as u64) < self.total_results && !self.items.is_empty()
+  }
+}
+
+/// `@attr` pagination block of list responses (user.getRecentTracks,
+/// user.getLovedTracks, tag.getTopTracks, ...)
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PageAttr {
+  /// One-based number of this page
+  #[serde(deserialize_with = "de::number", default)]
+  pub page: u64,
+  #[serde(rename = "perPage", deserialize_with = "de::number", default)]
+  pub per_page: u64,
+  /// Number of items across all pages
+  #[serde(deserialize_with = "de::number", default)]
+  pub total: u64,
+  /// Number of pages, as Last.fm counts them
+  #[serde(rename = "totalPages", deserialize_with = "de::number", default)]
+  pub total_pages: u64,
+  /// Whose list this is; each of these is empty unless the method
+  /// reports it
+  #[serde(deserialize_with = "de::string", default)]
+  pub user: String,
+  #[serde(deserialize_with = "de::string", default)]
+  pub tag: String,
+  #[serde(deserialize_with = "de::string", default)]
+  pub artist: String,
+}
+
+impl PageAttr {
+  /// Wrap one page of items with this pagination metadata
+  pub(crate) fn into_page<T>(self, items: Vec<T>) -> SearchPage<T> {
+    SearchPage {
+      total_results: self.total,
+      start_index: self.page.saturating_sub(1) * self.per_page,
+      items_per_page: self.per_page,
+      items,
+      attr: Some(self),
+    }
+  }
+}
+
+/// Track entry returned by track.search
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct TrackMatch {
+  #[serde(deserialize_with = "de::string", default)]
+  pub name: String,
+  #[serde(deserialize_with = "de::string", default)]
+  pub artist: String,
+  /// Last.fm page, `None` when missing or malformed
+  #[serde(deserialize_with = "de::url", default)]
+  pub url: Option<Url>,
+  #[serde(deserialize_with = "de::number", default)]
+  pub listeners: u64,
+  #[serde(deserialize_with = "de::mbid", default)]
+  pub mbid: Option<Mbid>,
+  /// Available sizes; entries Last.fm sends without a URL are dropped
+  #[serde(deserialize_with = "de::images", default)]
+  pub image: Vec<Image>,
+  /// Fields this version doesn't model yet
+  #[serde(flatten)]
+  #[cfg_attr(feature = "strict-schema", serde(deserialize_with = "de::no_extra"))]
+  pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// `Artist – Track`
+impl fmt::Display for TrackMatch {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write_track(f, &self.artist, &self.name, None)
+  }
+}
+
+/// Response wrapper for track.search
+#[derive(Debug, Deserialize)]
+pub(crate) struct TrackSearchResponse {
+  results: TrackSearchResults,
+}
+
+#[derive(Debug, Deserialize)]
+struct TrackSearchResults {
+  #[serde(
+    rename = "opensearch:totalResults",
+    deserialize_with = "de::number",
+    default
+  )]
+  total_results: u64,
+  #[serde(
+    rename = "opensearch:startIndex",
+    deserialize_with = "de::number",
+    default
+  )]
+  start_index: u64,
+  #[serde(
+    rename = "opensearch:itemsPerPage",
+    deserialize_with = "de::number",
+    default
+  )]
+  items_per_page: u64,
+  #[serde(rename = "trackmatches", default)]
+  matches: TrackMatches,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TrackMatches {
+  #[serde(deserialize_with = "de::one_or_many", default)]
+  track: Vec<TrackMatch>,
+}
+
+impl From<TrackSearchResponse> for SearchPage<TrackMatch> {
+  fn from(resp: TrackSearchResponse) -> Self {
+    SearchPage {
+      total_results: resp.results.total_results,
+      start_index: resp.results.start_index,
+      items_per_page: resp.results.items_per_page,
+      items: resp.results.matches.track,
+      attr: None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_track_search_pagination() {
+    let json = r##"{"results":{
+      "opensearch:Query":{"#text":"","role":"request","startPage":"3"},
+      "opensearch:totalResults":"3600",
+      "opensearch:startIndex":"60",
+      "opensearch:itemsPerPage":"30",
+      "trackmatches":{"track":[
+        {"name":"Believe","artist":"Cher","url":"https://www.last.fm/music/Cher/_/Believe","listeners":"1000","mbid":""}
+      ]},
+      "@attr":{"for":"believe"}
+    }}"##;
+
+    let resp: TrackSearchResponse = serde_json::from_str(json).unwrap();
+    let page = SearchPage::from(resp);
+    assert_eq!(page.page(), 3);
+    assert_eq!(page.total_pages(), 120);
+    assert!(page.has_next_page());
+    assert_eq!(page.items[0].artist, "Cher");
+    assert_eq!(page.items[0].listeners, 1000);
+  }
+
+  #[test]
+  fn test_last_page() {
+    let page = SearchPage {
+      total_results: 31,
+      start_index: 30,
+      items_per_page: 30,
+      items: vec![()],
+      attr: None,
+    };
+    assert_eq!(page.page(), 2);
+    assert_eq!(page.total_pages(), 2);
+    assert!(!page.has_next_page());
+  }
+
+  #[test]
+  fn test_page_attr() {
+    let json = r#"{"user":"rj","tag":"","page":"2","perPage":"50","totalPages":"3","total":"120"}"#;
+    let attr: PageAttr = serde_json::from_str(json).unwrap();
+    assert_eq!((attr.total_pages, attr.user.as_str()), (3, "rj"));
+    assert!(attr.tag.is_empty() && attr.artist.is_empty());
+
+    let page = attr.clone().into_page(vec![(); 50]);
+    assert_eq!((page.page(), page.total_pages()), (2, 3));
+    assert_eq!(page.attr, Some(attr));
+  }
+}
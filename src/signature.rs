@@ -1,51 +1,138 @@
-use std::collections::BTreeMap;
+#[cfg(any(feature = "scrobble", feature = "auth-flow"))]
+use crate::params::Params;
 
-/// Generate Last.fm API signature
+/// Request signing scheme
 ///
-/// Steps:
-/// 1. Sort parameters alphabetically (excluding 'format')
-/// 2. Concatenate as name+value pairs
-/// 3. Append secret
-/// 4. MD5 hash the result
-pub fn generate(params: &BTreeMap<String, String>, secret: &str) -> String {
-  let mut sig_string = String::new();
-
-  for (key, value) in params.iter() {
-    if key != "format" {
-      sig_string.push_str(key);
-      sig_string.push_str(value);
-    }
+/// The client builds the canonical message (parameters sorted by name,
+/// concatenated as name+value pairs, excluding `format` and `callback`) and
+/// the signer turns it into the `api_sig` value. Implement this for servers
+/// that speak the Last.fm protocol with a different hash or an HMAC.
+pub trait Signer: Send + Sync {
+  /// Sign the canonical message with the shared API secret
+  fn sign(&self, message: &str, secret: &str) -> String;
+}
+
+/// Last.fm's signing scheme: hex MD5 of the message with the secret appended
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Md5Signer;
+
+impl Signer for Md5Signer {
+  fn sign(&self, message: &str, secret: &str) -> String {
+    let mut context = md5::Context::new();
+    context.consume(message.as_bytes());
+    context.consume(secret.as_bytes());
+    format!("{:x}", context.compute())
   }
+}
 
-  sig_string.push_str(secret);
+/// Parameters never included in the signature
+#[cfg(any(feature = "scrobble", feature = "auth-flow"))]
+const UNSIGNED: [&str; 2] = ["format", "callback"];
 
-  format!("{:x}", md5::compute(sig_string.as_bytes()))
+/// Whether a parameter is left out of the signature
+#[cfg(any(feature = "scrobble", feature = "auth-flow"))]
+pub(crate) fn is_unsigned(name: &str) -> bool {
+  UNSIGNED.contains(&name)
 }
 
-#[cfg(test)]
+/// Generate Last.fm API signature
+///
+/// Steps:
+/// 1. Sort parameters by name, byte-wise (excluding 'format' and 'callback')
+/// 2. Concatenate as name+value pairs, values unencoded
+/// 3. Sign the UTF-8 bytes of the result with the secret
+#[cfg(any(
+  all(test, any(feature = "scrobble", feature = "auth-flow")),
+  feature = "bench"
+))]
+pub(crate) fn generate(params: &mut Params<'_>, secret: &str, signer: &dyn Signer) -> String {
+  signer.sign(&params.signing_message(), secret)
+}
+
+/// Likely causes of Last.fm rejecting the signature on `params`
+#[cfg(any(feature = "scrobble", feature = "auth-flow"))]
+pub(crate) fn hints(params: &Params<'_>) -> Vec<&'static str> {
+  let mut hints = vec!["check that the API secret belongs to the API key"];
+  if params.signed_names().iter().any(|name| name == "sk") {
+    hints.push("session keys only work with the API key they were issued for");
+  }
+  if params.has_non_ascii() {
+    hints.push(
+      "values are signed as UTF-8, so anything re-encoding them in transit breaks the signature",
+    );
+  }
+  hints.push("custom signers must sign the parameters sorted by name, without format or callback");
+  hints
+}
+
+#[cfg(all(test, any(feature = "scrobble", feature = "auth-flow")))]
 mod tests {
   use super::*;
 
   #[test]
   fn test_signature_generation() {
-    let mut params = BTreeMap::new();
-    params.insert("method".to_string(), "auth.getSession".to_string());
-    params.insert("api_key".to_string(), "testkey".to_string());
-    params.insert("token".to_string(), "testtoken".to_string());
+    let mut params = Params::new("auth.getSession");
+    params.push("api_key", "testkey");
+    params.push("token", "testtoken");
+
+    let sig = generate(&mut params, "testsecret", &Md5Signer);
+
+    // MD5("api_keytestkeymethodauth.getSessiontokentesttokentestsecret")
+    assert_eq!(sig, "7e846232cc93d093646ff399db5c3d75");
+  }
 
-    let sig = generate(&params, "testsecret");
+  #[test]
+  fn test_signature_multibyte_utf8() {
+    let mut params = Params::new("track.updateNowPlaying");
+    params.push("api_key", "testkey");
+    params.push("sk", "testsk");
+    params.push("artist", "坂本龍一");
+    params.push("track", "戦場のメリークリスマス");
+    params.push("album", "Merry Christmas Mr. Lawrence");
+    params.push("format", "json");
+
+    // MD5 over the raw UTF-8 bytes of:
+    // "albumMerry Christmas Mr. Lawrenceapi_keytestkeyartist坂本龍一method..."
+    assert_eq!(
+      generate(&mut params, "testsecret", &Md5Signer),
+      "e9d59e7644df40fce6c110d2d7cebc32"
+    );
+  }
+
+  #[cfg(feature = "scrobble")]
+  #[test]
+  fn test_signature_indexed_batch_params() {
+    let mut params = Params::new("track.scrobble");
+    params.push("api_key", "k");
+    params.push("sk", "s");
+    params.push_indexed("artist", 0, "Björk");
+    params.push_indexed("track", 0, "Jóga");
+    params.push_indexed("timestamp", 0, "1700000000");
+    params.push_indexed("artist", 1, "Sigur Rós");
+    params.push_indexed("track", 1, "Hoppípolla");
+    params.push_indexed("timestamp", 1, "1700000300");
 
-    // Expected: MD5("api_keytestkeymethodauth.getSessiontokentesttokentestsecret")
-    assert_eq!(sig.len(), 32);
+    assert_eq!(
+      generate(&mut params, "sec", &Md5Signer),
+      "6e8c5806954a659a0092f53d6981510e"
+    );
+  }
+
+  #[test]
+  fn test_signature_excludes_callback() {
+    let mut params = Params::new("test");
+    params.push("callback", "cb");
+
+    let expected = format!("{:x}", md5::compute("methodtestsecret".as_bytes()));
+    assert_eq!(generate(&mut params, "secret", &Md5Signer), expected);
   }
 
   #[test]
   fn test_signature_excludes_format() {
-    let mut params = BTreeMap::new();
-    params.insert("method".to_string(), "test".to_string());
-    params.insert("format".to_string(), "json".to_string());
+    let mut params = Params::new("test");
+    params.push("format", "json");
 
-    let sig = generate(&params, "secret");
+    let sig = generate(&mut params, "secret", &Md5Signer);
 
     // format should be excluded from signature
     let expected_input = "methodtestsecret";
@@ -53,4 +140,34 @@ mod tests {
 
     assert_eq!(sig, expected);
   }
+
+  #[test]
+  fn test_signature_sorts_unordered_input() {
+    let mut params = Params::new("auth.getSession");
+    params.push("token", "testtoken").push("api_key", "testkey");
+    assert_eq!(
+      generate(&mut params, "testsecret", &Md5Signer),
+      "7e846232cc93d093646ff399db5c3d75"
+    );
+  }
+
+  #[test]
+  fn test_custom_signer_receives_canonical_message() {
+    struct Echo;
+
+    impl Signer for Echo {
+      fn sign(&self, message: &str, secret: &str) -> String {
+        format!("{}|{}", message, secret)
+      }
+    }
+
+    let mut params = Params::new("test");
+    params.push("api_key", "key");
+    params.push("format", "json");
+
+    assert_eq!(
+      generate(&mut params, "secret", &Echo),
+      "api_keykeymethodtest|secret"
+    );
+  }
 }
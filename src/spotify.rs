@@ -0,0 +1,194 @@
+//! Spotify extended streaming history import
+//!
+//! Spotify's privacy export contains `endsong_*.json` (newer exports:
+//! `Streaming_History_Audio_*.json`) files, each a JSON array of plays.
+//! [`scrobbles_from_reader`] turns one of those files into [`Scrobble`]s,
+//! keeping only plays that Last.fm would have counted.
+
+use std::io::Read;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::de;
+use crate::error::Result;
+use crate::scrobble::Scrobble;
+
+/// Tracks shorter than this are never scrobbled
+pub const MIN_TRACK_LENGTH: Duration = Duration::from_secs(30);
+
+/// Listening this long always counts, whatever the track length
+pub const ALWAYS_SCROBBLE_AFTER: Duration = Duration::from_secs(240);
+
+/// Player name attached to imported scrobbles
+const PLAYER: &str = "Spotify";
+
+/// One entry of Spotify's extended streaming history
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SpotifyPlay {
+  /// When playback stopped, e.g. `2019-05-14T07:28:49Z`
+  #[serde(deserialize_with = "de::string", default)]
+  pub ts: String,
+  #[serde(deserialize_with = "de::number", default)]
+  pub ms_played: u64,
+  #[serde(deserialize_with = "de::option", default)]
+  pub master_metadata_track_name: Option<String>,
+  /// Despite the name, this is the track artist
+  #[serde(deserialize_with = "de::option", default)]
+  pub master_metadata_album_artist_name: Option<String>,
+  #[serde(deserialize_with = "de::option", default)]
+  pub master_metadata_album_album_name: Option<String>,
+  #[serde(deserialize_with = "de::option", default)]
+  pub spotify_track_uri: Option<String>,
+  #[serde(deserialize_with = "de::option", default)]
+  pub reason_end: Option<String>,
+  #[serde(deserialize_with = "de::option", default)]
+  pub incognito_mode: Option<bool>,
+}
+
+impl SpotifyPlay {
+  /// Time spent listening
+  pub fn played(&self) -> Duration {
+    Duration::from_millis(self.ms_played)
+  }
+
+  /// Whether Last.fm would have counted this play
+  ///
+  /// The export doesn't include track lengths, so the "half the track" rule
+  /// can only be applied when the track played to the end (`reason_end` of
+  /// `trackdone`). Otherwise the play must reach [`ALWAYS_SCROBBLE_AFTER`].
+  /// Podcast episodes and incognito sessions are never eligible.
+  pub fn is_eligible(&self) -> bool {
+    if self.incognito_mode == Some(true)
+      || self.master_metadata_track_name.is_none()
+      || self.master_metadata_album_artist_name.is_none()
+    {
+      return false;
+    }
+
+    let played = self.played();
+    played >= ALWAYS_SCROBBLE_AFTER
+      || (self.reason_end.as_deref() == Some("trackdone") && played > MIN_TRACK_LENGTH)
+  }
+
+  /// Start time as a unix timestamp, derived from the end time and play length
+  pub fn started_at(&self) -> Option<u64> {
+    parse_utc(&self.ts).map(|ended| ended.saturating_sub(self.ms_played / 1000))
+  }
+
+  /// Convert to a scrobble, or `None` if the play isn't eligible
+  pub fn to_scrobble(&self) -> Option<Scrobble> {
+    if !self.is_eligible() {
+      return None;
+    }
+
+    let artist = self.master_metadata_album_artist_name.as_ref()?;
+    let track = self.master_metadata_track_name.as_ref()?;
+    let mut scrobble = Scrobble::new(artist, track, self.started_at()?).with_player(PLAYER);
+    if self.reason_end.as_deref() == Some("trackdone") {
+      scrobble = scrobble.with_duration(self.played());
+    }
+    if let Some(album) = &self.master_metadata_album_album_name {
+      scrobble = scrobble.with_album(album);
+    }
+    Some(scrobble)
+  }
+}
+
+/// Read one history file and keep the eligible plays, oldest first
+///
+/// # Example
+///
+/// ```no_run
+/// # fn example() -> last_fm_rs::Result<()> {
+/// use std::fs::File;
+/// use last_fm_rs::spotify;
+///
+/// let file = File::open("endsong_0.json")?;
+/// let scrobbles = spotify::scrobbles_from_reader(file)?;
+/// println!("{} plays to backfill", scrobbles.len());
+/// # Ok(())
+/// # }
+/// ```
+pub fn scrobbles_from_reader<R: Read>(reader: R) -> Result<Vec<Scrobble>> {
+  let plays: Vec<SpotifyPlay> = serde_json::from_reader(reader)?;
+  let mut scrobbles: Vec<Scrobble> = plays.iter().filter_map(SpotifyPlay::to_scrobble).collect();
+  scrobbles.sort_by_key(|scrobble| scrobble.timestamp);
+  Ok(scrobbles)
+}
+
+/// Parse `YYYY-MM-DDTHH:MM:SSZ` into a unix timestamp
+fn parse_utc(ts: &str) -> Option<u64> {
+  let ts = ts.strip_suffix('Z')?;
+  let (date, time) = ts.split_once('T')?;
+
+  let mut date = date.splitn(3, '-').map(str::parse::<u64>);
+  let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+  let mut time = time.splitn(3, ':').map(str::parse::<u64>);
+  let (hour, minute, second) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+
+  if year < 1970 || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+    return None;
+  }
+  if hour > 23 || minute > 59 || second > 60 {
+    return None;
+  }
+
+  // Days since the epoch for a proleptic Gregorian date (Howard Hinnant's algorithm)
+  let y = if month <= 2 { year - 1 } else { year };
+  let era = y / 400;
+  let yoe = y - era * 400;
+  let mp = (month + 9) % 12;
+  let doy = (153 * mp + 2) / 5 + day - 1;
+  let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+  let days = (era * 146_097 + doe).checked_sub(719_468)?;
+
+  Some(days * 86_400 + hour * 3_600 + minute * 60 + second)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_utc() {
+    assert_eq!(parse_utc("1970-01-01T00:00:00Z"), Some(0));
+    assert_eq!(parse_utc("2019-05-14T07:28:49Z"), Some(1_557_818_929));
+    assert_eq!(parse_utc("2024-02-29T12:00:00Z"), Some(1_709_208_000));
+    assert_eq!(parse_utc("2019-05-14 07:28:49"), None);
+    assert_eq!(parse_utc("2019-13-14T07:28:49Z"), None);
+  }
+
+  #[test]
+  fn test_history_filtering() {
+    let json = r#"[
+      {"ts":"2019-05-14T07:32:49Z","ms_played":240000,"master_metadata_track_name":"Believe",
+       "master_metadata_album_artist_name":"Cher","master_metadata_album_album_name":"Believe",
+       "reason_end":"fwdbtn","incognito_mode":false},
+      {"ts":"2019-05-14T07:28:49Z","ms_played":45000,"master_metadata_track_name":"Intro",
+       "master_metadata_album_artist_name":"The xx","master_metadata_album_album_name":"xx",
+       "reason_end":"trackdone"},
+      {"ts":"2019-05-14T07:40:00Z","ms_played":20000,"master_metadata_track_name":"Skipped",
+       "master_metadata_album_artist_name":"Cher","reason_end":"trackdone"},
+      {"ts":"2019-05-14T07:50:00Z","ms_played":90000,"master_metadata_track_name":"Halfway",
+       "master_metadata_album_artist_name":"Cher","reason_end":"fwdbtn"},
+      {"ts":"2019-05-14T08:50:00Z","ms_played":1800000,"master_metadata_track_name":null,
+       "episode_name":"A Podcast","reason_end":"trackdone"},
+      {"ts":"2019-05-14T09:50:00Z","ms_played":300000,"master_metadata_track_name":"Secret",
+       "master_metadata_album_artist_name":"Cher","incognito_mode":true}
+    ]"#;
+
+    let scrobbles = scrobbles_from_reader(json.as_bytes()).unwrap();
+    assert_eq!(scrobbles.len(), 2);
+
+    assert_eq!(scrobbles[0].artist, "The xx");
+    assert_eq!(scrobbles[0].timestamp, 1_557_818_929 - 45);
+    assert_eq!(scrobbles[0].duration, Some(Duration::from_secs(45)));
+
+    assert_eq!(scrobbles[1].track, "Believe");
+    assert_eq!(scrobbles[1].album.as_deref(), Some("Believe"));
+    assert_eq!(scrobbles[1].duration, None);
+    assert_eq!(scrobbles[1].player.as_deref(), Some("Spotify"));
+  }
+}
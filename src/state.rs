@@ -0,0 +1,49 @@
+//! Type-level authentication states for [`Client`](crate::Client)
+//!
+//! A plain `Client` is [`Dynamic`]: it can call every method, and the ones
+//! that act for a user fail at runtime with
+//! [`Error::Auth`](crate::Error::Auth) when no session key is set. A
+//! `Client<Unauthenticated>` leaves those methods out, so forgetting to log
+//! in is a compile error, and a `Client<Authenticated>` had a session key
+//! when it was created.
+//!
+//! Only a `Dynamic` client can log in or out in place, with
+//! [`set_session_key`](crate::Client::set_session_key) and
+//! [`clear_session`](crate::Client::clear_session), for every clone sharing
+//! its session. Changing state takes a copy of the session instead, so no
+//! other handle can empty an `Authenticated` client's key; end one with
+//! [`logout`](crate::Client::logout).
+
+mod sealed {
+  pub trait Sealed {}
+}
+
+/// Authentication state of a [`Client`](crate::Client); implemented by
+/// [`Dynamic`], [`Unauthenticated`] and [`Authenticated`] only
+pub trait ClientState: sealed::Sealed + Clone + Send + Sync + 'static {}
+
+/// States that can now-play, scrobble and love tracks
+pub trait Authorized: ClientState {}
+
+/// Session checked at call time (the default)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Dynamic;
+
+/// Not logged in: only authentication and read-only calls are available
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Unauthenticated;
+
+/// Logged in with a session key (or a token-mode client)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Authenticated;
+
+impl sealed::Sealed for Dynamic {}
+impl sealed::Sealed for Unauthenticated {}
+impl sealed::Sealed for Authenticated {}
+
+impl ClientState for Dynamic {}
+impl ClientState for Unauthenticated {}
+impl ClientState for Authenticated {}
+
+impl Authorized for Dynamic {}
+impl Authorized for Authenticated {}
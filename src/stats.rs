@@ -0,0 +1,363 @@
+//! Local listening statistics
+//!
+//! Aggregates plays from any source (the recent-tracks stream, an export,
+//! or scrobbles about to be submitted) over an arbitrary date range, for
+//! reports the API's fixed [`Period`](crate::Period)s can't express.
+//!
+//! # Example
+//!
+//! ```
+//! use last_fm_rs::stats::Stats;
+//! use last_fm_rs::Scrobble;
+//!
+//! let plays = vec![
+//!   Scrobble::new("Cher", "Believe", 1_700_000_000),
+//!   Scrobble::new("Cher", "Strong Enough", 1_700_086_400),
+//!   Scrobble::new("ABBA", "Waterloo", 1_700_090_000),
+//! ];
+//!
+//! let mut stats = Stats::between(1_699_999_999, 1_700_100_000);
+//! stats.extend(&plays);
+//!
+//! assert_eq!(stats.top_artists(1)[0].artist, "Cher");
+//! assert_eq!(stats.longest_streak().map(|streak| streak.days), Some(2));
+//! ```
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use crate::export::ExportRecord;
+use crate::recent::RecentTrack;
+use crate::scrobble::Scrobble;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// A single listen, regardless of where it came from
+pub trait Play {
+  fn artist(&self) -> &str;
+  fn track(&self) -> &str;
+  fn album(&self) -> Option<&str>;
+  /// Unix timestamp, `None` for plays still in progress
+  fn timestamp(&self) -> Option<u64>;
+}
+
+impl<P: Play + ?Sized> Play for &P {
+  fn artist(&self) -> &str {
+    (**self).artist()
+  }
+
+  fn track(&self) -> &str {
+    (**self).track()
+  }
+
+  fn album(&self) -> Option<&str> {
+    (**self).album()
+  }
+
+  fn timestamp(&self) -> Option<u64> {
+    (**self).timestamp()
+  }
+}
+
+impl Play for Scrobble {
+  fn artist(&self) -> &str {
+    &self.artist
+  }
+
+  fn track(&self) -> &str {
+    &self.track
+  }
+
+  fn album(&self) -> Option<&str> {
+    self.album.as_deref()
+  }
+
+  fn timestamp(&self) -> Option<u64> {
+    Some(self.timestamp)
+  }
+}
+
+impl Play for ExportRecord {
+  fn artist(&self) -> &str {
+    &self.artist
+  }
+
+  fn track(&self) -> &str {
+    &self.track
+  }
+
+  fn album(&self) -> Option<&str> {
+    Some(self.album.as_str()).filter(|album| !album.is_empty())
+  }
+
+  fn timestamp(&self) -> Option<u64> {
+    Some(self.timestamp)
+  }
+}
+
+impl Play for RecentTrack {
+  fn artist(&self) -> &str {
+    &self.artist.name
+  }
+
+  fn track(&self) -> &str {
+    &self.name
+  }
+
+  fn album(&self) -> Option<&str> {
+    self
+      .album
+      .as_ref()
+      .map(|album| album.title.as_str())
+      .filter(|album| !album.is_empty())
+  }
+
+  fn timestamp(&self) -> Option<u64> {
+    RecentTrack::timestamp(self)
+  }
+}
+
+/// Play count for an artist, album or track
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Ranked {
+  pub artist: String,
+  /// Album or track title; `None` when ranking artists
+  pub title: Option<String>,
+  pub plays: u64,
+}
+
+/// `Artist – Title (12 plays)`, or `Artist (12 plays)` for artists
+impl fmt::Display for Ranked {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.artist)?;
+    if let Some(title) = &self.title {
+      write!(f, " – {}", title)?;
+    }
+    let unit = if self.plays == 1 { "play" } else { "plays" };
+    write!(f, " ({} {})", self.plays, unit)
+  }
+}
+
+/// Run of consecutive days with at least one play
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Streak {
+  /// Unix timestamp of the first day's midnight, in the configured offset
+  pub start: i64,
+  pub days: u64,
+}
+
+/// Running aggregation over a set of plays
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+  from: Option<u64>,
+  to: Option<u64>,
+  utc_offset: i64,
+  total: u64,
+  artists: HashMap<String, u64>,
+  albums: HashMap<(String, String), u64>,
+  tracks: HashMap<(String, String), u64>,
+  hours: [u64; 24],
+  days: BTreeSet<i64>,
+}
+
+impl Stats {
+  /// Aggregate every play
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Aggregate plays with `from <= timestamp < to`
+  pub fn between(from: u64, to: u64) -> Self {
+    Self {
+      from: Some(from),
+      to: Some(to),
+      ..Self::default()
+    }
+  }
+
+  /// Bucket hours and days in a fixed offset from UTC (e.g. `3600` for UTC+1)
+  pub fn with_utc_offset(mut self, seconds: i32) -> Self {
+    self.utc_offset = seconds.into();
+    self
+  }
+
+  /// Count one play, ignoring it if it's in progress or outside the range
+  pub fn add(&mut self, play: &impl Play) {
+    let Some(timestamp) = play.timestamp() else {
+      return;
+    };
+    if self.from.is_some_and(|from| timestamp < from) || self.to.is_some_and(|to| timestamp >= to) {
+      return;
+    }
+
+    self.total += 1;
+    *self.artists.entry(play.artist().to_string()).or_default() += 1;
+    if let Some(album) = play.album() {
+      *self
+        .albums
+        .entry((play.artist().to_string(), album.to_string()))
+        .or_default() += 1;
+    }
+    *self
+      .tracks
+      .entry((play.artist().to_string(), play.track().to_string()))
+      .or_default() += 1;
+
+    let local = i64::try_from(timestamp).unwrap_or(i64::MAX) + self.utc_offset;
+    self.hours[local.rem_euclid(SECONDS_PER_DAY) as usize / 3_600] += 1;
+    self.days.insert(local.div_euclid(SECONDS_PER_DAY));
+  }
+
+  /// Number of plays counted
+  pub fn total(&self) -> u64 {
+    self.total
+  }
+
+  /// Most played artists, most plays first
+  pub fn top_artists(&self, limit: usize) -> Vec<Ranked> {
+    let ranked = self.artists.iter().map(|(artist, plays)| Ranked {
+      artist: artist.clone(),
+      title: None,
+      plays: *plays,
+    });
+    top(ranked, limit)
+  }
+
+  /// Most played albums, most plays first
+  pub fn top_albums(&self, limit: usize) -> Vec<Ranked> {
+    top(titled(&self.albums), limit)
+  }
+
+  /// Most played tracks, most plays first
+  pub fn top_tracks(&self, limit: usize) -> Vec<Ranked> {
+    top(titled(&self.tracks), limit)
+  }
+
+  /// Plays per hour of day, index 0 being midnight
+  pub fn hour_histogram(&self) -> [u64; 24] {
+    self.hours
+  }
+
+  /// Longest run of consecutive listening days (the earliest on ties)
+  pub fn longest_streak(&self) -> Option<Streak> {
+    let mut best: Option<(i64, u64)> = None;
+    let mut current: Option<(i64, u64)> = None;
+
+    for &day in &self.days {
+      current = match current {
+        Some((start, len)) if start + len as i64 == day => Some((start, len + 1)),
+        _ => Some((day, 1)),
+      };
+      if current.map(|(_, len)| len) > best.map(|(_, len)| len) {
+        best = current;
+      }
+    }
+
+    best.map(|(day, days)| Streak {
+      start: day * SECONDS_PER_DAY - self.utc_offset,
+      days,
+    })
+  }
+}
+
+impl<P: Play> Extend<P> for Stats {
+  fn extend<I: IntoIterator<Item = P>>(&mut self, plays: I) {
+    for play in plays {
+      self.add(&play);
+    }
+  }
+}
+
+fn titled(counts: &HashMap<(String, String), u64>) -> impl Iterator<Item = Ranked> + '_ {
+  counts.iter().map(|((artist, title), plays)| Ranked {
+    artist: artist.clone(),
+    title: Some(title.clone()),
+    plays: *plays,
+  })
+}
+
+/// Sort by plays, breaking ties by name so results are stable
+fn top(ranked: impl Iterator<Item = Ranked>, limit: usize) -> Vec<Ranked> {
+  let mut ranked: Vec<Ranked> = ranked.collect();
+  ranked.sort_unstable_by(|a, b| {
+    b.plays
+      .cmp(&a.plays)
+      .then_with(|| a.artist.cmp(&b.artist))
+      .then_with(|| a.title.cmp(&b.title))
+  });
+  ranked.truncate(limit);
+  ranked
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const DAY: u64 = 86_400;
+  const START: u64 = 1_700_006_400; // 2023-11-15 00:00:00 UTC
+
+  fn play(artist: &str, track: &str, timestamp: u64) -> Scrobble {
+    Scrobble::new(artist, track, timestamp).with_album(format!("{} album", artist))
+  }
+
+  #[test]
+  fn test_top_lists_and_range() {
+    let plays = vec![
+      play("Cher", "Believe", START),
+      play("Cher", "Believe", START + 60),
+      play("Cher", "Strong Enough", START + 120),
+      play("ABBA", "Waterloo", START + 180),
+      play("ABBA", "SOS", START + 10 * DAY),
+    ];
+
+    let mut stats = Stats::between(START, START + DAY);
+    stats.extend(&plays);
+
+    assert_eq!(stats.total(), 4);
+    let artists = stats.top_artists(10);
+    assert_eq!(artists[0].artist, "Cher");
+    assert_eq!(artists[0].plays, 3);
+    assert_eq!(artists[1].plays, 1);
+
+    let tracks = stats.top_tracks(1);
+    assert_eq!(tracks.len(), 1);
+    assert_eq!(tracks[0].to_string(), "Cher – Believe (2 plays)");
+    assert_eq!(artists[1].to_string(), "ABBA (1 play)");
+    assert_eq!(tracks[0].title.as_deref(), Some("Believe"));
+    assert_eq!(tracks[0].plays, 2);
+
+    assert_eq!(stats.top_albums(10)[1].title.as_deref(), Some("ABBA album"));
+  }
+
+  #[test]
+  fn test_streaks_and_hours() {
+    let days = [0, 1, 2, 5, 6, 7, 8, 20];
+    let plays: Vec<Scrobble> = days
+      .iter()
+      .map(|day| play("Cher", "Believe", START + day * DAY + 23 * 3_600))
+      .collect();
+
+    let mut stats = Stats::new();
+    stats.extend(&plays);
+    let streak = stats.longest_streak().unwrap();
+    assert_eq!(streak.days, 4);
+    assert_eq!(streak.start, (START + 5 * DAY) as i64);
+    assert_eq!(stats.hour_histogram()[23], 8);
+
+    // One hour ahead of UTC every play lands at midnight of the next day
+    let mut shifted = Stats::new().with_utc_offset(3_600);
+    shifted.extend(&plays);
+    assert_eq!(shifted.hour_histogram()[0], 8);
+    assert_eq!(
+      shifted.longest_streak().unwrap().start,
+      (START + 6 * DAY) as i64 - 3_600
+    );
+  }
+
+  #[test]
+  fn test_empty() {
+    let stats = Stats::new();
+    assert!(stats.top_artists(5).is_empty());
+    assert_eq!(stats.longest_streak(), None);
+  }
+}
@@ -0,0 +1,40 @@
+//! Global allocator for unit tests that count allocations
+//!
+//! Counts are kept per thread, so tests running in parallel don't skew each
+//! other; the overhead on every other test is one thread-local increment.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Counts allocations made on the current thread
+struct CountingAlloc;
+
+thread_local! {
+  static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+    System.alloc(layout)
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    System.dealloc(ptr, layout)
+  }
+
+  unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+    let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+    System.realloc(ptr, layout, new_size)
+  }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Run `f`, returning how many allocations it made on this thread
+pub(crate) fn allocations<R>(f: impl FnOnce() -> R) -> (usize, R) {
+  let before = ALLOCATIONS.with(Cell::get);
+  let result = f();
+  (ALLOCATIONS.with(Cell::get) - before, result)
+}
@@ -0,0 +1,129 @@
+//! HTTP server on a loopback port for tests that need a real connection
+//!
+//! The server runs on its own thread, so it works from plain `#[test]`s,
+//! under any Tokio runtime and with paused time.
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Canned response: a status line such as `200 OK` and a body
+pub(crate) struct Reply {
+  status: &'static str,
+  body: String,
+}
+
+impl Reply {
+  pub fn ok(body: impl Into<String>) -> Self {
+    Self::status("200 OK", body)
+  }
+
+  pub fn status(status: &'static str, body: impl Into<String>) -> Self {
+    Self {
+      status,
+      body: body.into(),
+    }
+  }
+
+  fn write_to(&self, socket: &mut TcpStream) {
+    let response = format!(
+      "HTTP/1.1 {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+      self.status,
+      self.body.len(),
+      self.body
+    );
+    let _ = socket.write_all(response.as_bytes());
+  }
+}
+
+pub(crate) struct TestServer {
+  addr: SocketAddr,
+  requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl TestServer {
+  /// Answer every request with `respond(request)`
+  pub fn start(respond: impl Fn(&str) -> Reply + Send + 'static) -> Self {
+    Self::spawn(move |listener, requests| {
+      for socket in listener.incoming() {
+        let Ok(mut socket) = socket else { continue };
+        let request = read_request(&mut socket);
+        let reply = respond(&request);
+        requests.lock().unwrap().push(request);
+        reply.write_to(&mut socket);
+      }
+    })
+  }
+
+  /// Answer one request per reply, in order, then refuse connections
+  pub fn replies(replies: Vec<Reply>) -> Self {
+    Self::spawn(move |listener, requests| {
+      for reply in replies {
+        let Ok((mut socket, _)) = listener.accept() else {
+          return;
+        };
+        let request = read_request(&mut socket);
+        requests.lock().unwrap().push(request);
+        reply.write_to(&mut socket);
+      }
+    })
+  }
+
+  /// Accept connections but never answer
+  pub fn silent() -> Self {
+    Self::spawn(|listener, _| {
+      let mut open = Vec::new();
+      for socket in listener.incoming().flatten() {
+        open.push(socket);
+      }
+    })
+  }
+
+  fn spawn(serve: impl FnOnce(TcpListener, Arc<Mutex<Vec<String>>>) + Send + 'static) -> Self {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let seen = requests.clone();
+    thread::spawn(move || serve(listener, seen));
+    Self { addr, requests }
+  }
+
+  /// `http://127.0.0.1:port` followed by `path`
+  pub fn url(&self, path: &str) -> String {
+    format!("http://{}{}", self.addr, path)
+  }
+
+  /// Requests answered so far, head and body, in order
+  pub fn requests(&self) -> Vec<String> {
+    self.requests.lock().unwrap().clone()
+  }
+}
+
+/// Read a request's head and, per its `content-length`, its body
+fn read_request(socket: &mut TcpStream) -> String {
+  let mut request = Vec::new();
+  let mut buf = [0; 4096];
+  while let Ok(len) = socket.read(&mut buf) {
+    if len == 0 {
+      break;
+    }
+    request.extend_from_slice(&buf[..len]);
+    let text = String::from_utf8_lossy(&request);
+    if let Some(head_end) = text.find("\r\n\r\n") {
+      let body_len = text[..head_end]
+        .lines()
+        .find_map(|line| {
+          let (name, value) = line.split_once(':')?;
+          name
+            .eq_ignore_ascii_case("content-length")
+            .then(|| value.trim().parse().ok())?
+        })
+        .unwrap_or(0);
+      if request.len() >= head_end + 4 + body_len {
+        break;
+      }
+    }
+  }
+  String::from_utf8_lossy(&request).into_owned()
+}
@@ -1,127 +1,478 @@
+use std::fmt;
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
+use url::Url;
+
+#[cfg(feature = "metadata")]
+use crate::client::Client;
+use crate::de;
+#[cfg(feature = "metadata")]
+use crate::error;
+use crate::mbid::Mbid;
+use crate::scrobble::{write_track, NowPlaying, Scrobble, Timestamp};
+#[cfg(feature = "metadata")]
+use crate::state::ClientState;
+
+/// Image size variants, ordered from smallest to largest
+///
+/// `Unknown` covers empty or unrecognized sizes and sorts below `Small`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum ImageSize {
+  #[default]
+  Unknown,
+  Small,
+  Medium,
+  Large,
+  ExtraLarge,
+  Mega,
+}
+
+impl ImageSize {
+  /// Wire representation used by Last.fm
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Unknown => "",
+      Self::Small => "small",
+      Self::Medium => "medium",
+      Self::Large => "large",
+      Self::ExtraLarge => "extralarge",
+      Self::Mega => "mega",
+    }
+  }
+}
+
+impl From<&str> for ImageSize {
+  fn from(size: &str) -> Self {
+    match size {
+      "small" => Self::Small,
+      "medium" => Self::Medium,
+      "large" => Self::Large,
+      "extralarge" => Self::ExtraLarge,
+      "mega" => Self::Mega,
+      _ => Self::Unknown,
+    }
+  }
+}
+
+impl Serialize for ImageSize {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(self.as_str())
+  }
+}
+
+impl<'de> Deserialize<'de> for ImageSize {
+  fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    Ok(Self::from(de::string(deserializer)?.as_str()))
+  }
+}
 
 /// Image with size variant
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
+#[cfg_attr(feature = "strict-schema", serde(deny_unknown_fields))]
 pub struct Image {
-  #[serde(rename = "#text")]
+  #[serde(rename = "#text", deserialize_with = "de::string", default)]
   pub url: String,
-  pub size: String,
+  #[serde(default)]
+  pub size: ImageSize,
+}
+
+impl Image {
+  /// File name Last.fm serves in place of missing artwork (a grey star)
+  const PLACEHOLDER: &'static str = "2a96cbd8b46e442fc41c2b86b821562f";
+
+  /// Whether this entry points at real artwork
+  ///
+  /// Last.fm returns empty URLs for sizes it doesn't have and a generic star
+  /// image for releases without artwork; neither is worth downloading.
+  pub fn is_available(&self) -> bool {
+    !self.url.trim().is_empty() && !self.url.contains(Self::PLACEHOLDER)
+  }
+}
+
+/// Downloaded image data
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cover {
+  /// `Content-Type` reported by the image host, e.g. `image/png`
+  pub content_type: String,
+  pub data: Vec<u8>,
 }
 
 /// Artist information (simplified)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct Artist {
+  #[serde(alias = "#text", deserialize_with = "de::string", default)]
   pub name: String,
-  #[serde(default)]
-  pub mbid: String,
-  pub url: String,
+  #[serde(deserialize_with = "de::mbid", default)]
+  pub mbid: Option<Mbid>,
+  /// Last.fm page, `None` when missing or malformed
+  #[serde(deserialize_with = "de::url", default)]
+  pub url: Option<Url>,
+  /// Fields this version doesn't model yet
+  #[serde(flatten)]
+  #[cfg_attr(feature = "strict-schema", serde(deserialize_with = "de::no_extra"))]
+  pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Album information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct Album {
+  #[serde(deserialize_with = "de::string", default)]
   pub artist: String,
+  #[serde(alias = "#text", deserialize_with = "de::string", default)]
   pub title: String,
-  #[serde(default)]
-  pub mbid: String,
-  pub url: String,
-  #[serde(default)]
+  #[serde(deserialize_with = "de::mbid", default)]
+  pub mbid: Option<Mbid>,
+  /// Last.fm page, `None` when missing or malformed
+  #[serde(deserialize_with = "de::url", default)]
+  pub url: Option<Url>,
+  /// Available sizes; entries Last.fm sends without a URL are dropped
+  #[serde(deserialize_with = "de::images", default)]
   pub image: Vec<Image>,
-  #[serde(rename = "@attr", default)]
+  #[serde(rename = "@attr", deserialize_with = "de::option", default)]
   pub attr: Option<AlbumAttr>,
+  /// Fields this version doesn't model yet
+  #[serde(flatten)]
+  #[cfg_attr(feature = "strict-schema", serde(deserialize_with = "de::no_extra"))]
+  pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Album {
+  /// Smallest image that is at least `size`, skipping unavailable entries
+  pub fn image_at_least(&self, size: ImageSize) -> Option<&Image> {
+    self
+      .image
+      .iter()
+      .filter(|image| image.size >= size && image.is_available())
+      .min_by_key(|image| image.size)
+  }
+
+  /// Largest available image, skipping unavailable entries
+  pub fn largest_image(&self) -> Option<&Image> {
+    self
+      .image
+      .iter()
+      .filter(|image| image.is_available())
+      .max_by_key(|image| image.size)
+  }
+
+  /// Image closest to `size`: the smallest one at least that big, otherwise
+  /// the largest one there is
+  pub fn best_image(&self, size: ImageSize) -> Option<&Image> {
+    self.image_at_least(size).or_else(|| self.largest_image())
+  }
+
+  /// Download the cover closest to `size` with the client's HTTP connection
+  ///
+  /// Returns `None` when the album has no usable artwork.
+  ///
+  /// # Example
+  ///
+  /// ```no_run
+  /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+  /// use last_fm_rs::{Client, ImageSize};
+  ///
+  /// let client = Client::new("api_key", "secret");
+  /// let track = client.track_get_info("Cher", "Believe", None).await?;
+  ///
+  /// if let Some(album) = &track.album {
+  ///   if let Some(cover) = album.fetch_cover(&client, ImageSize::Large).await? {
+  ///     std::fs::write("cover", &cover.data)?;
+  ///   }
+  /// }
+  /// # Ok(())
+  /// # }
+  /// ```
+  #[cfg(feature = "metadata")]
+  pub async fn fetch_cover(
+    &self,
+    client: &Client<impl ClientState>,
+    size: ImageSize,
+  ) -> error::Result<Option<Cover>> {
+    match self.best_image(size) {
+      Some(image) => client.fetch_image(image).await.map(Some),
+      None => Ok(None),
+    }
+  }
+}
+
+impl fmt::Display for Artist {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.name)
+  }
+}
+
+/// `Artist – Album`, or just the title when the artist is unknown
+impl fmt::Display for Album {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if self.artist.is_empty() {
+      f.write_str(&self.title)
+    } else {
+      write!(f, "{} – {}", self.artist, self.title)
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
+#[cfg_attr(feature = "strict-schema", serde(deny_unknown_fields))]
 pub struct AlbumAttr {
+  #[serde(deserialize_with = "de::string", default)]
   pub position: String,
 }
 
 /// Tag information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct Tag {
+  #[serde(deserialize_with = "de::string", default)]
   pub name: String,
-  pub url: String,
+  /// Last.fm page, `None` when missing or malformed
+  #[serde(deserialize_with = "de::url", default)]
+  pub url: Option<Url>,
+  /// Fields this version doesn't model yet
+  #[serde(flatten)]
+  #[cfg_attr(feature = "strict-schema", serde(deserialize_with = "de::no_extra"))]
+  pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Top tags wrapper
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
+#[cfg_attr(feature = "strict-schema", serde(deny_unknown_fields))]
 pub struct TopTags {
-  #[serde(default)]
+  #[serde(deserialize_with = "de::one_or_many", default)]
   pub tag: Vec<Tag>,
 }
 
 /// Wiki content
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct Wiki {
+  #[serde(deserialize_with = "de::string", default)]
   pub published: String,
+  #[serde(deserialize_with = "de::string", default)]
   pub summary: String,
+  #[serde(deserialize_with = "de::string", default)]
   pub content: String,
+  /// Fields this version doesn't model yet
+  #[serde(flatten)]
+  #[cfg_attr(feature = "strict-schema", serde(deserialize_with = "de::no_extra"))]
+  pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[cfg(feature = "chrono")]
+impl Wiki {
+  /// Publication date parsed from Last.fm's `"01 Jan 2015, 12:00"` format (UTC)
+  pub fn published_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::NaiveDateTime::parse_from_str(self.published.trim(), "%d %b %Y, %H:%M")
+      .ok()
+      .map(|naive| naive.and_utc())
+  }
 }
 
 /// Streamable information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct Streamable {
-  #[serde(rename = "#text")]
+  #[serde(rename = "#text", deserialize_with = "de::string", default)]
   pub text: String,
+  #[serde(deserialize_with = "de::string", default)]
   pub fulltrack: String,
+  /// Fields this version doesn't model yet
+  #[serde(flatten)]
+  #[cfg_attr(feature = "strict-schema", serde(deserialize_with = "de::no_extra"))]
+  pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Track information from track.getInfo
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct TrackInfo {
+  #[serde(deserialize_with = "de::string", default)]
   pub name: String,
-  #[serde(default)]
-  pub mbid: String,
-  pub url: String,
-  #[serde(deserialize_with = "deserialize_optional_string_as_u64", default)]
-  pub duration: Option<u64>,
-  #[serde(default)]
+  #[serde(deserialize_with = "de::mbid", default)]
+  pub mbid: Option<Mbid>,
+  /// Last.fm page, `None` when missing or malformed
+  #[serde(deserialize_with = "de::url", default)]
+  pub url: Option<Url>,
+  /// Track length; Last.fm reports `0` when unknown, which maps to `None`
+  #[serde(with = "de::duration_millis", default)]
+  pub duration: Option<Duration>,
+  #[serde(deserialize_with = "de::option", default)]
   pub streamable: Option<Streamable>,
-  #[serde(deserialize_with = "deserialize_string_as_u64", default)]
+  #[serde(deserialize_with = "de::number", default)]
   pub listeners: u64,
-  #[serde(deserialize_with = "deserialize_string_as_u64", default)]
+  #[serde(deserialize_with = "de::number", default)]
   pub playcount: u64,
   pub artist: Artist,
-  #[serde(default)]
+  #[serde(deserialize_with = "de::option", default)]
   pub album: Option<Album>,
-  #[serde(deserialize_with = "deserialize_optional_string_as_u64", default)]
+  #[serde(deserialize_with = "de::option_number", default)]
   pub userplaycount: Option<u64>,
-  #[serde(deserialize_with = "deserialize_optional_string_as_u64", default)]
+  #[serde(deserialize_with = "de::option_number", default)]
   pub userloved: Option<u64>,
-  #[serde(default)]
+  #[serde(deserialize_with = "de::option", default)]
   pub toptags: Option<TopTags>,
-  #[serde(default)]
+  #[serde(deserialize_with = "de::option", default)]
   pub wiki: Option<Wiki>,
+  /// Fields this version doesn't model yet
+  #[serde(flatten)]
+  #[cfg_attr(feature = "strict-schema", serde(deserialize_with = "de::no_extra"))]
+  pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl TrackInfo {
+  /// Now-playing notification for this track, carrying over the album,
+  /// duration (in whole seconds) and MBID
+  pub fn to_now_playing(&self) -> NowPlaying {
+    let mut now_playing = NowPlaying::new(&self.artist.name, &self.name);
+    now_playing.album = self
+      .album
+      .as_ref()
+      .map(|album| album.title.clone())
+      .filter(|title| !title.is_empty());
+    now_playing.duration = self
+      .duration
+      .map(|duration| Duration::from_secs(duration.as_secs()));
+    now_playing.mbid = self.mbid.clone();
+    now_playing
+  }
+
+  /// Scrobble of this track started at `timestamp`, carrying over the same
+  /// fields as [`TrackInfo::to_now_playing`]
+  pub fn to_scrobble(&self, timestamp: impl Into<Timestamp>) -> Scrobble {
+    self.to_now_playing().into_scrobble(timestamp)
+  }
+}
+
+/// `Artist – Track (Album)`
+impl fmt::Display for TrackInfo {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let album = self.album.as_ref().map(|album| album.title.as_str());
+    write_track(f, &self.artist.name, &self.name, album)
+  }
 }
 
 /// Response wrapper for track.getInfo
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
+#[cfg_attr(feature = "strict-schema", serde(deny_unknown_fields))]
 pub struct TrackInfoResponse {
   pub track: TrackInfo,
 }
 
-// Custom deserializers for Last.fm's string-encoded numbers
-fn deserialize_string_as_u64<'de, D>(deserializer: D) -> Result<u64, D::Error>
-where
-  D: serde::Deserializer<'de>,
-{
-  let s: String = Deserialize::deserialize(deserializer)?;
-  s.parse().unwrap_or(0).pipe(Ok)
-}
+#[cfg(test)]
+mod tests {
+  use super::*;
 
-fn deserialize_optional_string_as_u64<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
-where
-  D: serde::Deserializer<'de>,
-{
-  let s: Option<String> = Deserialize::deserialize(deserializer)?;
-  Ok(s.and_then(|s| s.parse().ok()))
-}
+  #[test]
+  fn test_image_selection() {
+    let album: Album = serde_json::from_str(
+      r##"{"artist":"A","title":"T","image":[
+        {"#text":"https://example.com/s.png","size":"small"},
+        {"#text":"","size":"large"},
+        {"#text":"https://example.com/xl.png","size":"extralarge"},
+        {"#text":"https://example.com/m.png","size":"mega"},
+        {"#text":"https://example.com/x.png","size":"huge"}
+      ]}"##,
+    )
+    .unwrap();
 
-// Helper trait for pipe syntax
-trait Pipe: Sized {
-  fn pipe<R>(self, f: impl FnOnce(Self) -> R) -> R {
-    f(self)
+    // The empty "large" entry is dropped
+    assert_eq!(album.image.len(), 4);
+    assert_eq!(album.image[3].size, ImageSize::Unknown);
+    let image = album.image_at_least(ImageSize::Large).unwrap();
+    assert_eq!(image.size, ImageSize::ExtraLarge);
+    assert_eq!(album.largest_image().unwrap().size, ImageSize::Mega);
+    assert!(album.image_at_least(ImageSize::Mega).is_some());
+  }
+
+  #[test]
+  fn test_best_image_skips_placeholders() {
+    let album: Album = serde_json::from_str(
+      r##"{"artist":"A","title":"T","image":[
+        {"#text":"https://example.com/s.png","size":"small"},
+        {"#text":" ","size":"medium"},
+        {"#text":"https://lastfm.freetls.fastly.net/i/u/300x300/2a96cbd8b46e442fc41c2b86b821562f.png","size":"extralarge"}
+      ]}"##,
+    )
+    .unwrap();
+
+    assert_eq!(
+      album.best_image(ImageSize::Large).unwrap().size,
+      ImageSize::Small
+    );
+    assert_eq!(album.largest_image().unwrap().size, ImageSize::Small);
+
+    let empty: Album = serde_json::from_str(r#"{"image":""}"#).unwrap();
+    assert!(empty.best_image(ImageSize::Small).is_none());
+  }
+
+  #[cfg(not(feature = "strict-schema"))]
+  #[test]
+  fn test_unknown_fields_are_kept() {
+    let artist: Artist = serde_json::from_str(
+      r##"{"#text":"Cher","mbid":"","url":"https://www.last.fm/music/Cher","ontour":"1"}"##,
+    )
+    .unwrap();
+    assert_eq!(artist.name, "Cher");
+    assert_eq!(artist.extra.len(), 1);
+    assert_eq!(artist.extra["ontour"], "1");
+
+    let json = serde_json::to_value(&artist).unwrap();
+    assert_eq!(json["ontour"], "1");
+
+    let wiki: Wiki = serde_json::from_str(
+      r#"{"summary":"Song","links":{"link":{"rel":"original","href":"https://www.last.fm"}}}"#,
+    )
+    .unwrap();
+    assert_eq!(wiki.summary, "Song");
+    assert_eq!(wiki.extra["links"]["link"]["rel"], "original");
   }
-}
 
-impl<T> Pipe for T {}
+  #[test]
+  fn test_urls_are_parsed_leniently() {
+    let artist: Artist =
+      serde_json::from_str(r#"{"name":"Cher","url":"https://www.last.fm/music/Cher"}"#).unwrap();
+    assert_eq!(artist.url.unwrap().path(), "/music/Cher");
+
+    for url in [r#""""#, r#""not a url""#, "null", "42"] {
+      let artist: Artist =
+        serde_json::from_str(&format!(r#"{{"name":"Cher","url":{}}}"#, url)).unwrap();
+      assert_eq!(artist.url, None, "{}", url);
+    }
+  }
+
+  #[cfg(feature = "chrono")]
+  #[test]
+  fn test_wiki_published_at() {
+    use chrono::{TimeZone, Utc};
+
+    let wiki: Wiki = serde_json::from_str(r#"{"published":"01 Mar 2024, 12:30"}"#).unwrap();
+    let published = Utc.with_ymd_and_hms(2024, 3, 1, 12, 30, 0).unwrap();
+    assert_eq!(wiki.published_at(), Some(published));
+  }
+
+  #[test]
+  fn test_conversions() {
+    let track: TrackInfo = serde_json::from_str(
+      r#"{"name":"Believe","mbid":"b1a9c0e9-d987-4042-ae91-78d6a3267d69","duration":"239500",
+        "artist":{"name":"Cher"},"album":{"artist":"Cher","title":"Believe"}}"#,
+    )
+    .unwrap();
+
+    let scrobble = track.to_scrobble(1_700_000_000);
+    assert_eq!(scrobble.to_string(), "Cher – Believe (Believe)");
+    assert_eq!(scrobble.timestamp, 1_700_000_000);
+    assert_eq!(scrobble.duration, Some(Duration::from_secs(239)));
+    assert_eq!(scrobble.mbid, track.mbid);
+
+    let now_playing = track.to_now_playing();
+    assert_eq!(now_playing.album.as_deref(), Some("Believe"));
+    assert_eq!(now_playing.duration, scrobble.duration);
+  }
+}
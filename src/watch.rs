@@ -0,0 +1,309 @@
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
+
+use futures::{Stream, TryStreamExt};
+
+use crate::client::Client;
+use crate::error::{Error, Result};
+use crate::recent::{RecentTrack, RecentTracksOptions};
+
+/// Cap on the rate-limit backoff, as a multiple of the poll interval
+const MAX_BACKOFF: u32 = 16;
+
+type Key = (String, String, Option<String>);
+
+/// Identity of a track, ignoring images and other decoration
+fn key(track: &RecentTrack) -> Key {
+  (
+    track.artist.name.clone(),
+    track.name.clone(),
+    track.album.as_ref().map(|album| album.title.clone()),
+  )
+}
+
+/// Wait before the poll after `error`: doubled while rate limited, the
+/// plain interval otherwise
+fn backoff(error: &Error, delay: Option<Duration>, interval: Duration) -> Duration {
+  match (error, delay) {
+    (Error::RateLimited { .. }, Some(delay)) => (delay * 2).min(interval * MAX_BACKOFF),
+    _ => interval,
+  }
+}
+
+/// Polling state behind [`Client::watch_now_playing`]
+struct NowPlayingWatcher {
+  client: Client,
+  user: String,
+  interval: Duration,
+  delay: Option<Duration>,
+  current: Option<Key>,
+}
+
+pub(crate) fn now_playing(
+  client: Client,
+  user: String,
+  interval: Duration,
+) -> impl Stream<Item = Result<Option<RecentTrack>>> + Send + 'static {
+  let watcher = NowPlayingWatcher {
+    client,
+    user,
+    interval,
+    delay: None,
+    current: None,
+  };
+
+  futures::stream::unfold(watcher, |mut watcher| async move {
+    loop {
+      if let Some(delay) = watcher.delay {
+        tokio::time::sleep(delay).await;
+      }
+      match watcher.client.now_playing(&watcher.user).await {
+        Ok(track) => {
+          watcher.delay = Some(watcher.interval);
+          let key = track.as_ref().map(key);
+          if key != watcher.current {
+            watcher.current = key;
+            return Some((Ok(track), watcher));
+          }
+        }
+        Err(error) => {
+          watcher.delay = Some(backoff(&error, watcher.delay, watcher.interval));
+          return Some((Err(error), watcher));
+        }
+      }
+    }
+  })
+}
+
+/// Newest scrobble time seen so far, and the scrobbles seen at exactly that
+/// time (Last.fm's `from` filter is inclusive)
+///
+/// Scrobbles at the mark are told apart by [`key`] alone: once one has been
+/// seen, a later poll takes another scrobble of the same track and album in
+/// that same second for it and skips it. Last.fm returns nothing else that
+/// would tell the two apart.
+struct HighWater {
+  timestamp: u64,
+  seen: HashSet<Key>,
+}
+
+impl HighWater {
+  /// Keep the scrobbles in `tracks` (newest first) not seen yet, and move
+  /// the mark past them
+  fn advance(&mut self, tracks: Vec<RecentTrack>) -> Vec<RecentTrack> {
+    let fresh: Vec<RecentTrack> = tracks
+      .into_iter()
+      .filter(|track| match track.timestamp() {
+        Some(timestamp) if timestamp == self.timestamp => !self.seen.contains(&key(track)),
+        Some(timestamp) => timestamp > self.timestamp,
+        None => false,
+      })
+      .collect();
+    for track in &fresh {
+      let timestamp = track.timestamp().unwrap_or_default();
+      if timestamp > self.timestamp {
+        self.timestamp = timestamp;
+        self.seen.clear();
+      }
+      if timestamp == self.timestamp {
+        self.seen.insert(key(track));
+      }
+    }
+    fresh
+  }
+}
+
+/// Polling state behind [`Client::watch_scrobbles`]
+struct ScrobbleWatcher {
+  client: Client,
+  user: String,
+  interval: Duration,
+  delay: Option<Duration>,
+  /// `None` until the first poll has found where the history ends
+  high_water: Option<HighWater>,
+  buffer: VecDeque<RecentTrack>,
+}
+
+impl ScrobbleWatcher {
+  /// Every scrobble at or after the high-water mark, newest first
+  async fn poll(&self, from: u64) -> Result<Vec<RecentTrack>> {
+    let options = RecentTracksOptions::new().with_from(from);
+    self
+      .client
+      .recent_tracks_stream(self.user.clone(), options)
+      .try_collect()
+      .await
+  }
+}
+
+pub(crate) fn scrobbles(
+  client: Client,
+  user: String,
+  interval: Duration,
+) -> impl Stream<Item = Result<RecentTrack>> + Send + 'static {
+  let watcher = ScrobbleWatcher {
+    client,
+    user,
+    interval,
+    delay: None,
+    high_water: None,
+    buffer: VecDeque::new(),
+  };
+
+  futures::stream::unfold(watcher, |mut watcher| async move {
+    loop {
+      if let Some(track) = watcher.buffer.pop_front() {
+        return Some((Ok(track), watcher));
+      }
+      if let Some(delay) = watcher.delay {
+        tokio::time::sleep(delay).await;
+      }
+      let result = match &watcher.high_water {
+        Some(high_water) => watcher.poll(high_water.timestamp).await,
+        None => {
+          let options = RecentTracksOptions::new().with_limit(50);
+          watcher
+            .client
+            .recent_tracks(&watcher.user, &options, 1)
+            .await
+            .map(|page| page.items)
+        }
+      };
+      match result {
+        Ok(tracks) => {
+          watcher.delay = Some(watcher.interval);
+          match &mut watcher.high_water {
+            Some(high_water) => watcher
+              .buffer
+              .extend(high_water.advance(tracks).into_iter().rev()),
+            None => {
+              let mut high_water = HighWater {
+                timestamp: 0,
+                seen: HashSet::new(),
+              };
+              high_water.advance(tracks);
+              watcher.high_water = Some(high_water);
+            }
+          }
+        }
+        Err(error) => {
+          watcher.delay = Some(backoff(&error, watcher.delay, watcher.interval));
+          return Some((Err(error), watcher));
+        }
+      }
+    }
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use futures::StreamExt;
+
+  use super::*;
+  use crate::test_server::{Reply, TestServer};
+
+  fn playing(track: &str) -> String {
+    format!(
+      r##"{{"recenttracks":{{"track":[{{"artist":{{"#text":"Cher"}},"name":"{}","@attr":{{"nowplaying":"true"}}}}]}}}}"##,
+      track
+    )
+  }
+
+  fn scrobbled(tracks: &[(&str, u64)]) -> String {
+    let tracks: Vec<String> = tracks
+      .iter()
+      .map(|(track, uts)| {
+        format!(
+          r##"{{"artist":{{"#text":"Cher"}},"name":"{}","date":{{"uts":"{}"}}}}"##,
+          track, uts
+        )
+      })
+      .collect();
+    format!(r#"{{"recenttracks":{{"track":[{}]}}}}"#, tracks.join(","))
+  }
+
+  #[test]
+  fn test_same_second_scrobbles_are_keyed_by_track() {
+    let page = |tracks: &[(&str, u64)]| {
+      let response: crate::recent::RecentTracksResponse =
+        serde_json::from_str(&scrobbled(tracks)).unwrap();
+      crate::search::SearchPage::from(response).items
+    };
+    let mut high_water = HighWater {
+      timestamp: 0,
+      seen: HashSet::new(),
+    };
+    high_water.advance(page(&[("Believe", 100)]));
+
+    let fresh = high_water.advance(page(&[
+      ("Strong Enough", 100),
+      ("Believe", 100),
+      ("Believe", 100),
+    ]));
+    let names: Vec<&str> = fresh.iter().map(|track| track.name.as_str()).collect();
+    assert_eq!(names, ["Strong Enough"]);
+    assert!(high_water.advance(page(&[("Believe", 100)])).is_empty());
+  }
+
+  #[tokio::test]
+  async fn test_new_scrobbles_are_yielded_once() {
+    let server = TestServer::replies(vec![
+      Reply::ok(scrobbled(&[("Believe", 100), ("Old", 50)])),
+      Reply::ok(scrobbled(&[("Believe", 100)])),
+      Reply::ok(scrobbled(&[
+        ("Song for the Lonely", 200),
+        ("Strong Enough", 200),
+        ("Believe", 100),
+      ])),
+      Reply::ok(scrobbled(&[
+        ("Love Hurts", 300),
+        ("Song for the Lonely", 200),
+        ("Strong Enough", 200),
+      ])),
+    ]);
+    let client = Client::builder("key", "secret")
+      .api_base(server.url("/2.0/"))
+      .build()
+      .unwrap();
+
+    let names: Vec<String> = scrobbles(client, "rj".to_string(), Duration::from_millis(1))
+      .take(3)
+      .map(|track| track.unwrap().name)
+      .collect()
+      .await;
+    assert_eq!(
+      names,
+      ["Strong Enough", "Song for the Lonely", "Love Hurts"]
+    );
+  }
+
+  #[tokio::test]
+  async fn test_now_playing_changes() {
+    let stopped = r##"{"recenttracks":{"track":[{"artist":{"#text":"Cher"},"name":"Believe","date":{"uts":"1700000000"}}]}}"##;
+    let server = TestServer::replies(vec![
+      Reply::ok(playing("Believe")),
+      Reply::ok(playing("Believe")),
+      Reply::ok(stopped),
+      Reply::ok(playing("Strong Enough")),
+    ]);
+    let client = Client::builder("key", "secret")
+      .api_base(server.url("/2.0/"))
+      .build()
+      .unwrap();
+
+    let changes: Vec<Option<String>> =
+      now_playing(client, "rj".to_string(), Duration::from_millis(1))
+        .take(3)
+        .map(|change| change.unwrap().map(|track| track.name))
+        .collect()
+        .await;
+    assert_eq!(
+      changes,
+      [
+        Some("Believe".to_string()),
+        None,
+        Some("Strong Enough".to_string())
+      ]
+    );
+  }
+}
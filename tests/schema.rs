@@ -0,0 +1,202 @@
+//! Parses sample responses for each endpoint and checks that every field
+//! lands in a modelled field
+//!
+//! The fixtures are synthetic: they were written by hand from the API
+//! documentation and the shapes the client has been seen to handle, not
+//! recorded from Last.fm. Replace one with a recorded response to check the
+//! models against the live API.
+//!
+//! Run with `--features strict-schema` to also check that an unknown field
+//! anywhere in a response, nested objects included, is rejected.
+
+use last_fm_rs::{LovedTrack, RecentTrack, ScrobbleResponse, TrackInfoResponse, TrackMatch};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+/// Deserialize a fixture, or the part of it at `path`
+fn fixture<T: DeserializeOwned>(json: &str, path: &[&str]) -> T {
+  let mut value: Value = serde_json::from_str(json).expect("valid JSON");
+  for key in path {
+    value = value[*key].take();
+  }
+  serde_json::from_value(value).expect("fixture matches the models")
+}
+
+/// List that Last.fm may collapse into a single object
+fn items<T: DeserializeOwned>(json: &str, path: &[&str]) -> Vec<T> {
+  match fixture::<Value>(json, path) {
+    Value::Array(items) => items
+      .into_iter()
+      .map(|item| serde_json::from_value(item).expect("fixture matches the models"))
+      .collect(),
+    item => vec![serde_json::from_value(item).expect("fixture matches the models")],
+  }
+}
+
+fn assert_modelled(what: &str, extra: &Map<String, Value>) {
+  assert!(
+    extra.is_empty(),
+    "unmodelled fields in {}: {:?}",
+    what,
+    extra.keys()
+  );
+}
+
+#[test]
+fn test_track_get_info() {
+  let track = fixture::<TrackInfoResponse>(include_str!("fixtures/track_get_info.json"), &[]).track;
+  assert_modelled("track", &track.extra);
+  assert_modelled("track artist", &track.artist.extra);
+
+  let album = track.album.expect("album");
+  assert_modelled("track album", &album.extra);
+  assert_eq!(album.image.len(), 4);
+  let tags = track.toptags.expect("tags").tag;
+  assert_eq!(tags.len(), 2);
+  for tag in &tags {
+    assert_modelled("track tag", &tag.extra);
+  }
+  assert_modelled("track wiki", &track.wiki.expect("wiki").extra);
+  assert_modelled(
+    "track streamable",
+    &track.streamable.expect("streamable").extra,
+  );
+}
+
+#[test]
+fn test_track_search() {
+  let matches: Vec<TrackMatch> = items(
+    include_str!("fixtures/track_search.json"),
+    &["results", "trackmatches", "track"],
+  );
+  assert_eq!(matches.len(), 2);
+  for track in &matches {
+    assert_modelled("track match", &track.extra);
+  }
+  assert_eq!(matches[0].image.len(), 1);
+  assert_eq!(matches[1].mbid, None);
+}
+
+#[test]
+fn test_recent_tracks() {
+  let tracks: Vec<RecentTrack> = items(
+    include_str!("fixtures/recent_tracks.json"),
+    &["recenttracks", "track"],
+  );
+  assert_eq!(tracks.len(), 2);
+  for track in &tracks {
+    assert_modelled("recent track", &track.extra);
+    assert_modelled("recent track artist", &track.artist.extra);
+    assert_modelled(
+      "recent track album",
+      &track.album.as_ref().expect("album").extra,
+    );
+  }
+  assert!(tracks[0].is_now_playing());
+  assert_eq!(tracks[1].timestamp(), Some(1_700_000_000));
+}
+
+#[test]
+fn test_loved_tracks() {
+  let tracks: Vec<LovedTrack> = items(
+    include_str!("fixtures/loved_tracks.json"),
+    &["lovedtracks", "track"],
+  );
+  assert_eq!(tracks.len(), 1);
+  assert_modelled("loved track", &tracks[0].extra);
+  assert_modelled("loved track artist", &tracks[0].artist.extra);
+}
+
+#[test]
+fn test_scrobble() {
+  let response: ScrobbleResponse = fixture(include_str!("fixtures/scrobble.json"), &[]);
+  let result = &response.scrobbles.results[0];
+  assert_modelled("scrobble result", &result.extra);
+  for corrected in [
+    &result.artist,
+    &result.track,
+    &result.album,
+    &result.album_artist,
+  ] {
+    assert_modelled("scrobble correction", &corrected.extra);
+  }
+  assert_modelled("scrobble ignored message", &result.ignored_message.extra);
+  let outcomes = response.outcomes();
+  assert_eq!(outcomes.len(), 1);
+  assert!(outcomes[0].accepted);
+  assert_eq!(outcomes[0].corrections.track.as_deref(), Some("Believe"));
+}
+
+/// Check that `T` parses the fixture part at `base` (a JSON pointer), but
+/// not once an unknown field is added to any of the objects at `nested`
+/// (pointers relative to `base`)
+#[cfg(feature = "strict-schema")]
+fn assert_unknown_fields_rejected<T: DeserializeOwned>(json: &str, base: &str, nested: &[&str]) {
+  let mut root: Value = serde_json::from_str(json).expect("valid JSON");
+  let value = root.pointer_mut(base).expect("base exists").take();
+  serde_json::from_value::<T>(value.clone()).expect("fixture matches the models");
+
+  for pointer in nested {
+    let mut value = value.clone();
+    value
+      .pointer_mut(pointer)
+      .and_then(Value::as_object_mut)
+      .unwrap_or_else(|| panic!("no object at {:?}", pointer))
+      .insert("unexpected".to_string(), Value::Bool(true));
+    assert!(
+      serde_json::from_value::<T>(value).is_err(),
+      "unknown field at {:?} was accepted",
+      pointer
+    );
+  }
+}
+
+#[cfg(feature = "strict-schema")]
+#[test]
+fn test_unknown_fields_are_rejected() {
+  assert_unknown_fields_rejected::<TrackInfoResponse>(
+    include_str!("fixtures/track_get_info.json"),
+    "",
+    &[
+      "",
+      "/track",
+      "/track/streamable",
+      "/track/artist",
+      "/track/album",
+      "/track/album/image/0",
+      "/track/album/@attr",
+      "/track/toptags",
+      "/track/toptags/tag/1",
+      "/track/wiki",
+    ],
+  );
+  let recent = include_str!("fixtures/recent_tracks.json");
+  assert_unknown_fields_rejected::<RecentTrack>(recent, "/recenttracks/track/0", &["/@attr"]);
+  assert_unknown_fields_rejected::<RecentTrack>(
+    recent,
+    "/recenttracks/track/1",
+    &["", "/artist", "/album", "/image/0", "/date"],
+  );
+  assert_unknown_fields_rejected::<LovedTrack>(
+    include_str!("fixtures/loved_tracks.json"),
+    "/lovedtracks/track",
+    &["", "/artist", "/image/0", "/date"],
+  );
+  assert_unknown_fields_rejected::<TrackMatch>(
+    include_str!("fixtures/track_search.json"),
+    "/results/trackmatches/track/0",
+    &["", "/image/0"],
+  );
+  assert_unknown_fields_rejected::<ScrobbleResponse>(
+    include_str!("fixtures/scrobble.json"),
+    "",
+    &[
+      "",
+      "/scrobbles",
+      "/scrobbles/@attr",
+      "/scrobbles/scrobble",
+      "/scrobbles/scrobble/track",
+      "/scrobbles/scrobble/ignoredMessage",
+    ],
+  );
+}